@@ -0,0 +1,21 @@
+// cargo run --example auth username password [server_address]
+
+use shiplift::{Docker, RegistryAuth};
+use std::env;
+use tokio::prelude::Future;
+
+fn main() {
+    let docker = Docker::new();
+    let username = env::args().nth(1).expect("You need to specify an username");
+    let password = env::args().nth(2).expect("You need to specify a password");
+    let mut auth = RegistryAuth::builder();
+    auth.username(username).password(password);
+    if let Some(server_address) = env::args().nth(3) {
+        auth.server_address(server_address);
+    }
+    let fut = docker
+        .auth(&auth.build())
+        .map(|status| println!("{:?}", status))
+        .map_err(|e| eprintln!("Error: {}", e));
+    tokio::run(fut);
+}
@@ -1,6 +1,6 @@
-use shiplift::{tty::StreamType, Docker, ExecContainerOptions};
+use shiplift::{tty, Docker, ExecContainerOptions};
 use std::env;
-use tokio::prelude::{Future, Stream};
+use tokio::prelude::Future;
 
 fn main() {
     let docker = Docker::new();
@@ -18,19 +18,8 @@ fn main() {
         .attach_stdout(true)
         .attach_stderr(true)
         .build();
-    let fut = docker
-        .containers()
-        .get(&id)
-        .exec(&options)
-        .for_each(|chunk| {
-            match chunk.stream_type {
-                StreamType::StdOut => println!("Stdout: {}", chunk.as_string_lossy()),
-                StreamType::StdErr => eprintln!("Stderr: {}", chunk.as_string_lossy()),
-                StreamType::StdIn => unreachable!(),
-            }
-            Ok(())
-        })
-        .map_err(|e| eprintln!("Error: {}", e));
+    let output = docker.containers().get(&id).exec(&options);
+    let fut = tty::copy_to_console(output).map_err(|e| eprintln!("Error: {}", e));
 
     tokio::run(fut);
 }
@@ -2,7 +2,7 @@ use futures::Future;
 use shiplift::Docker;
 
 fn main() {
-    let docker = Docker::host("http://yourhost".parse().unwrap());
+    let docker = Docker::host("http://yourhost").unwrap();
 
     let fut = docker
         .ping()
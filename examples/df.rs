@@ -0,0 +1,12 @@
+use shiplift::Docker;
+use tokio::prelude::Future;
+
+fn main() {
+    let docker = Docker::new();
+    tokio::run(
+        docker
+            .df(&Default::default())
+            .map(|usage| println!("data usage {:#?}", usage))
+            .map_err(|e| eprintln!("Error: {}", e)),
+    );
+}
@@ -1,6 +1,6 @@
 // cargo run --example imagepull busybox
 
-use shiplift::{Docker, PullOptions};
+use shiplift::{models::Progress, Docker, PullOptions};
 use std::env;
 use tokio::prelude::{Future, Stream};
 
@@ -14,7 +14,13 @@ fn main() {
         .images()
         .pull(&PullOptions::builder().image(img).build())
         .for_each(|output| {
-            println!("{:?}", output);
+            match serde_json::from_value::<Progress>(output) {
+                Ok(progress) => match (&progress.id, progress.percent()) {
+                    (Some(id), Some(percent)) => println!("{}: {:.0}%", id, percent),
+                    _ => println!("{}", progress.status.unwrap_or_default()),
+                },
+                Err(e) => eprintln!("Unexpected output: {}", e),
+            }
             Ok(())
         })
         .map_err(|e| eprintln!("Error: {}", e));
@@ -1,19 +1,20 @@
 use shiplift::Docker;
-use std::{env, fs::File};
-use tokio::prelude::{Future, Stream};
+use std::env;
+use tokio::{
+    fs::File,
+    prelude::{Future, Stream},
+};
 
 fn main() {
     let docker = Docker::new();
     let path = env::args()
         .nth(1)
         .expect("You need to specify an image path");
-    let f = File::open(path).expect("Unable to open file");
 
-    let reader = Box::from(f);
-
-    let fut = docker
-        .images()
-        .import(reader)
+    let fut = File::open(path)
+        .map_err(shiplift::Error::from)
+        .map(move |file| docker.images().import_reader(file))
+        .flatten_stream()
         .for_each(|output| {
             println!("{:?}", output);
             Ok(())
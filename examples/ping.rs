@@ -0,0 +1,12 @@
+use shiplift::Docker;
+use tokio::prelude::Future;
+
+fn main() {
+    let docker = Docker::new();
+    tokio::run(
+        docker
+            .ping_info()
+            .map(|ping| println!("ping {:?}", ping))
+            .map_err(|e| eprintln!("Error: {}", e)),
+    );
+}
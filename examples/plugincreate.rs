@@ -0,0 +1,21 @@
+// cargo run --example plugincreate my-plugin ./plugin-dir
+
+use shiplift::Docker;
+use std::env;
+use tokio::prelude::Future;
+
+fn main() {
+    let docker = Docker::new();
+    let name = env::args()
+        .nth(1)
+        .expect("You need to specify a plugin name");
+    let path = env::args()
+        .nth(2)
+        .expect("You need to specify a path to the plugin's directory");
+    let fut = docker
+        .plugins()
+        .create(&name, &path)
+        .map(|_| println!("created plugin"))
+        .map_err(|e| eprintln!("Error: {}", e));
+    tokio::run(fut);
+}
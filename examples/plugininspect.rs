@@ -0,0 +1,17 @@
+use shiplift::Docker;
+use std::env;
+use tokio::prelude::Future;
+
+fn main() {
+    let docker = Docker::new();
+    let name = env::args()
+        .nth(1)
+        .expect("You need to specify a plugin name");
+    let fut = docker
+        .plugins()
+        .get(&name)
+        .inspect()
+        .map(|plugin| println!("{:#?}", plugin))
+        .map_err(|e| eprintln!("Error: {}", e));
+    tokio::run(fut);
+}
@@ -0,0 +1,36 @@
+// cargo run --example plugininstall vieux/sshfs:latest
+
+use shiplift::{Docker, PluginInstallOptions};
+use std::env;
+use tokio::prelude::{Future, Stream};
+
+fn main() {
+    env_logger::init();
+    let docker = Docker::new();
+    let remote = env::args()
+        .nth(1)
+        .expect("You need to specify a plugin reference");
+
+    let fut = docker
+        .plugins()
+        .privileges(&remote)
+        .map(move |privileges| {
+            for privilege in &privileges {
+                println!(
+                    "granting {} ({}): {:?}",
+                    privilege.name, privilege.description, privilege.value
+                );
+            }
+            PluginInstallOptions::builder(remote)
+                .privileges(privileges)
+                .build()
+        })
+        .and_then(move |opts| {
+            docker.plugins().install(&opts).for_each(|output| {
+                println!("{:?}", output);
+                Ok(())
+            })
+        })
+        .map_err(|e| eprintln!("Error: {}", e));
+    tokio::run(fut);
+}
@@ -0,0 +1,17 @@
+use shiplift::Docker;
+use tokio::prelude::Future;
+
+fn main() {
+    let docker = Docker::new();
+    let fut = docker
+        .plugins()
+        .list(&Default::default())
+        .map(|plugins| {
+            for p in plugins {
+                println!("plugin -> {:#?}", p)
+            }
+        })
+        .map_err(|e| eprintln!("Error: {}", e));
+
+    tokio::run(fut);
+}
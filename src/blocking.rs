@@ -0,0 +1,141 @@
+//! A synchronous interface to the docker daemon, for programs which aren't
+//! otherwise asynchronous, such as CLI tools and build scripts
+//!
+//! Requests are made with the asynchronous client, on a runtime owned by the
+//! blocking `Docker`. Its common calls are wrapped directly, while the rest of
+//! the api can be driven with `block_on` and `iter`.
+//!
+//! ```no_run
+//! use shiplift::{blocking::Docker, ContainerListOptions};
+//!
+//! let docker = Docker::new().unwrap();
+//! println!("{:?}", docker.version().unwrap());
+//! let containers = docker
+//!     .block_on(docker.client().containers().list(&ContainerListOptions::default()))
+//!     .unwrap();
+//! for container in containers {
+//!     println!("{}", container.id);
+//! }
+//! ```
+
+use crate::{
+    models::{Event, Info, PingInfo, Version},
+    EventsOptions, Result,
+};
+use futures::{sync::oneshot, Future, Stream};
+use std::sync::mpsc;
+use tokio::runtime::Runtime;
+
+/// A docker client whose calls block until they complete
+pub struct Docker {
+    docker: crate::Docker,
+    runtime: Runtime,
+}
+
+impl Docker {
+    /// Connects to the daemon configured by the environment, as
+    /// `shiplift::Docker::try_new` does
+    pub fn new() -> Result<Docker> {
+        Docker::from_client(crate::Docker::try_new()?)
+    }
+
+    /// Connects to the daemon configured by the environment, failing unless it
+    /// can be reached, as `shiplift::Docker::connect` does
+    pub fn connect() -> Result<Docker> {
+        let mut blocking = Docker::new()?;
+        blocking.docker = blocking.block_on(blocking.docker.clone().negotiate())?;
+        Ok(blocking)
+    }
+
+    /// Makes blocking calls with an existing client, such as one configured
+    /// with `shiplift::Docker::builder`
+    pub fn from_client(docker: crate::Docker) -> Result<Docker> {
+        Ok(Docker {
+            docker,
+            runtime: Runtime::new()?,
+        })
+    }
+
+    /// The asynchronous client requests are made with
+    pub fn client(&self) -> &crate::Docker {
+        &self.docker
+    }
+
+    /// Runs a future from the asynchronous client to completion
+    pub fn block_on<F>(
+        &self,
+        future: F,
+    ) -> std::result::Result<F::Item, F::Error>
+    where
+        F: Future + Send + 'static,
+        F::Item: Send,
+        F::Error: Send,
+    {
+        oneshot::spawn(future, &self.runtime.executor()).wait()
+    }
+
+    /// Iterates over a stream from the asynchronous client, such as logs or
+    /// events, blocking until each item is received
+    pub fn iter<S>(
+        &self,
+        stream: S,
+    ) -> impl Iterator<Item = std::result::Result<S::Item, S::Error>>
+    where
+        S: Stream + Send + 'static,
+        S::Item: Send,
+        S::Error: Send,
+    {
+        let (sender, receiver) = mpsc::channel();
+        // stops once the iterator is dropped and items can no longer be sent
+        self.runtime.executor().spawn(
+            stream
+                .then(move |item| sender.send(item).map_err(|_| ()))
+                .for_each(|_| Ok(())),
+        );
+        receiver.into_iter()
+    }
+
+    /// Returns version information associated with the docker daemon
+    pub fn version(&self) -> Result<Version> {
+        self.block_on(self.docker.version())
+    }
+
+    /// Returns information associated with the docker daemon
+    pub fn info(&self) -> Result<Info> {
+        self.block_on(self.docker.info())
+    }
+
+    /// Returns a simple ping response indicating the docker daemon is accessible
+    pub fn ping(&self) -> Result<String> {
+        self.block_on(self.docker.ping())
+    }
+
+    /// Pings the docker daemon with a `HEAD` request, returning the
+    /// metadata it reports in the response headers
+    pub fn ping_info(&self) -> Result<PingInfo> {
+        self.block_on(self.docker.ping_info())
+    }
+
+    /// Returns an iterator over docker events
+    pub fn events(
+        &self,
+        opts: &EventsOptions,
+    ) -> impl Iterator<Item = Result<Event>> {
+        self.iter(self.docker.events(opts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Docker;
+    use crate::testing::MockTransport;
+    use hyper::{Method, StatusCode};
+
+    #[test]
+    fn calls_block_until_complete() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        let docker = Docker::from_client(crate::Docker::from_transport(mock)).unwrap();
+        assert_eq!("OK", docker.ping().unwrap());
+    }
+}
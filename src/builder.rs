@@ -1,6 +1,10 @@
 //! Interfaces for building various structures
 
-use crate::{errors::Error, Result};
+use crate::{
+    errors::Error,
+    models::{Event, EventType, PluginPrivilege},
+    Result,
+};
 use serde::Serialize;
 use serde_json::{self, json, map::Map, Value};
 use std::{
@@ -8,6 +12,7 @@ use std::{
     collections::{BTreeMap, HashMap},
     hash::Hash,
     iter::{IntoIterator, Peekable},
+    time::Duration,
 };
 use url::form_urlencoded;
 
@@ -49,9 +54,8 @@ impl RegistryAuth {
 
     /// serialize authentication as JSON in base64
     pub fn serialize(&self) -> String {
-        serde_json::to_string(self)
-            .map(|c| base64::encode(&c))
-            .unwrap()
+        let auth = serde_json::to_string(self).expect("credentials of strings always serialize");
+        base64::encode(&auth)
     }
 }
 
@@ -182,6 +186,7 @@ impl TagOptionsBuilder {
 pub struct PullOptions {
     auth: Option<RegistryAuth>,
     params: HashMap<&'static str, String>,
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl PullOptions {
@@ -212,6 +217,7 @@ impl PullOptions {
 pub struct PullOptionsBuilder {
     auth: Option<RegistryAuth>,
     params: HashMap<&'static str, String>,
+    timeout: Option<Duration>,
 }
 
 impl PullOptionsBuilder {
@@ -276,10 +282,98 @@ impl PullOptionsBuilder {
         self
     }
 
+    /// The maximum time to wait on the daemon for this request, in place of
+    /// the client's request timeout
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn build(&mut self) -> PullOptions {
         PullOptions {
             auth: self.auth.take(),
             params: self.params.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Options for pulling several images at once with `Images::pull_many`
+#[derive(Debug)]
+pub struct PullManyOptions {
+    pub(crate) auth: Option<RegistryAuth>,
+    pub(crate) parallelism: usize,
+}
+
+impl Default for PullManyOptions {
+    fn default() -> Self {
+        PullManyOptions {
+            auth: None,
+            // the daemon's own default for concurrent layer downloads
+            parallelism: 3,
+        }
+    }
+}
+
+impl PullManyOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> PullManyOptionsBuilder {
+        PullManyOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct PullManyOptionsBuilder {
+    options: PullManyOptions,
+}
+
+impl PullManyOptionsBuilder {
+    /// Credentials to pull every image with
+    pub fn auth(
+        &mut self,
+        auth: RegistryAuth,
+    ) -> &mut Self {
+        self.options.auth = Some(auth);
+        self
+    }
+
+    /// The most images pulled at once, at least one. Three by default
+    pub fn parallelism(
+        &mut self,
+        parallelism: usize,
+    ) -> &mut Self {
+        self.options.parallelism = parallelism.max(1);
+        self
+    }
+
+    pub fn build(&mut self) -> PullManyOptions {
+        PullManyOptions {
+            auth: self.options.auth.take(),
+            parallelism: self.options.parallelism,
+        }
+    }
+}
+
+/// How a Windows container is isolated from the host
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Isolation {
+    /// The daemon's default, set by its `--exec-opt isolation`
+    Default,
+    /// Sharing the host's kernel, as Linux containers do
+    Process,
+    /// In a lightweight Hyper-V virtual machine
+    HyperV,
+}
+
+impl Isolation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Isolation::Default => "default",
+            Isolation::Process => "process",
+            Isolation::HyperV => "hyperv",
         }
     }
 }
@@ -288,6 +382,8 @@ impl PullOptionsBuilder {
 pub struct BuildOptions {
     pub path: String,
     params: HashMap<&'static str, String>,
+    auths: BTreeMap<String, RegistryAuth>,
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl BuildOptions {
@@ -313,12 +409,26 @@ impl BuildOptions {
             )
         }
     }
+
+    /// The registry credentials for the build's `FROM` images, as the value of
+    /// an `X-Registry-Config` header
+    pub(crate) fn registry_config_header(&self) -> Option<String> {
+        if self.auths.is_empty() {
+            None
+        } else {
+            let config = serde_json::to_string(&self.auths)
+                .expect("credentials keyed by string always serialize");
+            Some(base64::encode(&config))
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct BuildOptionsBuilder {
     path: String,
     params: HashMap<&'static str, String>,
+    auths: BTreeMap<String, RegistryAuth>,
+    timeout: Option<Duration>,
 }
 
 impl BuildOptionsBuilder {
@@ -422,18 +532,244 @@ impl BuildOptionsBuilder {
         self
     }
 
+    /// An id for the build, with which a BuildKit build can be cancelled
+    pub fn build_id<T>(
+        &mut self,
+        id: T,
+    ) -> &mut Self
+    where
+        T: Into<String>,
+    {
+        self.params.insert("buildid", id.into());
+        self
+    }
+
+    /// How the build's containers are isolated, on Windows daemons
+    pub fn isolation(
+        &mut self,
+        isolation: Isolation,
+    ) -> &mut Self {
+        self.params
+            .insert("isolation", isolation.as_str().to_owned());
+        self
+    }
+
+    /// Credentials for pulling the build's base images from the registry at
+    /// `server`, such as `https://index.docker.io/v1/` or `registry.example.org`.
+    /// May be given once per registry
+    pub fn registry_auth<S>(
+        &mut self,
+        server: S,
+        auth: RegistryAuth,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.auths.insert(server.into(), auth);
+        self
+    }
+
     // todo: memswap
     // todo: cpusetcpus
     // todo: cpuperiod
     // todo: cpuquota
     // todo: buildargs
 
+    /// The maximum time to wait on the daemon for this request, in place of
+    /// the client's request timeout
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn build(&self) -> BuildOptions {
         BuildOptions {
             path: self.path.clone(),
             params: self.params.clone(),
+            auths: self.auths.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Filters for list endpoints and event streams, sent to the daemon as a json
+/// object mapping each key to the values to match.
+///
+/// An object matches if it matches any of the values given for each key.
+///
+/// ```
+/// use shiplift::{ContainerListOptions, Filters};
+///
+/// let opts = ContainerListOptions::builder()
+///     .filters(Filters::new().label("com.example.team=infra").status("running"))
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filters {
+    filters: BTreeMap<String, Vec<String>>,
+}
+
+impl Filters {
+    pub fn new() -> Filters {
+        Filters::default()
+    }
+
+    /// Matches objects with the given value for a key, for keys without a
+    /// method of their own
+    pub fn add<K, V>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> &mut Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.filters
+            .entry(key.into())
+            .or_insert_with(Vec::new)
+            .push(value.into());
+        self
+    }
+
+    /// Matches objects with a label, given as `key` or `key=value`
+    pub fn label(
+        &mut self,
+        label: &str,
+    ) -> &mut Self {
+        self.add("label", label)
+    }
+
+    /// Matches objects by name
+    pub fn name(
+        &mut self,
+        name: &str,
+    ) -> &mut Self {
+        self.add("name", name)
+    }
+
+    /// Matches objects by id
+    pub fn id(
+        &mut self,
+        id: &str,
+    ) -> &mut Self {
+        self.add("id", id)
+    }
+
+    /// Matches containers in a state, e.g. `running` or `exited`
+    pub fn status(
+        &mut self,
+        status: &str,
+    ) -> &mut Self {
+        self.add("status", status)
+    }
+
+    /// Matches containers which exited with the given code
+    pub fn exit_code(
+        &mut self,
+        code: u64,
+    ) -> &mut Self {
+        self.add("exit", code.to_string())
+    }
+
+    /// Matches containers created from an image or its descendants
+    pub fn ancestor(
+        &mut self,
+        image: &str,
+    ) -> &mut Self {
+        self.add("ancestor", image)
+    }
+
+    /// Matches images which are, or aren't, dangling
+    pub fn dangling(
+        &mut self,
+        dangling: bool,
+    ) -> &mut Self {
+        self.add("dangling", dangling.to_string())
+    }
+
+    /// Matches images by reference, e.g. `busybox:*`
+    pub fn reference(
+        &mut self,
+        reference: &str,
+    ) -> &mut Self {
+        self.add("reference", reference)
+    }
+
+    /// Matches objects created before a timestamp, or a duration such as `24h`
+    /// before now, when pruning
+    pub fn until(
+        &mut self,
+        until: &str,
+    ) -> &mut Self {
+        self.add("until", until)
+    }
+
+    /// Matches networks or volumes by driver
+    pub fn driver(
+        &mut self,
+        driver: &str,
+    ) -> &mut Self {
+        self.add("driver", driver)
+    }
+
+    /// Matches events for a container, by name or id
+    pub fn container(
+        &mut self,
+        container: &str,
+    ) -> &mut Self {
+        self.add("container", container)
+    }
+
+    /// Matches events for an image, by name or id
+    pub fn image(
+        &mut self,
+        image: &str,
+    ) -> &mut Self {
+        self.add("image", image)
+    }
+
+    /// Matches events by action, e.g. `start` or `die`
+    pub fn event(
+        &mut self,
+        event: &str,
+    ) -> &mut Self {
+        self.add("event", event)
+    }
+
+    /// Matches events for a kind of object
+    pub fn event_type(
+        &mut self,
+        typ: EventType,
+    ) -> &mut Self {
+        self.add("type", typ.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Adds the values of other filters to these
+    fn extend(
+        &mut self,
+        other: &Filters,
+    ) {
+        for (key, values) in &other.filters {
+            self.filters
+                .entry(key.clone())
+                .or_insert_with(Vec::new)
+                .extend(values.iter().cloned());
         }
     }
+
+    /// serialize filters as the json the `filters` query parameter expects
+    pub fn serialize(&self) -> String {
+        serde_json::to_string(&self.filters)
+            .expect("lists of strings keyed by string always serialize")
+    }
 }
 
 /// Options for filtering container list results
@@ -474,6 +810,7 @@ pub enum ContainerFilter {
 #[derive(Default)]
 pub struct ContainerListOptionsBuilder {
     params: HashMap<&'static str, String>,
+    filters: Filters,
 }
 
 impl ContainerListOptionsBuilder {
@@ -481,19 +818,25 @@ impl ContainerListOptionsBuilder {
         &mut self,
         filters: Vec<ContainerFilter>,
     ) -> &mut Self {
-        let mut param = HashMap::new();
+        let mut param = Filters::new();
         for f in filters {
             match f {
-                ContainerFilter::ExitCode(c) => param.insert("exit", vec![c.to_string()]),
-                ContainerFilter::Status(s) => param.insert("status", vec![s]),
-                ContainerFilter::LabelName(n) => param.insert("label", vec![n]),
-                ContainerFilter::Label(n, v) => param.insert("label", vec![format!("{}={}", n, v)]),
+                ContainerFilter::ExitCode(c) => param.exit_code(c),
+                ContainerFilter::Status(s) => param.status(&s),
+                ContainerFilter::LabelName(n) => param.label(&n),
+                ContainerFilter::Label(n, v) => param.label(&format!("{}={}", n, v)),
             };
         }
-        // structure is a a json encoded object mapping string keys to a list
-        // of string values
-        self.params
-            .insert("filters", serde_json::to_string(&param).unwrap());
+        self.filters(&param)
+    }
+
+    /// Only list containers matching the given filters
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
         self
     }
 
@@ -530,6 +873,58 @@ impl ContainerListOptionsBuilder {
     }
 }
 
+/// Options for choosing which stopped containers to delete when pruning
+#[derive(Default, Debug)]
+pub struct ContainerPruneOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl ContainerPruneOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> ContainerPruneOptionsBuilder {
+        ContainerPruneOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `ContainerPruneOptions`
+#[derive(Default)]
+pub struct ContainerPruneOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
+}
+
+impl ContainerPruneOptionsBuilder {
+    /// Only delete containers matching the given filters, which may be
+    /// `until` and `label`
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
+        self
+    }
+
+    pub fn build(&self) -> ContainerPruneOptions {
+        ContainerPruneOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
 /// Interface for building a new docker container from an existing image
 #[derive(Serialize, Debug)]
 pub struct ContainerOptions {
@@ -543,25 +938,29 @@ fn insert<'a, I, V>(
     key_path: &mut Peekable<I>,
     value: &V,
     parent_node: &mut Value,
-) where
+) -> Result<()>
+where
     V: Serialize,
     I: Iterator<Item = &'a str>,
 {
-    let local_key = key_path.next().unwrap();
+    // split always yields at least one key
+    let local_key = key_path.next().unwrap_or_default();
+    let parent = parent_node.as_object_mut().ok_or_else(|| {
+        Error::from(<serde_json::Error as serde::ser::Error>::custom(format!(
+            "can't set {} on a value which isn't an object",
+            local_key
+        )))
+    })?;
 
     if key_path.peek().is_some() {
-        let node = parent_node
-            .as_object_mut()
-            .unwrap()
+        let node = parent
             .entry(local_key.to_string())
             .or_insert(Value::Object(Map::new()));
 
-        insert(key_path, value, node);
+        insert(key_path, value, node)
     } else {
-        parent_node
-            .as_object_mut()
-            .unwrap()
-            .insert(local_key.to_string(), serde_json::to_value(value).unwrap());
+        parent.insert(local_key.to_string(), serde_json::to_value(value)?);
+        Ok(())
     }
 }
 
@@ -573,32 +972,139 @@ impl ContainerOptions {
 
     /// serialize options as a string. returns None if no options are defined
     pub fn serialize(&self) -> Result<String> {
-        serde_json::to_string(&self.to_json()).map_err(Error::from)
+        self.validate()?;
+        serde_json::to_string(&self.to_json()?).map_err(Error::from)
+    }
+
+    /// Checks for options the daemon would reject, so that creating the
+    /// container fails with `Error::InvalidInput` rather than a bad request
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref name) = self.name {
+            validate_container_name(name)?;
+        }
+        match self.params.get("HostConfig.Memory").and_then(Value::as_u64) {
+            Some(memory) if memory > 0 && memory < MIN_MEMORY => {
+                return Err(Error::InvalidInput(format!(
+                    "memory limit of {} bytes is below the minimum of 6MB",
+                    memory
+                )));
+            }
+            _ => (),
+        }
+        let ports = self
+            .params
+            .get("ExposedPorts")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|ports| ports.keys());
+        for port in ports {
+            validate_port(port)?;
+        }
+        let host_ports = self
+            .params
+            .get("HostConfig.PortBindings")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|bindings| bindings.values())
+            .filter_map(Value::as_array)
+            .flatten()
+            .filter_map(|binding| binding.get("HostPort").and_then(Value::as_str));
+        for port in host_ports {
+            match port.parse::<u32>() {
+                // 0 lets the daemon pick a port
+                Ok(port) if port <= 65535 => (),
+                _ => {
+                    return Err(Error::InvalidInput(format!(
+                        "host port {} is out of range",
+                        port
+                    )))
+                }
+            }
+        }
+        let restart_policy = self
+            .params
+            .get("HostConfig.RestartPolicy.Name")
+            .and_then(Value::as_str);
+        if let Some(policy) = restart_policy {
+            if !RESTART_POLICIES.contains(&policy) {
+                return Err(Error::InvalidInput(format!(
+                    "unknown restart policy {}",
+                    policy
+                )));
+            }
+        }
+        let auto_remove = self.params.get("HostConfig.AutoRemove") == Some(&json!(true));
+        if auto_remove && restart_policy.map_or(false, |policy| policy != "no") {
+            return Err(Error::InvalidInput(
+                "auto removed containers can't have a restart policy".to_owned(),
+            ));
+        }
+        Ok(())
     }
 
-    fn to_json(&self) -> Value {
+    fn to_json(&self) -> Result<Value> {
         let mut body_members = Map::new();
         // The HostConfig element gets initialized to an empty object,
         // for backward compatibility.
         body_members.insert("HostConfig".to_string(), Value::Object(Map::new()));
         let mut body = Value::Object(body_members);
-        self.parse_from(&self.params, &mut body);
-        body
+        self.parse_from(&self.params, &mut body)?;
+        Ok(body)
     }
 
     pub fn parse_from<'a, K, V>(
         &self,
         params: &'a HashMap<K, V>,
         body: &mut Value,
-    ) where
+    ) -> Result<()>
+    where
         &'a HashMap<K, V>: IntoIterator,
         K: ToString + Eq + Hash,
         V: Serialize,
     {
         for (k, v) in params.iter() {
             let key_string = k.to_string();
-            insert(&mut key_string.split('.').peekable(), v, body)
+            insert(&mut key_string.split('.').peekable(), v, body)?;
         }
+        Ok(())
+    }
+}
+
+/// The smallest memory limit the daemon accepts
+const MIN_MEMORY: u64 = 6 * 1024 * 1024;
+
+const RESTART_POLICIES: &[&str] = &["no", "always", "unless-stopped", "on-failure"];
+
+/// Container names must match `/?[a-zA-Z0-9][a-zA-Z0-9_.-]+`
+fn validate_container_name(name: &str) -> Result<()> {
+    let name = name.trim_start_matches('/');
+    let mut chars = name.chars();
+    let valid = chars.next().map_or(false, |c| c.is_ascii_alphanumeric())
+        && name.len() > 1
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "invalid container name {:?}, names must match [a-zA-Z0-9][a-zA-Z0-9_.-]+",
+            name
+        )))
+    }
+}
+
+/// Ports are given as `port/protocol`
+fn validate_port(port: &str) -> Result<()> {
+    let mut parts = port.splitn(2, '/');
+    let in_range = parts
+        .next()
+        .and_then(|number| number.parse::<u32>().ok())
+        .map_or(false, |number| (1..=65535).contains(&number));
+    match parts.next() {
+        Some("tcp") | Some("udp") | Some("sctp") if in_range => Ok(()),
+        _ => Err(Error::InvalidInput(format!(
+            "invalid port {}, ports must be between 1 and 65535 and use tcp, udp or sctp",
+            port
+        ))),
     }
 }
 
@@ -633,6 +1139,16 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// The user, and optionally group, commands run as, by name or id, such as
+    /// `nobody` or `1000:1000` (corresponds to the `-u` docker cli argument)
+    pub fn user(
+        &mut self,
+        user: &str,
+    ) -> &mut Self {
+        self.params.insert("User", json!(user));
+        self
+    }
+
     /// Specify any bind mounts, taking the form of `/some/host/path:/some/container/path`
     pub fn volumes(
         &mut self,
@@ -735,6 +1251,16 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// The limit on memory plus swap, in bytes, or `-1` for unlimited swap
+    pub fn memory_swap(
+        &mut self,
+        memory_swap: i64,
+    ) -> &mut Self {
+        self.params
+            .insert("HostConfig.MemorySwap", json!(memory_swap));
+        self
+    }
+
     /// Sets an integer value representing the container's
     /// relative CPU weight versus other containers.
     pub fn cpu_shares(
@@ -746,6 +1272,17 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// Limits the container to a number of CPUs, such as `1.5` (corresponds to
+    /// the `--cpus` docker cli argument)
+    pub fn cpus(
+        &mut self,
+        cpus: f64,
+    ) -> &mut Self {
+        self.params
+            .insert("HostConfig.NanoCpus", json!((cpus * 1e9) as u64));
+        self
+    }
+
     pub fn labels(
         &mut self,
         labels: &HashMap<&str, &str>,
@@ -791,43 +1328,98 @@ impl ContainerOptionsBuilder {
         self
     }
 
-    pub fn extra_hosts(
+    /// Whether to close `stdin` once the first client attached to it
+    /// disconnects
+    pub fn stdin_once(
         &mut self,
-        hosts: Vec<&str>,
+        stdin_once: bool,
     ) -> &mut Self {
-        self.params.insert("HostConfig.ExtraHosts", json!(hosts));
+        self.params.insert("StdinOnce", json!(stdin_once));
         self
     }
 
-    pub fn volumes_from(
+    /// Entries added to the container's `/etc/hosts`, in the form `host:ip`.
+    /// An ip of `host-gateway` is replaced by the daemon with the host's address
+    pub fn extra_hosts(
         &mut self,
-        volumes: Vec<&str>,
+        hosts: Vec<&str>,
     ) -> &mut Self {
-        self.params.insert("HostConfig.VolumesFrom", json!(volumes));
+        self.params.insert("HostConfig.ExtraHosts", json!(hosts));
         self
     }
 
-    pub fn network_mode(
+    /// The DNS servers the container uses in place of the host's
+    pub fn dns(
         &mut self,
-        network: &str,
+        servers: Vec<&str>,
     ) -> &mut Self {
-        self.params.insert("HostConfig.NetworkMode", json!(network));
+        self.params.insert("HostConfig.Dns", json!(servers));
         self
     }
 
-    pub fn env(
+    /// The domains searched for unqualified host names
+    pub fn dns_search(
         &mut self,
-        envs: Vec<&str>,
+        domains: Vec<&str>,
     ) -> &mut Self {
-        self.params.insert("Env", json!(envs));
+        self.params.insert("HostConfig.DnsSearch", json!(domains));
         self
     }
 
-    pub fn cmd(
+    /// Options for the container's resolver, such as `ndots:2`
+    pub fn dns_options(
         &mut self,
-        cmds: Vec<&str>,
+        options: Vec<&str>,
     ) -> &mut Self {
-        self.params.insert("Cmd", json!(cmds));
+        self.params.insert("HostConfig.DnsOptions", json!(options));
+        self
+    }
+
+    pub fn volumes_from(
+        &mut self,
+        volumes: Vec<&str>,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.VolumesFrom", json!(volumes));
+        self
+    }
+
+    pub fn network_mode(
+        &mut self,
+        network: &str,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.NetworkMode", json!(network));
+        self
+    }
+
+    /// Connects the container to a network as it's created, reachable from the
+    /// network's other containers by the given aliases as well as its name.
+    /// The daemon only accepts one network here, the rest must be connected
+    /// once the container is created
+    pub fn network(
+        &mut self,
+        network: &str,
+        aliases: Vec<&str>,
+    ) -> &mut Self {
+        self.params.insert(
+            "NetworkingConfig.EndpointsConfig",
+            json!({ network: { "Aliases": aliases } }),
+        );
+        self
+    }
+
+    pub fn env(
+        &mut self,
+        envs: Vec<&str>,
+    ) -> &mut Self {
+        self.params.insert("Env", json!(envs));
+        self
+    }
+
+    pub fn cmd(
+        &mut self,
+        cmds: Vec<&str>,
+    ) -> &mut Self {
+        self.params.insert("Cmd", json!(cmds));
         self
     }
 
@@ -839,6 +1431,15 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// The entrypoint as separate arguments, for entrypoints with more than one
+    pub fn entrypoint_args(
+        &mut self,
+        entrypoint: Vec<&str>,
+    ) -> &mut Self {
+        self.params.insert("Entrypoint", json!(entrypoint));
+        self
+    }
+
     pub fn capabilities(
         &mut self,
         capabilities: Vec<&str>,
@@ -847,6 +1448,17 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// Kernel capabilities to drop from the container's default set, such as
+    /// `NET_RAW`, or `ALL`
+    pub fn cap_drop(
+        &mut self,
+        capabilities: Vec<&str>,
+    ) -> &mut Self {
+        self.params
+            .insert("HostConfig.CapDrop", json!(capabilities));
+        self
+    }
+
     pub fn devices(
         &mut self,
         devices: Vec<HashMap<String, String>>,
@@ -855,6 +1467,27 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// Rules added to the container's device cgroup allow list, in the form
+    /// `<type> <major>:<minor> <permissions>`, such as `c 189:* rmw`
+    pub fn device_cgroup_rules(
+        &mut self,
+        rules: Vec<&str>,
+    ) -> &mut Self {
+        self.params
+            .insert("HostConfig.DeviceCgroupRules", json!(rules));
+        self
+    }
+
+    /// Options for the storage driver, per container, such as `size` to limit
+    /// the size of its writable layer with `overlay2` on xfs
+    pub fn storage_opt(
+        &mut self,
+        opts: &HashMap<&str, &str>,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.StorageOpt", json!(opts));
+        self
+    }
+
     pub fn log_driver(
         &mut self,
         log_driver: &str,
@@ -864,6 +1497,16 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// Options for the log driver, such as `max-size` for `json-file`
+    pub fn log_options(
+        &mut self,
+        opts: &HashMap<&str, &str>,
+    ) -> &mut Self {
+        self.params
+            .insert("HostConfig.LogConfig.Config", json!(opts));
+        self
+    }
+
     pub fn restart_policy(
         &mut self,
         name: &str,
@@ -888,6 +1531,8 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// The user namespace the container runs in, `host` to share the host's
+    /// when the daemon remaps users
     pub fn userns_mode(
         &mut self,
         mode: &str,
@@ -896,6 +1541,43 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// The IPC namespace the container uses, such as `host`, `shareable` or
+    /// `container:<name|id>`
+    pub fn ipc_mode(
+        &mut self,
+        mode: &str,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.IpcMode", json!(mode));
+        self
+    }
+
+    /// The PID namespace the container uses, `host` or `container:<name|id>`
+    pub fn pid_mode(
+        &mut self,
+        mode: &str,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.PidMode", json!(mode));
+        self
+    }
+
+    /// The UTS namespace the container uses, `host` to share the host's hostname
+    pub fn uts_mode(
+        &mut self,
+        mode: &str,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.UTSMode", json!(mode));
+        self
+    }
+
+    /// Additional groups, by name or id, the container's process runs as a member of
+    pub fn group_add(
+        &mut self,
+        groups: Vec<&str>,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.GroupAdd", json!(groups));
+        self
+    }
+
     pub fn privileged(
         &mut self,
         set: bool,
@@ -904,6 +1586,27 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// The OCI runtime to run the container with, such as `runsc` or `nvidia`,
+    /// which must be registered with the daemon. Defaults to the daemon's
+    /// default runtime
+    pub fn runtime(
+        &mut self,
+        runtime: &str,
+    ) -> &mut Self {
+        self.params.insert("HostConfig.Runtime", json!(runtime));
+        self
+    }
+
+    /// How the container is isolated, on Windows daemons
+    pub fn isolation(
+        &mut self,
+        isolation: Isolation,
+    ) -> &mut Self {
+        self.params
+            .insert("HostConfig.Isolation", json!(isolation.as_str()));
+        self
+    }
+
     pub fn build(&self) -> ContainerOptions {
         ContainerOptions {
             name: self.name.clone(),
@@ -999,6 +1702,16 @@ impl ExecContainerOptionsBuilder {
         self
     }
 
+    /// Allocate a TTY for the exec command, which can then be resized with
+    /// `Exec::resize`. Its output isn't split into stdout and stderr
+    pub fn tty(
+        &mut self,
+        tty: bool,
+    ) -> &mut Self {
+        self.params_bool.insert("Tty", tty);
+        self
+    }
+
     pub fn build(&self) -> ExecContainerOptions {
         ExecContainerOptions {
             params: self.params.clone(),
@@ -1008,7 +1721,7 @@ impl ExecContainerOptionsBuilder {
 }
 
 /// Options for filtering streams of Docker events
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct EventsOptions {
     params: HashMap<&'static str, String>,
 }
@@ -1030,34 +1743,59 @@ impl EventsOptions {
             )
         }
     }
-}
 
-#[derive(Copy, Clone)]
-pub enum EventFilterType {
-    Container,
-    Image,
-    Volume,
-    Network,
-    Daemon,
-}
+    /// The same options, only returning events emitted since the given one,
+    /// including those emitted at the same time
+    pub(crate) fn resume_from(
+        &self,
+        event: &Event,
+    ) -> EventsOptions {
+        let mut params = self.params.clone();
+        #[cfg(feature = "chrono")]
+        params.insert("since", event_timestamp(&event.time_nano));
+        #[cfg(not(feature = "chrono"))]
+        params.insert(
+            "since",
+            format!(
+                "{}.{:09}",
+                event.time_nano / 1_000_000_000,
+                event.time_nano % 1_000_000_000
+            ),
+        );
+        EventsOptions { params }
+    }
 
-fn event_filter_type_to_string(filter: EventFilterType) -> &'static str {
-    match filter {
-        EventFilterType::Container => "container",
-        EventFilterType::Image => "image",
-        EventFilterType::Volume => "volume",
-        EventFilterType::Network => "network",
-        EventFilterType::Daemon => "daemon",
+    /// Whether the stream of events ends at some point in time
+    pub(crate) fn is_bounded(&self) -> bool {
+        self.params.contains_key("until")
     }
 }
 
-/// Filter options for image listings
+/// Formats a timestamp the way the events endpoint expects, as
+/// seconds since the epoch with a nanosecond fraction
+#[cfg(feature = "chrono")]
+fn event_timestamp<Tz>(timestamp: &chrono::DateTime<Tz>) -> String
+where
+    Tz: chrono::TimeZone,
+{
+    format!(
+        "{}.{:09}",
+        timestamp.timestamp(),
+        timestamp.timestamp_subsec_nanos()
+    )
+}
+
+/// Filter options for docker events
 pub enum EventFilter {
+    /// Container name or id
     Container(String),
+    /// Event action, e.g. `start` or `die`
     Event(String),
+    /// Image name or id
     Image(String),
+    /// Label in the form `key` or `key=value`
     Label(String),
-    Type(EventFilterType),
+    Type(EventType),
     Volume(String),
     Network(String),
     Daemon(String),
@@ -1067,32 +1805,77 @@ pub enum EventFilter {
 #[derive(Default)]
 pub struct EventsOptionsBuilder {
     params: HashMap<&'static str, String>,
-    events: Vec<String>,
-    containers: Vec<String>,
-    images: Vec<String>,
-    labels: Vec<String>,
-    volumes: Vec<String>,
-    networks: Vec<String>,
-    daemons: Vec<String>,
-    types: Vec<String>,
+    filters: Filters,
 }
 
 impl EventsOptionsBuilder {
-    /// Filter events since a given timestamp
+    /// Only return events emitted after a given point in time
+    #[cfg(feature = "chrono")]
+    pub fn since<Tz>(
+        &mut self,
+        timestamp: &chrono::DateTime<Tz>,
+    ) -> &mut Self
+    where
+        Tz: chrono::TimeZone,
+    {
+        self.params.insert("since", event_timestamp(timestamp));
+        self
+    }
+
+    /// Only return events emitted after a given unix timestamp
+    #[cfg(not(feature = "chrono"))]
     pub fn since(
         &mut self,
-        ts: &u64,
+        timestamp: &u64,
+    ) -> &mut Self {
+        self.params.insert("since", timestamp.to_string());
+        self
+    }
+
+    /// Only return events emitted after a given unix timestamp
+    pub fn since_timestamp(
+        &mut self,
+        timestamp: i64,
     ) -> &mut Self {
-        self.params.insert("since", ts.to_string());
+        self.params.insert("since", timestamp.to_string());
+        self
+    }
+
+    /// Only return events emitted before a given point in time.
+    ///
+    /// The stream ends once this point has passed, rather than waiting for new events.
+    #[cfg(feature = "chrono")]
+    pub fn until<Tz>(
+        &mut self,
+        timestamp: &chrono::DateTime<Tz>,
+    ) -> &mut Self
+    where
+        Tz: chrono::TimeZone,
+    {
+        self.params.insert("until", event_timestamp(timestamp));
         self
     }
 
-    /// Filter events until a given timestamp
+    /// Only return events emitted before a given unix timestamp.
+    ///
+    /// The stream ends once this point has passed, rather than waiting for new events.
+    #[cfg(not(feature = "chrono"))]
     pub fn until(
         &mut self,
-        ts: &u64,
+        timestamp: &u64,
+    ) -> &mut Self {
+        self.params.insert("until", timestamp.to_string());
+        self
+    }
+
+    /// Only return events emitted before a given unix timestamp.
+    ///
+    /// The stream ends once this point has passed, rather than waiting for new events.
+    pub fn until_timestamp(
+        &mut self,
+        timestamp: i64,
     ) -> &mut Self {
-        self.params.insert("until", ts.to_string());
+        self.params.insert("until", timestamp.to_string());
         self
     }
 
@@ -1100,46 +1883,29 @@ impl EventsOptionsBuilder {
         &mut self,
         filters: Vec<EventFilter>,
     ) -> &mut Self {
-        let mut params = HashMap::new();
+        let mut params = Filters::new();
         for f in filters {
             match f {
-                EventFilter::Container(n) => {
-                    self.containers.push(n);
-                    params.insert("container", self.containers.clone())
-                }
-                EventFilter::Event(n) => {
-                    self.events.push(n);
-                    params.insert("event", self.events.clone())
-                }
-                EventFilter::Image(n) => {
-                    self.images.push(n);
-                    params.insert("image", self.images.clone())
-                }
-                EventFilter::Label(n) => {
-                    self.labels.push(n);
-                    params.insert("label", self.labels.clone())
-                }
-                EventFilter::Volume(n) => {
-                    self.volumes.push(n);
-                    params.insert("volume", self.volumes.clone())
-                }
-                EventFilter::Network(n) => {
-                    self.networks.push(n);
-                    params.insert("network", self.networks.clone())
-                }
-                EventFilter::Daemon(n) => {
-                    self.daemons.push(n);
-                    params.insert("daemon", self.daemons.clone())
-                }
-                EventFilter::Type(n) => {
-                    let event_type = event_filter_type_to_string(n).to_string();
-                    self.types.push(event_type);
-                    params.insert("type", self.types.clone())
-                }
+                EventFilter::Container(n) => params.container(&n),
+                EventFilter::Event(n) => params.event(&n),
+                EventFilter::Image(n) => params.image(&n),
+                EventFilter::Label(n) => params.label(&n),
+                EventFilter::Volume(n) => params.add("volume", n),
+                EventFilter::Network(n) => params.add("network", n),
+                EventFilter::Daemon(n) => params.add("daemon", n),
+                EventFilter::Type(n) => params.event_type(n),
             };
         }
-        self.params
-            .insert("filters", serde_json::to_string(&params).unwrap());
+        self.filters(&params)
+    }
+
+    /// Only return events matching the given filters
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
         self
     }
 
@@ -1150,10 +1916,82 @@ impl EventsOptionsBuilder {
     }
 }
 
+/// How many lines from the end of a container's logs to return
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tail {
+    /// The whole log
+    All,
+    /// Only the last lines, with `Lines(0)` returning none of the existing log
+    Lines(u64),
+}
+
+impl Tail {
+    fn as_param(self) -> String {
+        match self {
+            Tail::All => "all".to_owned(),
+            Tail::Lines(lines) => lines.to_string(),
+        }
+    }
+}
+
+/// Options for choosing which unused images to delete when pruning
+#[derive(Default, Debug)]
+pub struct ImagePruneOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl ImagePruneOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> ImagePruneOptionsBuilder {
+        ImagePruneOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `ImagePruneOptions`
+#[derive(Default)]
+pub struct ImagePruneOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
+}
+
+impl ImagePruneOptionsBuilder {
+    /// Only delete images matching the given filters, which may be `dangling`,
+    /// `until` and `label`. Only dangling images are deleted unless
+    /// `dangling(false)` is given
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
+        self
+    }
+
+    pub fn build(&self) -> ImagePruneOptions {
+        ImagePruneOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
 /// Options for controlling log request results
 #[derive(Default, Debug)]
 pub struct LogsOptions {
     params: HashMap<&'static str, String>,
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl LogsOptions {
@@ -1180,6 +2018,7 @@ impl LogsOptions {
 #[derive(Default)]
 pub struct LogsOptionsBuilder {
     params: HashMap<&'static str, String>,
+    timeout: Option<Duration>,
 }
 
 impl LogsOptionsBuilder {
@@ -1215,12 +2054,22 @@ impl LogsOptionsBuilder {
         self
     }
 
-    /// how_many can either be "all" or a to_string() of the number
+    /// Prefix each line with the attributes given to it by the log driver,
+    /// which can be read with `tty::Chunk::details`
+    pub fn details(
+        &mut self,
+        d: bool,
+    ) -> &mut Self {
+        self.params.insert("details", d.to_string());
+        self
+    }
+
+    /// Only return lines from the end of the log. Defaults to `Tail::All`
     pub fn tail(
         &mut self,
-        how_many: &str,
+        how_many: Tail,
     ) -> &mut Self {
-        self.params.insert("tail", how_many.to_owned());
+        self.params.insert("tail", how_many.as_param());
         self
     }
 
@@ -1246,47 +2095,171 @@ impl LogsOptionsBuilder {
         self
     }
 
+    /// The maximum time to wait on the daemon for this request, in place of
+    /// the client's request timeout
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     pub fn build(&self) -> LogsOptions {
         LogsOptions {
             params: self.params.clone(),
+            timeout: self.timeout,
         }
     }
 }
 
-/// Filter options for image listings
-pub enum ImageFilter {
-    Dangling,
-    LabelName(String),
-    Label(String, String),
-}
-
-/// Options for filtering image list results
+/// Options for waiting on a container to stop
 #[derive(Default, Debug)]
-pub struct ImageListOptions {
-    params: HashMap<&'static str, String>,
+pub struct WaitOptions {
+    pub(crate) timeout: Option<Duration>,
 }
 
-impl ImageListOptions {
-    pub fn builder() -> ImageListOptionsBuilder {
-        ImageListOptionsBuilder::default()
-    }
-    pub fn serialize(&self) -> Option<String> {
-        if self.params.is_empty() {
-            None
-        } else {
-            Some(
-                form_urlencoded::Serializer::new(String::new())
-                    .extend_pairs(&self.params)
-                    .finish(),
-            )
-        }
+impl WaitOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> WaitOptionsBuilder {
+        WaitOptionsBuilder::default()
     }
 }
 
-/// Builder interface for `ImageListOptions`
+/// Builder interface for `WaitOptions`
 #[derive(Default)]
-pub struct ImageListOptionsBuilder {
-    params: HashMap<&'static str, String>,
+pub struct WaitOptionsBuilder {
+    timeout: Option<Duration>,
+}
+
+impl WaitOptionsBuilder {
+    /// The longest to wait for the container to stop. Without one, waits
+    /// however long the container runs
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(&self) -> WaitOptions {
+        WaitOptions {
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Options for exporting a container or image to a tarball
+#[derive(Default, Debug)]
+pub struct ExportOptions {
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl ExportOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> ExportOptionsBuilder {
+        ExportOptionsBuilder::default()
+    }
+}
+
+/// Builder interface for `ExportOptions`
+#[derive(Default)]
+pub struct ExportOptionsBuilder {
+    timeout: Option<Duration>,
+}
+
+impl ExportOptionsBuilder {
+    /// The maximum time to wait on the daemon for this request, in place of
+    /// the client's request timeout
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(&self) -> ExportOptions {
+        ExportOptions {
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Options for importing images from a tarball
+#[derive(Default, Debug)]
+pub struct ImportOptions {
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl ImportOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> ImportOptionsBuilder {
+        ImportOptionsBuilder::default()
+    }
+}
+
+/// Builder interface for `ImportOptions`
+#[derive(Default)]
+pub struct ImportOptionsBuilder {
+    timeout: Option<Duration>,
+}
+
+impl ImportOptionsBuilder {
+    /// The maximum time to wait on the daemon for this request, in place of
+    /// the client's request timeout. The daemon only responds once it has read
+    /// the whole tarball, so large ones need longer
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(&self) -> ImportOptions {
+        ImportOptions {
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Filter options for image listings
+pub enum ImageFilter {
+    Dangling,
+    LabelName(String),
+    Label(String, String),
+}
+
+/// Options for filtering image list results
+#[derive(Default, Debug)]
+pub struct ImageListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl ImageListOptions {
+    pub fn builder() -> ImageListOptionsBuilder {
+        ImageListOptionsBuilder::default()
+    }
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `ImageListOptions`
+#[derive(Default)]
+pub struct ImageListOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
 }
 
 impl ImageListOptionsBuilder {
@@ -1318,18 +2291,24 @@ impl ImageListOptionsBuilder {
         &mut self,
         filters: Vec<ImageFilter>,
     ) -> &mut Self {
-        let mut param = HashMap::new();
+        let mut param = Filters::new();
         for f in filters {
             match f {
-                ImageFilter::Dangling => param.insert("dangling", vec![true.to_string()]),
-                ImageFilter::LabelName(n) => param.insert("label", vec![n]),
-                ImageFilter::Label(n, v) => param.insert("label", vec![format!("{}={}", n, v)]),
+                ImageFilter::Dangling => param.dangling(true),
+                ImageFilter::LabelName(n) => param.label(&n),
+                ImageFilter::Label(n, v) => param.label(&format!("{}={}", n, v)),
             };
         }
-        // structure is a a json encoded object mapping string keys to a list
-        // of string values
-        self.params
-            .insert("filters", serde_json::to_string(&param).unwrap());
+        self.filters(&param)
+    }
+
+    /// Only list images matching the given filters
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
         self
     }
 
@@ -1396,13 +2375,34 @@ impl RmContainerOptionsBuilder {
     }
 }
 
-/// Options for filtering networks list results
-#[derive(Default, Debug)]
-pub struct NetworkListOptions {
+/// Options for copying a file into a container with `Container::copy_file_into_with`
+#[derive(Clone, Debug)]
+pub struct CopyFileOptions {
+    pub(crate) mode: u32,
+    pub(crate) uid: u64,
+    pub(crate) gid: u64,
+    pub(crate) mtime: u64,
     params: HashMap<&'static str, String>,
 }
 
-impl NetworkListOptions {
+impl Default for CopyFileOptions {
+    fn default() -> Self {
+        CopyFileOptions {
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+            mtime: 0,
+            params: HashMap::new(),
+        }
+    }
+}
+
+impl CopyFileOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> CopyFileOptionsBuilder {
+        CopyFileOptionsBuilder::default()
+    }
+
     /// serialize options as a string. returns None if no options are defined
     pub fn serialize(&self) -> Option<String> {
         if self.params.is_empty() {
@@ -1417,218 +2417,884 @@ impl NetworkListOptions {
     }
 }
 
-/// Interface for creating new docker network
-#[derive(Serialize, Debug)]
-pub struct NetworkCreateOptions {
-    params: HashMap<&'static str, Value>,
+/// Builder interface for `CopyFileOptions`
+#[derive(Default)]
+pub struct CopyFileOptionsBuilder {
+    options: CopyFileOptions,
 }
 
-impl NetworkCreateOptions {
-    /// return a new instance of a builder for options
-    pub fn builder(name: &str) -> NetworkCreateOptionsBuilder {
-        NetworkCreateOptionsBuilder::new(name)
+impl CopyFileOptionsBuilder {
+    /// The file's permissions. Defaults to `0o644`
+    pub fn mode(
+        &mut self,
+        mode: u32,
+    ) -> &mut Self {
+        self.options.mode = mode;
+        self
     }
 
-    /// serialize options as a string. returns None if no options are defined
-    pub fn serialize(&self) -> Result<String> {
-        serde_json::to_string(&self.params).map_err(Error::from)
+    /// The user and group owning the file. Defaults to root
+    pub fn owner(
+        &mut self,
+        uid: u64,
+        gid: u64,
+    ) -> &mut Self {
+        self.options.uid = uid;
+        self.options.gid = gid;
+        self
     }
 
-    pub fn parse_from<'a, K, V>(
-        &self,
-        params: &'a HashMap<K, V>,
-        body: &mut serde_json::Map<String, Value>,
-    ) where
-        &'a HashMap<K, V>: IntoIterator,
-        K: ToString + Eq + Hash,
-        V: Serialize,
-    {
-        for (k, v) in params.iter() {
-            let key = k.to_string();
-            let value = serde_json::to_value(v).unwrap();
+    /// The file's modification time, in seconds since the unix epoch
+    pub fn mtime(
+        &mut self,
+        mtime: u64,
+    ) -> &mut Self {
+        self.options.mtime = mtime;
+        self
+    }
 
-            body.insert(key, value);
-        }
+    /// Fail rather than replace a directory in the container with the file,
+    /// or a file with a directory
+    pub fn no_overwrite_dir_non_dir(
+        &mut self,
+        no_overwrite: bool,
+    ) -> &mut Self {
+        self.options
+            .params
+            .insert("noOverwriteDirNonDir", no_overwrite.to_string());
+        self
+    }
+
+    /// Give the file the uid and gid of the container's user rather than those
+    /// set with `owner`
+    pub fn copy_uid_gid(
+        &mut self,
+        copy: bool,
+    ) -> &mut Self {
+        self.options.params.insert("copyUIDGID", copy.to_string());
+        self
+    }
+
+    pub fn build(&self) -> CopyFileOptions {
+        self.options.clone()
     }
 }
 
-#[derive(Default)]
-pub struct NetworkCreateOptionsBuilder {
-    params: HashMap<&'static str, Value>,
+/// Options for extracting an archive into a container with `Container::copy_to_with`
+#[derive(Default, Debug)]
+pub struct ArchiveOptions {
+    params: HashMap<&'static str, String>,
 }
 
-impl NetworkCreateOptionsBuilder {
-    pub(crate) fn new(name: &str) -> Self {
-        let mut params = HashMap::new();
-        params.insert("Name", json!(name));
-        NetworkCreateOptionsBuilder { params }
+impl ArchiveOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> ArchiveOptionsBuilder {
+        ArchiveOptionsBuilder::default()
     }
 
-    pub fn driver(
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `ArchiveOptions`
+#[derive(Default)]
+pub struct ArchiveOptionsBuilder {
+    params: HashMap<&'static str, String>,
+}
+
+impl ArchiveOptionsBuilder {
+    /// Fail rather than replace a directory in the container with a file from
+    /// the archive, or a file with a directory
+    pub fn no_overwrite_dir_non_dir(
         &mut self,
-        name: &str,
+        no_overwrite: bool,
     ) -> &mut Self {
-        if !name.is_empty() {
-            self.params.insert("Driver", json!(name));
-        }
+        self.params
+            .insert("noOverwriteDirNonDir", no_overwrite.to_string());
         self
     }
 
-    pub fn label(
+    /// Give the extracted files the uid and gid of the container's user
+    /// rather than those recorded in the archive
+    pub fn copy_uid_gid(
         &mut self,
-        labels: HashMap<String, String>,
+        copy: bool,
     ) -> &mut Self {
-        self.params.insert("Labels", json!(labels));
+        self.params.insert("copyUIDGID", copy.to_string());
         self
     }
 
-    pub fn build(&self) -> NetworkCreateOptions {
-        NetworkCreateOptions {
+    pub fn build(&self) -> ArchiveOptions {
+        ArchiveOptions {
             params: self.params.clone(),
         }
     }
 }
 
-/// Interface for connect container to network
-#[derive(Serialize, Debug)]
-pub struct ContainerConnectionOptions {
-    params: HashMap<&'static str, Value>,
+/// Options for filtering networks list results
+#[derive(Default, Debug)]
+pub struct NetworkListOptions {
+    params: HashMap<&'static str, String>,
 }
 
-impl ContainerConnectionOptions {
-    /// serialize options as a string. returns None if no options are defined
-    pub fn serialize(&self) -> Result<String> {
-        serde_json::to_string(&self.params).map_err(Error::from)
+impl NetworkListOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> NetworkListOptionsBuilder {
+        NetworkListOptionsBuilder::default()
     }
 
-    pub fn parse_from<'a, K, V>(
-        &self,
-        params: &'a HashMap<K, V>,
-        body: &mut BTreeMap<String, Value>,
-    ) where
-        &'a HashMap<K, V>: IntoIterator,
-        K: ToString + Eq + Hash,
-        V: Serialize,
-    {
-        for (k, v) in params.iter() {
-            let key = k.to_string();
-            let value = serde_json::to_value(v).unwrap();
-
-            body.insert(key, value);
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
         }
     }
-
-    /// return a new instance of a builder for options
-    pub fn builder(container_id: &str) -> ContainerConnectionOptionsBuilder {
-        ContainerConnectionOptionsBuilder::new(container_id)
-    }
 }
 
+/// Builder interface for `NetworkListOptions`
 #[derive(Default)]
-pub struct ContainerConnectionOptionsBuilder {
-    params: HashMap<&'static str, Value>,
+pub struct NetworkListOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
 }
 
-impl ContainerConnectionOptionsBuilder {
-    pub(crate) fn new(container_id: &str) -> Self {
-        let mut params = HashMap::new();
-        params.insert("Container", json!(container_id));
-        ContainerConnectionOptionsBuilder { params }
-    }
-
-    pub fn aliases(
+impl NetworkListOptionsBuilder {
+    /// Only list networks matching the given filters
+    pub fn filters(
         &mut self,
-        aliases: Vec<&str>,
+        filters: &Filters,
     ) -> &mut Self {
-        self.params
-            .insert("EndpointConfig", json!({ "Aliases": json!(aliases) }));
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
         self
     }
 
-    pub fn force(&mut self) -> &mut Self {
-        self.params.insert("Force", json!(true));
+    pub fn build(&self) -> NetworkListOptions {
+        NetworkListOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Options for choosing which unused networks to delete when pruning
+#[derive(Default, Debug)]
+pub struct NetworkPruneOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl NetworkPruneOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> NetworkPruneOptionsBuilder {
+        NetworkPruneOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `NetworkPruneOptions`
+#[derive(Default)]
+pub struct NetworkPruneOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
+}
+
+impl NetworkPruneOptionsBuilder {
+    /// Only delete networks matching the given filters, which may be `until`
+    /// and `label`
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
+        self
+    }
+
+    pub fn build(&self) -> NetworkPruneOptions {
+        NetworkPruneOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Options for filtering volume list results
+#[derive(Default, Debug)]
+pub struct VolumeListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl VolumeListOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> VolumeListOptionsBuilder {
+        VolumeListOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `VolumeListOptions`
+#[derive(Default)]
+pub struct VolumeListOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
+}
+
+impl VolumeListOptionsBuilder {
+    /// Only list volumes matching the given filters
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
+        self
+    }
+
+    pub fn build(&self) -> VolumeListOptions {
+        VolumeListOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Options for choosing which unused volumes to delete when pruning
+#[derive(Default, Debug)]
+pub struct VolumePruneOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl VolumePruneOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> VolumePruneOptionsBuilder {
+        VolumePruneOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Builder interface for `VolumePruneOptions`
+#[derive(Default)]
+pub struct VolumePruneOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
+}
+
+impl VolumePruneOptionsBuilder {
+    /// Only delete volumes matching the given filters, which may be `label`
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
+        self
+    }
+
+    pub fn build(&self) -> VolumePruneOptions {
+        VolumePruneOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Interface for creating new docker network
+#[derive(Serialize, Debug)]
+pub struct NetworkCreateOptions {
+    params: HashMap<&'static str, Value>,
+}
+
+impl NetworkCreateOptions {
+    /// return a new instance of a builder for options
+    pub fn builder(name: &str) -> NetworkCreateOptionsBuilder {
+        NetworkCreateOptionsBuilder::new(name)
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Result<String> {
+        serde_json::to_string(&self.params).map_err(Error::from)
+    }
+
+    pub fn parse_from<'a, K, V>(
+        &self,
+        params: &'a HashMap<K, V>,
+        body: &mut serde_json::Map<String, Value>,
+    ) -> Result<()>
+    where
+        &'a HashMap<K, V>: IntoIterator,
+        K: ToString + Eq + Hash,
+        V: Serialize,
+    {
+        for (k, v) in params.iter() {
+            let key = k.to_string();
+            let value = serde_json::to_value(v)?;
+
+            body.insert(key, value);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct NetworkCreateOptionsBuilder {
+    params: HashMap<&'static str, Value>,
+}
+
+impl NetworkCreateOptionsBuilder {
+    pub(crate) fn new(name: &str) -> Self {
+        let mut params = HashMap::new();
+        params.insert("Name", json!(name));
+        NetworkCreateOptionsBuilder { params }
+    }
+
+    pub fn driver(
+        &mut self,
+        name: &str,
+    ) -> &mut Self {
+        if !name.is_empty() {
+            self.params.insert("Driver", json!(name));
+        }
+        self
+    }
+
+    pub fn label(
+        &mut self,
+        labels: HashMap<String, String>,
+    ) -> &mut Self {
+        self.params.insert("Labels", json!(labels));
+        self
+    }
+
+    pub fn build(&self) -> NetworkCreateOptions {
+        NetworkCreateOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Interface for connect container to network
+#[derive(Serialize, Debug)]
+pub struct ContainerConnectionOptions {
+    params: HashMap<&'static str, Value>,
+}
+
+impl ContainerConnectionOptions {
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Result<String> {
+        serde_json::to_string(&self.params).map_err(Error::from)
+    }
+
+    pub fn parse_from<'a, K, V>(
+        &self,
+        params: &'a HashMap<K, V>,
+        body: &mut BTreeMap<String, Value>,
+    ) -> Result<()>
+    where
+        &'a HashMap<K, V>: IntoIterator,
+        K: ToString + Eq + Hash,
+        V: Serialize,
+    {
+        for (k, v) in params.iter() {
+            let key = k.to_string();
+            let value = serde_json::to_value(v)?;
+
+            body.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// return a new instance of a builder for options
+    pub fn builder(container_id: &str) -> ContainerConnectionOptionsBuilder {
+        ContainerConnectionOptionsBuilder::new(container_id)
+    }
+}
+
+#[derive(Default)]
+pub struct ContainerConnectionOptionsBuilder {
+    params: HashMap<&'static str, Value>,
+}
+
+impl ContainerConnectionOptionsBuilder {
+    pub(crate) fn new(container_id: &str) -> Self {
+        let mut params = HashMap::new();
+        params.insert("Container", json!(container_id));
+        ContainerConnectionOptionsBuilder { params }
+    }
+
+    pub fn aliases(
+        &mut self,
+        aliases: Vec<&str>,
+    ) -> &mut Self {
+        self.params
+            .insert("EndpointConfig", json!({ "Aliases": json!(aliases) }));
+        self
+    }
+
+    pub fn force(&mut self) -> &mut Self {
+        self.params.insert("Force", json!(true));
+        self
+    }
+
+    pub fn build(&self) -> ContainerConnectionOptions {
+        ContainerConnectionOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Interface for creating volumes
+#[derive(Serialize, Debug)]
+pub struct VolumeCreateOptions {
+    params: HashMap<&'static str, Value>,
+}
+
+impl VolumeCreateOptions {
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Result<String> {
+        serde_json::to_string(&self.params).map_err(Error::from)
+    }
+
+    pub fn parse_from<'a, K, V>(
+        &self,
+        params: &'a HashMap<K, V>,
+        body: &mut BTreeMap<String, Value>,
+    ) -> Result<()>
+    where
+        &'a HashMap<K, V>: IntoIterator,
+        K: ToString + Eq + Hash,
+        V: Serialize,
+    {
+        for (k, v) in params.iter() {
+            let key = k.to_string();
+            let value = serde_json::to_value(v)?;
+
+            body.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// return a new instance of a builder for options
+    pub fn builder() -> VolumeCreateOptionsBuilder {
+        VolumeCreateOptionsBuilder::new()
+    }
+}
+
+#[derive(Default)]
+pub struct VolumeCreateOptionsBuilder {
+    params: HashMap<&'static str, Value>,
+}
+
+impl VolumeCreateOptionsBuilder {
+    pub(crate) fn new() -> Self {
+        let params = HashMap::new();
+        VolumeCreateOptionsBuilder { params }
+    }
+
+    pub fn name(
+        &mut self,
+        name: &str,
+    ) -> &mut Self {
+        self.params.insert("Name", json!(name));
+        self
+    }
+
+    /// The volume driver to use. Defaults to `local`
+    pub fn driver(
+        &mut self,
+        driver: &str,
+    ) -> &mut Self {
+        self.params.insert("Driver", json!(driver));
+        self
+    }
+
+    pub fn labels(
+        &mut self,
+        labels: &HashMap<&str, &str>,
+    ) -> &mut Self {
+        self.params.insert("Labels", json!(labels));
+        self
+    }
+
+    pub fn build(&self) -> VolumeCreateOptions {
+        VolumeCreateOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// The largest secret the daemon accepts, in bytes
+pub const MAX_SECRET_SIZE: usize = 500 * 1024;
+
+/// Interface for creating swarm secrets
+#[derive(Serialize, Debug)]
+pub struct SecretCreateOptions {
+    params: HashMap<&'static str, Value>,
+    #[serde(skip)]
+    size: usize,
+}
+
+impl SecretCreateOptions {
+    /// return a new instance of a builder for options
+    pub fn builder(name: &str) -> SecretCreateOptionsBuilder {
+        SecretCreateOptionsBuilder::new(name)
+    }
+
+    /// serialize options as a string, failing if the secret is larger than
+    /// `MAX_SECRET_SIZE`
+    pub fn serialize(&self) -> Result<String> {
+        if self.size > MAX_SECRET_SIZE {
+            return Err(Error::InvalidInput(format!(
+                "secret is {} bytes, but may be at most {} bytes",
+                self.size, MAX_SECRET_SIZE
+            )));
+        }
+        serde_json::to_string(&self.params).map_err(Error::from)
+    }
+}
+
+pub struct SecretCreateOptionsBuilder {
+    params: HashMap<&'static str, Value>,
+    size: usize,
+}
+
+impl SecretCreateOptionsBuilder {
+    pub(crate) fn new(name: &str) -> Self {
+        let mut params = HashMap::new();
+        params.insert("Name", json!(name));
+        SecretCreateOptionsBuilder { params, size: 0 }
+    }
+
+    /// The secret's content, which is base64 encoded for the daemon
+    pub fn data(
+        &mut self,
+        data: &[u8],
+    ) -> &mut Self {
+        self.params.insert("Data", json!(base64::encode(data)));
+        self.size = data.len();
+        self
+    }
+
+    pub fn labels(
+        &mut self,
+        labels: &HashMap<&str, &str>,
+    ) -> &mut Self {
+        self.params.insert("Labels", json!(labels));
+        self
+    }
+
+    pub fn build(&self) -> SecretCreateOptions {
+        SecretCreateOptions {
+            params: self.params.clone(),
+            size: self.size,
+        }
+    }
+}
+
+/// Options for filtering plugin list results
+#[derive(Default, Debug)]
+pub struct PluginListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl PluginListOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> PluginListOptionsBuilder {
+        PluginListOptionsBuilder::default()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+}
+
+/// Filter options for plugin listings
+pub enum PluginFilter {
+    /// Plugin capability, e.g. `volumedriver` or `authz`
+    Capability(String),
+    Enabled(bool),
+}
+
+/// Builder interface for `PluginListOptions`
+#[derive(Default)]
+pub struct PluginListOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    filters: Filters,
+}
+
+impl PluginListOptionsBuilder {
+    pub fn filter(
+        &mut self,
+        filters: Vec<PluginFilter>,
+    ) -> &mut Self {
+        let mut param = Filters::new();
+        for f in filters {
+            match f {
+                PluginFilter::Capability(c) => param.add("capability", c),
+                PluginFilter::Enabled(e) => param.add("enable", e.to_string()),
+            };
+        }
+        self.filters(&param)
+    }
+
+    /// Only list plugins matching the given filters
+    pub fn filters(
+        &mut self,
+        filters: &Filters,
+    ) -> &mut Self {
+        self.filters.extend(filters);
+        self.params.insert("filters", self.filters.serialize());
+        self
+    }
+
+    pub fn build(&self) -> PluginListOptions {
+        PluginListOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Options for installing a plugin from a registry
+#[derive(Default, Debug)]
+pub struct PluginInstallOptions {
+    auth: Option<RegistryAuth>,
+    params: HashMap<&'static str, String>,
+    privileges: Vec<PluginPrivilege>,
+}
+
+impl PluginInstallOptions {
+    /// return a new instance of a builder for options
+    /// remote is the registry reference of the plugin to install, e.g. `vieux/sshfs:latest`
+    pub fn builder<R>(remote: R) -> PluginInstallOptionsBuilder
+    where
+        R: Into<String>,
+    {
+        PluginInstallOptionsBuilder::new(remote)
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish(),
+            )
+        }
+    }
+
+    /// serialize the granted privileges as the JSON request body
+    pub fn serialize_privileges(&self) -> Result<String> {
+        serde_json::to_string(&self.privileges).map_err(Error::from)
+    }
+
+    pub(crate) fn auth_header(&self) -> Option<String> {
+        self.auth.clone().map(|a| a.serialize())
+    }
+}
+
+/// Builder interface for `PluginInstallOptions`
+#[derive(Default)]
+pub struct PluginInstallOptionsBuilder {
+    auth: Option<RegistryAuth>,
+    params: HashMap<&'static str, String>,
+    privileges: Vec<PluginPrivilege>,
+}
+
+impl PluginInstallOptionsBuilder {
+    pub(crate) fn new<R>(remote: R) -> Self
+    where
+        R: Into<String>,
+    {
+        let mut params = HashMap::new();
+        params.insert("remote", remote.into());
+        PluginInstallOptionsBuilder {
+            params,
+            ..Default::default()
+        }
+    }
+
+    /// Local name of the installed plugin, defaults to the remote reference
+    pub fn name<N>(
+        &mut self,
+        name: N,
+    ) -> &mut Self
+    where
+        N: Into<String>,
+    {
+        self.params.insert("name", name.into());
+        self
+    }
+
+    /// Privileges granted to the plugin. These are usually the privileges
+    /// returned by `Plugins::privileges` for the same remote, after they
+    /// have been accepted by the user.
+    pub fn privileges(
+        &mut self,
+        privileges: Vec<PluginPrivilege>,
+    ) -> &mut Self {
+        self.privileges = privileges;
+        self
+    }
+
+    pub fn auth(
+        &mut self,
+        auth: RegistryAuth,
+    ) -> &mut Self {
+        self.auth = Some(auth);
         self
     }
 
-    pub fn build(&self) -> ContainerConnectionOptions {
-        ContainerConnectionOptions {
+    pub fn build(&mut self) -> PluginInstallOptions {
+        PluginInstallOptions {
+            auth: self.auth.take(),
             params: self.params.clone(),
+            privileges: self.privileges.clone(),
         }
     }
 }
 
-/// Interface for creating volumes
-#[derive(Serialize, Debug)]
-pub struct VolumeCreateOptions {
-    params: HashMap<&'static str, Value>,
+/// Object types which may be requested from the data usage endpoint
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DataUsageType {
+    Container,
+    Image,
+    Volume,
+    BuildCache,
 }
 
-impl VolumeCreateOptions {
-    /// serialize options as a string. returns None if no options are defined
-    pub fn serialize(&self) -> Result<String> {
-        serde_json::to_string(&self.params).map_err(Error::from)
+impl DataUsageType {
+    fn as_str(self) -> &'static str {
+        match self {
+            DataUsageType::Container => "container",
+            DataUsageType::Image => "image",
+            DataUsageType::Volume => "volume",
+            DataUsageType::BuildCache => "build-cache",
+        }
     }
+}
 
-    pub fn parse_from<'a, K, V>(
-        &self,
-        params: &'a HashMap<K, V>,
-        body: &mut BTreeMap<String, Value>,
-    ) where
-        &'a HashMap<K, V>: IntoIterator,
-        K: ToString + Eq + Hash,
-        V: Serialize,
-    {
-        for (k, v) in params.iter() {
-            let key = k.to_string();
-            let value = serde_json::to_value(v).unwrap();
+/// Options for controlling data usage results
+#[derive(Default, Debug)]
+pub struct DataUsageOptions {
+    types: Vec<DataUsageType>,
+}
 
-            body.insert(key, value);
-        }
+impl DataUsageOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> DataUsageOptionsBuilder {
+        DataUsageOptionsBuilder::default()
     }
 
-    /// return a new instance of a builder for options
-    pub fn builder() -> VolumeCreateOptionsBuilder {
-        VolumeCreateOptionsBuilder::new()
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.types.is_empty() {
+            None
+        } else {
+            Some(
+                form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(self.types.iter().map(|t| ("type", t.as_str())))
+                    .finish(),
+            )
+        }
     }
 }
 
+/// Builder interface for `DataUsageOptions`
 #[derive(Default)]
-pub struct VolumeCreateOptionsBuilder {
-    params: HashMap<&'static str, Value>,
+pub struct DataUsageOptionsBuilder {
+    types: Vec<DataUsageType>,
 }
 
-impl VolumeCreateOptionsBuilder {
-    pub(crate) fn new() -> Self {
-        let params = HashMap::new();
-        VolumeCreateOptionsBuilder { params }
-    }
-
-    pub fn name(
-        &mut self,
-        name: &str,
-    ) -> &mut Self {
-        self.params.insert("Name", json!(name));
-        self
-    }
-
-    pub fn labels(
+impl DataUsageOptionsBuilder {
+    /// Only compute usage for the given object types.
+    ///
+    /// Requires API version 1.42 or newer. Older daemons ignore this option
+    /// and always report every object type.
+    pub fn types(
         &mut self,
-        labels: &HashMap<&str, &str>,
+        types: Vec<DataUsageType>,
     ) -> &mut Self {
-        self.params.insert("Labels", json!(labels));
+        self.types.extend(types);
         self
     }
 
-    pub fn build(&self) -> VolumeCreateOptions {
-        VolumeCreateOptions {
-            params: self.params.clone(),
+    pub fn build(&self) -> DataUsageOptions {
+        DataUsageOptions {
+            types: self.types.clone(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ContainerOptionsBuilder, LogsOptionsBuilder, RegistryAuth};
+    use super::{
+        ArchiveOptions, BuildOptions, ContainerFilter, ContainerListOptions,
+        ContainerOptionsBuilder, ContainerPruneOptions, CopyFileOptions, DataUsageOptions,
+        DataUsageType, EventFilter, EventsOptions, Filters, ImagePruneOptions, Isolation,
+        LogsOptionsBuilder, NetworkPruneOptions, PluginFilter, PluginListOptions, PullManyOptions,
+        RegistryAuth, SecretCreateOptions, Tail, VolumePruneOptions, MAX_SECRET_SIZE,
+    };
+    use crate::{models::EventType, Error};
+    use std::collections::HashMap;
 
     #[test]
     fn container_options_simple() {
@@ -1667,6 +3333,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn container_options_runtime() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .runtime("runsc")
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{"Runtime":"runsc"},"Image":"test_image"}"#,
+            options.serialize().unwrap()
+        );
+    }
+
+    #[test]
+    fn container_options_dns() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .dns(vec!["10.0.0.2"])
+            .dns_search(vec!["internal"])
+            .dns_options(vec!["ndots:2"])
+            .extra_hosts(vec!["host.docker.internal:host-gateway"])
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{"Dns":["10.0.0.2"],"DnsOptions":["ndots:2"],"DnsSearch":["internal"],"ExtraHosts":["host.docker.internal:host-gateway"]},"Image":"test_image"}"#,
+            options.serialize().unwrap()
+        );
+    }
+
+    #[test]
+    fn container_options_namespaces() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .ipc_mode("host")
+            .pid_mode("container:db")
+            .uts_mode("host")
+            .userns_mode("host")
+            .group_add(vec!["audio", "1001"])
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{"GroupAdd":["audio","1001"],"IpcMode":"host","PidMode":"container:db","UTSMode":"host","UsernsMode":"host"},"Image":"test_image"}"#,
+            options.serialize().unwrap()
+        );
+    }
+
+    #[test]
+    fn container_options_resources_and_logging() {
+        let mut log_options = HashMap::new();
+        log_options.insert("max-size", "10m");
+        let options = ContainerOptionsBuilder::new("test_image")
+            .user("1000:1000")
+            .stdin_once(true)
+            .memory(1 << 30)
+            .memory_swap(-1)
+            .cpus(1.5)
+            .cap_drop(vec!["ALL"])
+            .log_driver("json-file")
+            .log_options(&log_options)
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{"CapDrop":["ALL"],"LogConfig":{"Config":{"max-size":"10m"},"Type":"json-file"},"Memory":1073741824,"MemorySwap":-1,"NanoCpus":1500000000},"Image":"test_image","StdinOnce":true,"User":"1000:1000"}"#,
+            options.serialize().unwrap()
+        );
+    }
+
+    #[test]
+    fn container_options_storage_and_devices() {
+        let mut storage = HashMap::new();
+        storage.insert("size", "10G");
+        let options = ContainerOptionsBuilder::new("test_image")
+            .storage_opt(&storage)
+            .device_cgroup_rules(vec!["c 189:* rmw"])
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{"DeviceCgroupRules":["c 189:* rmw"],"StorageOpt":{"size":"10G"}},"Image":"test_image"}"#,
+            options.serialize().unwrap()
+        );
+    }
+
+    #[test]
+    fn isolation() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .isolation(Isolation::HyperV)
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{"Isolation":"hyperv"},"Image":"test_image"}"#,
+            options.serialize().unwrap()
+        );
+
+        let options = BuildOptions::builder(".")
+            .isolation(Isolation::Process)
+            .build();
+        assert_eq!(Some("isolation=process".to_owned()), options.serialize());
+    }
+
     #[test]
     fn container_options_expose() {
         let options = ContainerOptionsBuilder::new("test_image")
@@ -1707,6 +3463,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn container_options_network() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .network("app_default", vec!["web"])
+            .build();
+        assert_eq!(
+            r#"{"HostConfig":{},"Image":"test_image","NetworkingConfig":{"EndpointsConfig":{"app_default":{"Aliases":["web"]}}}}"#,
+            options.serialize().unwrap()
+        );
+    }
+
     /// Test container options that are nested 3 levels deep.
     #[test]
     fn container_options_nested() {
@@ -1742,6 +3509,43 @@ mod tests {
         );
     }
 
+    /// Test options the daemon would reject are caught
+    #[test]
+    fn container_options_validation() {
+        let invalid = |options: &ContainerOptionsBuilder| match options.build().serialize() {
+            Err(Error::InvalidInput(_)) => true,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => false,
+        };
+
+        assert!(!invalid(
+            ContainerOptionsBuilder::new("test_image").name("/web-1.0_a")
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image").name("-web")
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image").name("web server")
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image").memory(1024)
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image").expose(0, "tcp", 8080)
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image").expose(80, "tcp", 70000)
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image").publish(80, "http")
+        ));
+        assert!(invalid(
+            ContainerOptionsBuilder::new("test_image")
+                .auto_remove(true)
+                .restart_policy("always", 0)
+        ));
+    }
+
     /// Test registry auth with token
     #[test]
     fn registry_auth_token() {
@@ -1782,6 +3586,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_options_registry_config() {
+        assert_eq!(
+            None,
+            BuildOptions::builder(".").build().registry_config_header()
+        );
+
+        let options = BuildOptions::builder(".")
+            .registry_auth("registry.example.org", RegistryAuth::token("abc"))
+            .registry_auth(
+                "https://index.docker.io/v1/",
+                RegistryAuth::builder()
+                    .username("user_abc")
+                    .password("password_abc")
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            Some(base64::encode(
+                r#"{"https://index.docker.io/v1/":{"username":"user_abc","password":"password_abc"},"registry.example.org":{"identitytoken":"abc"}}"#
+            )),
+            options.registry_config_header()
+        );
+    }
+
     #[cfg(feature = "chrono")]
     #[test]
     fn logs_options() {
@@ -1793,7 +3622,7 @@ mod tests {
             .stdout(true)
             .stderr(true)
             .timestamps(true)
-            .tail("all")
+            .tail(Tail::All)
             .since(&since)
             .build();
 
@@ -1815,7 +3644,7 @@ mod tests {
             .stdout(true)
             .stderr(true)
             .timestamps(true)
-            .tail("all")
+            .tail(Tail::Lines(10))
             .since(2_147_483_647)
             .build();
 
@@ -1825,7 +3654,238 @@ mod tests {
         assert!(serialized.contains("stdout=true"));
         assert!(serialized.contains("stderr=true"));
         assert!(serialized.contains("timestamps=true"));
-        assert!(serialized.contains("tail=all"));
+        assert!(serialized.contains("tail=10"));
         assert!(serialized.contains("since=2147483647"));
     }
+
+    #[test]
+    fn data_usage_options() {
+        assert_eq!(None, DataUsageOptions::default().serialize());
+
+        let options = DataUsageOptions::builder()
+            .types(vec![DataUsageType::Image, DataUsageType::BuildCache])
+            .build();
+
+        assert_eq!(
+            Some("type=image&type=build-cache".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn events_options_type_filter() {
+        let options = EventsOptions::builder()
+            .filter(vec![
+                EventFilter::Type(EventType::Container),
+                EventFilter::Type(EventType::Network),
+            ])
+            .build();
+
+        assert_eq!(
+            Some("filters=%7B%22type%22%3A%5B%22container%22%2C%22network%22%5D%7D".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn events_options_since_until() {
+        let since = chrono::DateTime::<chrono::Utc>::from_utc(
+            chrono::NaiveDateTime::from_timestamp(1_500_000_000, 0),
+            chrono::Utc,
+        );
+        let until = chrono::DateTime::<chrono::Utc>::from_utc(
+            chrono::NaiveDateTime::from_timestamp(1_500_000_060, 500),
+            chrono::Utc,
+        );
+
+        let serialized = EventsOptions::builder()
+            .since(&since)
+            .until(&until)
+            .build()
+            .serialize()
+            .unwrap();
+
+        assert!(serialized.contains("since=1500000000.000000000"));
+        assert!(serialized.contains("until=1500000060.000000500"));
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[test]
+    fn events_options_since_until() {
+        let serialized = EventsOptions::builder()
+            .since(&1_500_000_000)
+            .until(&1_500_000_060)
+            .build()
+            .serialize()
+            .unwrap();
+
+        assert!(serialized.contains("since=1500000000"));
+        assert!(serialized.contains("until=1500000060"));
+    }
+
+    #[test]
+    fn events_options_since_until_timestamps() {
+        let serialized = EventsOptions::builder()
+            .since_timestamp(1_500_000_000)
+            .until_timestamp(1_500_000_060)
+            .build()
+            .serialize()
+            .unwrap();
+
+        assert!(serialized.contains("since=1500000000"));
+        assert!(serialized.contains("until=1500000060"));
+    }
+
+    #[test]
+    fn filters_collect_values_by_key() {
+        let mut filters = Filters::new();
+        filters.label("a=1").label("b").status("running");
+        assert_eq!(
+            r#"{"label":["a=1","b"],"status":["running"]}"#,
+            filters.serialize()
+        );
+    }
+
+    #[test]
+    fn container_list_options_filters() {
+        let options = ContainerListOptions::builder()
+            .filter(vec![
+                ContainerFilter::LabelName("a".into()),
+                ContainerFilter::LabelName("b".into()),
+            ])
+            .filters(Filters::new().name("web"))
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22label%22%3A%5B%22a%22%2C%22b%22%5D%2C%22name%22%3A%5B%22web%22%5D%7D"
+                    .to_string()
+            ),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn container_prune_options_filters() {
+        assert_eq!(None, ContainerPruneOptions::builder().build().serialize());
+
+        let options = ContainerPruneOptions::builder()
+            .filters(Filters::new().until("24h").label("ci"))
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22label%22%3A%5B%22ci%22%5D%2C%22until%22%3A%5B%2224h%22%5D%7D"
+                    .to_string()
+            ),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn image_prune_options_filters() {
+        let options = ImagePruneOptions::builder()
+            .filters(Filters::new().dangling(false).until("2020-01-01T00:00:00Z"))
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22dangling%22%3A%5B%22false%22%5D%2C%22until%22%3A%5B%222020-01-01T00%3A00%3A00Z%22%5D%7D"
+                    .to_string()
+            ),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn network_prune_options_filters() {
+        assert_eq!(None, NetworkPruneOptions::builder().build().serialize());
+
+        let options = NetworkPruneOptions::builder()
+            .filters(Filters::new().until("10m"))
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22until%22%3A%5B%2210m%22%5D%7D".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn volume_prune_options_filters() {
+        let options = VolumePruneOptions::builder()
+            .filters(Filters::new().label("keep!=true"))
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22label%22%3A%5B%22keep%21%3Dtrue%22%5D%7D".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn plugin_list_options_filter() {
+        let options = PluginListOptions::builder()
+            .filter(vec![PluginFilter::Capability("volumedriver".into())])
+            .build();
+
+        assert_eq!(
+            Some("filters=%7B%22capability%22%3A%5B%22volumedriver%22%5D%7D".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn pull_many_options() {
+        assert_eq!(3, PullManyOptions::default().parallelism);
+        let options = PullManyOptions::builder()
+            .parallelism(0)
+            .auth(RegistryAuth::token("t"))
+            .build();
+        assert_eq!(1, options.parallelism);
+        assert!(options.auth.is_some());
+    }
+
+    #[test]
+    fn copy_file_options() {
+        let defaults = CopyFileOptions::default();
+        assert_eq!(0o644, defaults.mode);
+        assert_eq!((0, 0), (defaults.uid, defaults.gid));
+        assert_eq!(None, defaults.serialize());
+
+        let options = CopyFileOptions::builder()
+            .mode(0o600)
+            .owner(1000, 1000)
+            .no_overwrite_dir_non_dir(true)
+            .build();
+        assert_eq!(0o600, options.mode);
+        assert_eq!((1000, 1000), (options.uid, options.gid));
+        assert_eq!(
+            Some("noOverwriteDirNonDir=true".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn archive_options() {
+        assert_eq!(None, ArchiveOptions::default().serialize());
+
+        let options = ArchiveOptions::builder().copy_uid_gid(true).build();
+        assert_eq!(Some("copyUIDGID=true".to_string()), options.serialize());
+    }
+
+    #[test]
+    fn secret_create_options() {
+        let options = SecretCreateOptions::builder("token")
+            .data(b"hunter2")
+            .build();
+        let serialized: serde_json::Value =
+            serde_json::from_str(&options.serialize().unwrap()).unwrap();
+        assert_eq!("token", serialized["Name"]);
+        assert_eq!("aHVudGVyMg==", serialized["Data"]);
+
+        let oversized = SecretCreateOptions::builder("blob")
+            .data(&vec![0; MAX_SECRET_SIZE + 1])
+            .build();
+        match oversized.serialize().unwrap_err() {
+            Error::InvalidInput(_) => (),
+            e => panic!("unexpected error {}", e),
+        }
+    }
 }
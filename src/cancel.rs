@@ -0,0 +1,136 @@
+//! Aborting long running operations, such as builds, pulls and exports
+
+use crate::Error;
+use futures::{task::AtomicTask, Async, Poll, Stream};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Aborts the operation it was created alongside. Cancelling drops the
+/// operation's stream, closing its connection to the daemon, and fails the
+/// stream with `Error::Cancelled` on its next poll
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle {
+    state: Arc<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    cancelled: AtomicBool,
+    task: AtomicTask,
+}
+
+impl CancelHandle {
+    /// Cancels the operation, waking whichever task is waiting on it
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.task.notify();
+    }
+
+    /// Whether the operation has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// A stream which fails with `Error::Cancelled` once its `CancelHandle` is
+/// cancelled, so that a cancelled operation can't be mistaken for one which
+/// finished
+#[derive(Debug)]
+pub struct Cancellable<S> {
+    stream: Option<S>,
+    handle: CancelHandle,
+    /// Whether the cancellation has been reported, after which the stream ends
+    reported: bool,
+}
+
+/// Wraps `stream` so it can be cancelled from elsewhere
+pub fn cancellable<S>(stream: S) -> (Cancellable<S>, CancelHandle)
+where
+    S: Stream<Error = Error>,
+{
+    let handle = CancelHandle::default();
+    let cancellable = Cancellable {
+        stream: Some(stream),
+        handle: handle.clone(),
+        reported: false,
+    };
+    (cancellable, handle)
+}
+
+impl<S> Stream for Cancellable<S>
+where
+    S: Stream<Error = Error>,
+{
+    type Item = S::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, Error> {
+        self.handle.state.task.register();
+        if self.handle.is_cancelled() && self.stream.is_some() {
+            // dropping the stream closes its connection to the daemon
+            self.stream = None;
+        }
+        match self.stream {
+            Some(ref mut stream) => stream.poll(),
+            None if self.reported => Ok(Async::Ready(None)),
+            None => {
+                self.reported = true;
+                Err(Error::Cancelled)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cancellable;
+    use crate::Error;
+    use futures::{stream, Async, Future, Stream};
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn cancelling_fails_the_stream() {
+        let (stream, handle) = cancellable(stream::repeat::<_, Error>(1));
+        let mut items = stream.wait();
+        assert_eq!(1, items.next().unwrap().unwrap());
+        handle.cancel();
+        match items.next() {
+            Some(Err(Error::Cancelled)) => (),
+            other => panic!("expected the stream to be cancelled, got {:?}", other),
+        }
+        assert!(items.next().is_none());
+        assert!(handle.is_cancelled());
+    }
+
+    #[test]
+    fn cancelling_wakes_a_waiting_stream() {
+        let pending = stream::poll_fn(|| Ok::<Async<Option<()>>, Error>(Async::NotReady));
+        let (stream, handle) = cancellable(pending);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            handle.cancel();
+        });
+        match stream.collect().wait() {
+            Err(Error::Cancelled) => (),
+            other => panic!("expected the stream to be cancelled, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cancelled_exports_are_not_written_out_as_complete() {
+        let (stream, handle) =
+            cancellable(stream::iter_ok::<_, Error>(vec![&b"ab"[..], &b"cd"[..]]));
+        let mut written = Vec::new();
+        let result = stream
+            .for_each(|chunk| {
+                written.extend_from_slice(chunk);
+                handle.cancel();
+                Ok(())
+            })
+            .wait();
+        assert!(result.is_err());
+        assert_eq!(b"ab", &written[..]);
+    }
+}
@@ -0,0 +1,491 @@
+//! Decoding of streamed responses, and limits on the lines and frames decoded
+
+use crate::Error;
+use bytes::BytesMut;
+use futures::{try_ready, Async, Poll, Stream};
+use hyper::Chunk;
+use serde::de::DeserializeOwned;
+use std::{marker::PhantomData, str};
+use tokio_codec::Decoder;
+
+/// Bounds on how much of a streamed response is buffered to decode a single
+/// line, such as a stats or events record, or a single frame of container
+/// output
+///
+/// ```no_run
+/// use shiplift::{codec::StreamLimits, Docker};
+///
+/// let docker = Docker::new().with_stream_limits(
+///     StreamLimits::default()
+///         .max_frame_length(1 << 20)
+///         .truncate(true),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StreamLimits {
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) max_frame_length: Option<usize>,
+    pub(crate) truncate: bool,
+}
+
+impl StreamLimits {
+    /// The longest line of a json stream, such as stats or events, in bytes.
+    /// Unlimited by default
+    pub fn max_line_length(
+        mut self,
+        length: usize,
+    ) -> StreamLimits {
+        self.max_line_length = Some(length);
+        self
+    }
+
+    /// The longest frame of container output, such as logs, in bytes.
+    /// Unlimited by default
+    pub fn max_frame_length(
+        mut self,
+        length: usize,
+    ) -> StreamLimits {
+        self.max_frame_length = Some(length);
+        self
+    }
+
+    /// Cut oversized lines and frames down to the limit, dropping the rest,
+    /// rather than failing the stream with `Error::TooLong`. Defaults to false
+    pub fn truncate(
+        mut self,
+        truncate: bool,
+    ) -> StreamLimits {
+        self.truncate = truncate;
+        self
+    }
+}
+
+/// Decodes newline delimited lines, enforcing a maximum length
+pub(crate) struct LinesDecoder {
+    max_length: Option<usize>,
+    truncate: bool,
+    /// Where to resume searching for a newline
+    next_index: usize,
+    /// Whether the rest of a truncated line is being dropped
+    discarding: bool,
+}
+
+impl LinesDecoder {
+    pub(crate) fn new(limits: &StreamLimits) -> Self {
+        LinesDecoder {
+            max_length: limits.max_line_length,
+            truncate: limits.truncate,
+            next_index: 0,
+            discarding: false,
+        }
+    }
+
+    fn line(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Option<String>, Error> {
+        let bytes = match bytes.split_last() {
+            Some((b'\r', rest)) => rest,
+            _ => bytes,
+        };
+        let bytes = match self.max_length {
+            Some(max) if bytes.len() > max && self.truncate => &bytes[..max],
+            Some(max) if bytes.len() > max => return Err(Error::TooLong(max)),
+            _ => bytes,
+        };
+        match str::from_utf8(bytes) {
+            Ok(line) => Ok(Some(line.to_owned())),
+            // a truncated line may end partway through a character
+            Err(e) if self.truncate && e.error_len().is_none() => Ok(Some(
+                String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned(),
+            )),
+            Err(e) => Err(Error::InvalidResponse(e.to_string())),
+        }
+    }
+}
+
+impl Decoder for LinesDecoder {
+    type Item = String;
+    type Error = Error;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<String>, Error> {
+        loop {
+            let newline = src[self.next_index..].iter().position(|b| *b == b'\n');
+            match newline {
+                Some(offset) => {
+                    let end = self.next_index + offset;
+                    let line = src.split_to(end + 1);
+                    self.next_index = 0;
+                    if self.discarding {
+                        self.discarding = false;
+                        continue;
+                    }
+                    return self.line(&line[..end]);
+                }
+                None if self.discarding => {
+                    src.clear();
+                    return Ok(None);
+                }
+                None => match self.max_length {
+                    // a newline can be beyond the limit when the line ends in `\r`
+                    Some(max) if src.len() > max + 1 => {
+                        if !self.truncate {
+                            return Err(Error::TooLong(max));
+                        }
+                        let line = src.split_to(max);
+                        self.next_index = 0;
+                        self.discarding = true;
+                        return self.line(&line[..]);
+                    }
+                    _ => {
+                        self.next_index = src.len();
+                        return Ok(None);
+                    }
+                },
+            }
+        }
+    }
+
+    fn decode_eof(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<String>, Error> {
+        if let Some(line) = self.decode(src)? {
+            return Ok(Some(line));
+        }
+        self.next_index = 0;
+        if src.is_empty() || self.discarding {
+            src.clear();
+            return Ok(None);
+        }
+        let line = src.take();
+        self.line(&line[..])
+    }
+}
+
+/// Decodes the elements of a json array one at a time, as they arrive,
+/// rather than buffering the whole array
+pub(crate) struct JsonArrayDecoder<T> {
+    state: ArrayState,
+    /// How deeply nested the scan is within the current element
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// How much of the current element has been scanned
+    scanned: usize,
+    element: PhantomData<fn() -> T>,
+}
+
+#[derive(PartialEq)]
+enum ArrayState {
+    Opening,
+    Elements,
+    Closed,
+}
+
+impl<T> JsonArrayDecoder<T> {
+    pub(crate) fn new() -> Self {
+        JsonArrayDecoder {
+            state: ArrayState::Opening,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            scanned: 0,
+            element: PhantomData,
+        }
+    }
+
+    /// Scans the current element for the `,` or `]` ending it
+    fn element_end(
+        &mut self,
+        src: &[u8],
+    ) -> Option<usize> {
+        for (i, &b) in src.iter().enumerate().skip(self.scanned) {
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => (),
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b',' | b']' if self.depth == 0 => {
+                    self.scanned = 0;
+                    return Some(i);
+                }
+                b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+                _ => (),
+            }
+        }
+        self.scanned = src.len();
+        None
+    }
+}
+
+fn skip_while(
+    src: &mut BytesMut,
+    skip: impl Fn(u8) -> bool,
+) {
+    let skipped = src.iter().take_while(|b| skip(**b)).count();
+    src.split_to(skipped);
+}
+
+impl<T> Decoder for JsonArrayDecoder<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = T;
+    type Error = Error;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<T>, Error> {
+        match self.state {
+            ArrayState::Opening => {
+                skip_while(src, |b| b.is_ascii_whitespace());
+                match src.first() {
+                    None => return Ok(None),
+                    Some(b'[') => {
+                        src.split_to(1);
+                        self.state = ArrayState::Elements;
+                    }
+                    Some(_) => {
+                        return Err(Error::InvalidResponse("expected a json array".to_owned()))
+                    }
+                }
+            }
+            ArrayState::Closed => {
+                src.clear();
+                return Ok(None);
+            }
+            ArrayState::Elements => (),
+        }
+        if self.scanned == 0 {
+            skip_while(src, |b| b.is_ascii_whitespace() || b == b',');
+            if src.first() == Some(&b']') {
+                src.clear();
+                self.state = ArrayState::Closed;
+                return Ok(None);
+            }
+        }
+        match self.element_end(&src[..]) {
+            Some(end) => {
+                let element = src.split_to(end);
+                serde_json::from_slice(&element[..])
+                    .map(Some)
+                    .map_err(Error::from)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<T>, Error> {
+        match self.decode(src)? {
+            Some(element) => Ok(Some(element)),
+            None if self.state == ArrayState::Closed => Ok(None),
+            None => Err(Error::InvalidResponse(
+                "the json array ended early".to_owned(),
+            )),
+        }
+    }
+}
+
+/// Decodes a stream of response chunks without copying them through an
+/// `AsyncRead`: a chunk arriving at an empty buffer becomes the buffer, so
+/// frames that fit inside one chunk are split out of it rather than copied
+pub(crate) struct Decoded<S, D> {
+    stream: S,
+    decoder: D,
+    buffer: BytesMut,
+    eof: bool,
+}
+
+pub(crate) fn decoded<S, D>(
+    stream: S,
+    decoder: D,
+) -> Decoded<S, D>
+where
+    S: Stream<Item = Chunk, Error = Error>,
+    D: Decoder<Error = Error>,
+{
+    Decoded {
+        stream,
+        decoder,
+        buffer: BytesMut::new(),
+        eof: false,
+    }
+}
+
+impl<S, D> Stream for Decoded<S, D>
+where
+    S: Stream<Item = Chunk, Error = Error>,
+    D: Decoder<Error = Error>,
+{
+    type Item = D::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<D::Item>, Error> {
+        loop {
+            if self.eof {
+                return self.decoder.decode_eof(&mut self.buffer).map(Async::Ready);
+            }
+            if let Some(item) = self.decoder.decode(&mut self.buffer)? {
+                return Ok(Async::Ready(Some(item)));
+            }
+            match try_ready!(self.stream.poll()) {
+                Some(chunk) => {
+                    if self.buffer.is_empty() {
+                        self.buffer = chunk.into_bytes().into();
+                    } else {
+                        self.buffer.extend_from_slice(&chunk);
+                    }
+                }
+                None => self.eof = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decoded, JsonArrayDecoder, LinesDecoder, StreamLimits};
+    use crate::{
+        models::Container, testing::MockTransport, tty::TtyDecoder, ContainerListOptions, Docker,
+        Error,
+    };
+    use bytes::BytesMut;
+    use futures::{stream, Future, Stream};
+    use hyper::{Method, StatusCode};
+    use tokio::runtime::current_thread::block_on_all;
+    use tokio_codec::Decoder;
+
+    fn decode_all(
+        limits: StreamLimits,
+        bytes: &[u8],
+    ) -> Result<Vec<String>, Error> {
+        let mut decoder = LinesDecoder::new(&limits);
+        let mut src = BytesMut::from(bytes);
+        let mut lines = Vec::new();
+        while let Some(line) = decoder.decode_eof(&mut src)? {
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+
+    #[test]
+    fn decodes_lines_of_any_length_by_default() {
+        assert_eq!(
+            vec!["{}", "{\"a\":1}", "last"],
+            decode_all(StreamLimits::default(), b"{}\r\n{\"a\":1}\nlast").unwrap()
+        );
+    }
+
+    #[test]
+    fn oversized_lines_are_errors() {
+        let limits = StreamLimits::default().max_line_length(4);
+        match decode_all(limits, b"ok\ntoo long\n") {
+            Err(Error::TooLong(4)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn oversized_lines_can_be_truncated() {
+        let limits = StreamLimits::default().max_line_length(4).truncate(true);
+        assert_eq!(
+            vec!["ok", "too ", "next"],
+            decode_all(limits, b"ok\ntoo long\nnext\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn decodes_json_arrays_element_by_element() {
+        let mut decoder = JsonArrayDecoder::<serde_json::Value>::new();
+        let mut src = BytesMut::from(&b" [{\"Names\":[\"/a,]\\\"\"]},"[..]);
+        let first = decoder.decode(&mut src).unwrap().unwrap();
+        assert_eq!("/a,]\"", first["Names"][0]);
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+
+        src.extend_from_slice(b" 2 ]\n");
+        assert_eq!(
+            Some(serde_json::json!(2)),
+            decoder.decode_eof(&mut src).unwrap()
+        );
+        assert!(decoder.decode_eof(&mut src).unwrap().is_none());
+    }
+
+    #[test]
+    fn truncated_json_arrays_are_errors() {
+        let mut decoder = JsonArrayDecoder::<serde_json::Value>::new();
+        let mut src = BytesMut::from(&b"[1, {\"a\":"[..]);
+        assert_eq!(
+            Some(serde_json::json!(1)),
+            decoder.decode_eof(&mut src).unwrap()
+        );
+        assert!(decoder.decode_eof(&mut src).is_err());
+    }
+
+    #[test]
+    fn lists_are_streamed_element_by_element() {
+        let fixture = include_bytes!("../fixtures/api/v1.41/containers.json");
+        let expected: Vec<Container> = serde_json::from_slice(fixture).unwrap();
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/containers/json")
+            .respond_chunks(StatusCode::OK, fixture.chunks(7).collect());
+        let docker = Docker::from_transport(mock);
+
+        let containers = block_on_all(
+            docker
+                .containers()
+                .list_stream(&ContainerListOptions::default())
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            expected.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            containers.iter().map(|c| &c.id).collect::<Vec<_>>()
+        );
+    }
+
+    fn frame(
+        stream: u8,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0, 0, 0, 0, payload.len() as u8];
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn frames_are_split_out_of_the_chunks_they_arrive_in() {
+        let out = [b'o'; 64];
+        let err = [b'e'; 64];
+        let mut first = frame(1, &out);
+        let mut second = frame(2, &err);
+        // the second frame straddles the first and second chunks
+        first.extend(second.drain(..40));
+        let first_chunk = first.as_ptr() as usize;
+        let chunks = vec![hyper::Chunk::from(first), hyper::Chunk::from(second)];
+
+        let decoded = decoded(stream::iter_ok(chunks), TtyDecoder::new())
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(2, decoded.len());
+        assert_eq!(&out[..], &decoded[0].data[..]);
+        assert_eq!(&err[..], &decoded[1].data[..]);
+        // the first frame lies wholly in the first chunk, so shares its memory
+        assert_eq!(first_chunk + 8, decoded[0].data.as_ptr() as usize);
+    }
+}
@@ -0,0 +1,1048 @@
+//! Bringing up simple docker-compose stacks against a single daemon, without the
+//! docker CLI
+//!
+//! A subset of the compose file format is understood: services run from an
+//! `image` with their command, entrypoint, environment, labels, ports, volumes,
+//! networks, restart policy and `depends_on` ordering, along with top level
+//! networks and volumes. Anything else, such as `build`, is ignored.
+//!
+//! Objects are named after the project, as compose names them, so a stack
+//! brought up here can be inspected with `docker-compose ps`.
+//!
+//! ```no_run
+//! use futures::Future;
+//! use shiplift::{compose::Project, Docker};
+//!
+//! let docker = Docker::new();
+//! let project = Project::from_path("docker-compose.yml").unwrap();
+//! tokio::run(
+//!     project
+//!         .up(&docker)
+//!         .map(|ids| println!("started {:?}", ids))
+//!         .map_err(|e| eprintln!("Error: {}", e)),
+//! );
+//! ```
+
+use crate::{
+    builder::{
+        ContainerConnectionOptions, ContainerOptions, NetworkCreateOptions, VolumeCreateOptions,
+    },
+    Docker, Error, Result,
+};
+use futures::{future, stream, Future, Stream};
+use hyper::StatusCode;
+use serde::{de, Deserialize, Deserializer};
+use serde_yaml::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The network services join when they don't name any
+const DEFAULT_NETWORK: &str = "default";
+
+/// The parts of a compose file this module understands
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ComposeFile {
+    pub version: Option<String>,
+    pub services: BTreeMap<String, Service>,
+    #[serde(deserialize_with = "nullable_values")]
+    pub networks: BTreeMap<String, NetworkSpec>,
+    #[serde(deserialize_with = "nullable_values")]
+    pub volumes: BTreeMap<String, VolumeSpec>,
+}
+
+impl FromStr for ComposeFile {
+    type Err = Error;
+
+    fn from_str(yaml: &str) -> Result<ComposeFile> {
+        serde_yaml::from_str(yaml).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Service {
+    pub image: Option<String>,
+    pub container_name: Option<String>,
+    pub command: Option<Command>,
+    pub entrypoint: Option<Command>,
+    /// Variables without a value are taken from the environment, if it has them
+    #[serde(deserialize_with = "list_or_map")]
+    pub environment: BTreeMap<String, Option<String>>,
+    #[serde(deserialize_with = "list_or_map")]
+    pub labels: BTreeMap<String, Option<String>>,
+    /// Ports in the short syntax, such as `80`, `8080:80` or `8080:80/udp`
+    #[serde(deserialize_with = "scalars")]
+    pub ports: Vec<String>,
+    /// Mounts in the short syntax, such as `data:/var/lib/data` or `./conf:/etc/app:ro`
+    pub volumes: Vec<String>,
+    /// The networks the service joins, with any extra aliases it has on each
+    #[serde(deserialize_with = "service_networks")]
+    pub networks: BTreeMap<String, Vec<String>>,
+    #[serde(deserialize_with = "names")]
+    pub depends_on: Vec<String>,
+    pub restart: Option<String>,
+    pub working_dir: Option<String>,
+    pub privileged: bool,
+    pub tty: bool,
+    pub cap_add: Vec<String>,
+    pub extra_hosts: Vec<String>,
+}
+
+/// A command in either the shell or exec form
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Command {
+    Shell(String),
+    Exec(Vec<String>),
+}
+
+impl Command {
+    /// The command's arguments, with the shell form split the way compose splits
+    /// it: on whitespace, respecting quotes and backslash escapes
+    pub fn args(&self) -> Result<Vec<String>> {
+        match self {
+            Command::Shell(command) => split_words(command),
+            Command::Exec(args) => Ok(args.clone()),
+        }
+    }
+}
+
+/// Splits a shell-form command into words following POSIX shell quoting rules,
+/// without any expansion
+fn split_words(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // whether a word has been started, so `''` yields an empty argument
+    let mut in_word = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\\' => {
+                in_word = true;
+                match chars.next() {
+                    // an escaped newline continues the line
+                    Some('\n') => (),
+                    Some(c) => word.push(c),
+                    None => word.push('\\'),
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated(command)),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ '$') | Some(c @ '`') | Some(c @ '"') | Some(c @ '\\') => {
+                                word.push(c)
+                            }
+                            Some('\n') => (),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unterminated(command)),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated(command)),
+                    }
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn unterminated(command: &str) -> Error {
+    Error::InvalidInput(format!("unterminated quote in command {}", command))
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkSpec {
+    pub driver: Option<String>,
+    #[serde(deserialize_with = "list_or_map")]
+    pub labels: BTreeMap<String, Option<String>>,
+    /// Networks created outside of the stack are used as they are
+    pub external: bool,
+    /// Overrides the name the network is given in the daemon
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct VolumeSpec {
+    pub driver: Option<String>,
+    #[serde(deserialize_with = "list_or_map")]
+    pub labels: BTreeMap<String, Option<String>>,
+    /// Volumes created outside of the stack are used as they are
+    pub external: bool,
+    /// Overrides the name the volume is given in the daemon
+    pub name: Option<String>,
+}
+
+/// A compose file along with the project name its objects are named after, and
+/// the directory relative host paths are resolved against
+#[derive(Clone, Debug)]
+pub struct Project {
+    name: String,
+    dir: PathBuf,
+    file: ComposeFile,
+}
+
+/// The options for creating a service's container, and connecting it to its
+/// other networks once created
+#[derive(Debug)]
+pub struct ServiceContainer {
+    pub service: String,
+    pub options: ContainerOptions,
+    /// Networks after the first, which the daemon can't connect to on creation
+    pub connections: Vec<(String, ContainerConnectionOptions)>,
+}
+
+impl Project {
+    /// A project for a parsed compose file, resolving relative host paths against
+    /// the current directory
+    pub fn new(
+        name: &str,
+        file: ComposeFile,
+    ) -> Result<Project> {
+        Ok(Project {
+            name: project_name(name),
+            dir: env::current_dir()?,
+            file,
+        })
+    }
+
+    /// Reads a compose file, naming the project after the directory it's in as
+    /// compose does
+    pub fn from_path<P>(path: P) -> Result<Project>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = fs::read_to_string(path)?.parse()?;
+        let dir = path
+            .canonicalize()?
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("/"));
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Project {
+            name: project_name(&name),
+            dir,
+            file,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn file(&self) -> &ComposeFile {
+        &self.file
+    }
+
+    /// The options for creating the project's networks, skipping external ones
+    pub fn networks(&self) -> Vec<NetworkCreateOptions> {
+        self.network_specs()
+            .into_iter()
+            .filter(|(_, spec)| !spec.external)
+            .map(|(network, spec)| {
+                let mut labels = self.labels(&spec.labels);
+                labels.insert("com.docker.compose.network".to_owned(), network.clone());
+                let mut opts = NetworkCreateOptions::builder(&self.network_name(&network));
+                opts.driver(spec.driver.as_ref().map_or("", String::as_str))
+                    .label(labels);
+                opts.build()
+            })
+            .collect()
+    }
+
+    /// The options for creating the project's volumes, skipping external ones
+    pub fn volumes(&self) -> Vec<VolumeCreateOptions> {
+        self.file
+            .volumes
+            .iter()
+            .filter(|(_, spec)| !spec.external)
+            .map(|(volume, spec)| {
+                let mut labels = self.labels(&spec.labels);
+                labels.insert("com.docker.compose.volume".to_owned(), volume.clone());
+                let labels = labels
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect::<HashMap<_, _>>();
+                let mut opts = VolumeCreateOptions::builder();
+                opts.name(&self.volume_name(volume)).labels(&labels);
+                if let Some(ref driver) = spec.driver {
+                    opts.driver(driver);
+                }
+                opts.build()
+            })
+            .collect()
+    }
+
+    /// The options for creating the project's containers, ordered so that
+    /// services come after those they depend on
+    pub fn containers(&self) -> Result<Vec<ServiceContainer>> {
+        self.startup_order()?
+            .into_iter()
+            .map(|service| self.container(service))
+            .collect()
+    }
+
+    /// Creates the project's networks, volumes and containers, then starts the
+    /// containers in dependency order, resolving to their ids
+    ///
+    /// Networks which already exist are reused, as are volumes. Containers are
+    /// always created, so bringing a project up twice fails on their names.
+    pub fn up(
+        &self,
+        docker: &Docker,
+    ) -> impl Future<Item = Vec<String>, Error = Error> {
+        let containers = match self.containers() {
+            Ok(containers) => containers,
+            Err(e) => return future::Either::A(future::err(e)),
+        };
+        let networks = {
+            let docker = docker.clone();
+            stream::iter_ok::<_, Error>(self.networks()).for_each(move |opts| {
+                docker.networks().create(&opts).then(|result| match result {
+                    Err(ref e) if e.inner().status() == Some(StatusCode::CONFLICT) => Ok(()),
+                    result => result.map(|_| ()),
+                })
+            })
+        };
+        let volumes = {
+            let docker = docker.clone();
+            stream::iter_ok::<_, Error>(self.volumes())
+                .for_each(move |opts| docker.volumes().create(&opts).map(|_| ()))
+        };
+        let docker = docker.clone();
+        let containers = stream::iter_ok::<_, Error>(containers)
+            .and_then(move |container| {
+                let docker = docker.clone();
+                docker
+                    .containers()
+                    .create(&container.options)
+                    .and_then(move |info| {
+                        let connect = {
+                            let docker = docker.clone();
+                            stream::iter_ok::<_, Error>(container.connections).for_each(
+                                move |(network, opts)| {
+                                    docker.networks().get(&network).connect(&opts)
+                                },
+                            )
+                        };
+                        connect.and_then(move |_| {
+                            docker
+                                .containers()
+                                .get(&info.id)
+                                .start()
+                                .map(move |_| info.id)
+                        })
+                    })
+            })
+            .collect();
+        future::Either::B(networks.and_then(|_| volumes).and_then(|_| containers))
+    }
+
+    fn container(
+        &self,
+        service_name: &str,
+    ) -> Result<ServiceContainer> {
+        let service = &self.file.services[service_name];
+        let image = service
+            .image
+            .as_ref()
+            .ok_or_else(|| Error::InvalidInput(format!("service {} has no image", service_name)))?;
+        let mut opts = ContainerOptions::builder(image);
+        let name = service
+            .container_name
+            .clone()
+            .unwrap_or_else(|| format!("{}_{}_1", self.name, service_name));
+        opts.name(&name);
+
+        if let Some(ref command) = service.command {
+            opts.cmd(command.args()?.iter().map(String::as_str).collect());
+        }
+        if let Some(ref entrypoint) = service.entrypoint {
+            opts.entrypoint_args(entrypoint.args()?.iter().map(String::as_str).collect());
+        }
+        let env = service
+            .environment
+            .iter()
+            .filter_map(|(key, value)| {
+                value
+                    .clone()
+                    .or_else(|| env::var(key).ok())
+                    .map(|value| format!("{}={}", key, value))
+            })
+            .collect::<Vec<_>>();
+        if !env.is_empty() {
+            opts.env(env.iter().map(String::as_str).collect());
+        }
+        let mut labels = self.labels(&service.labels);
+        labels.insert(
+            "com.docker.compose.service".to_owned(),
+            service_name.to_owned(),
+        );
+        labels.insert(
+            "com.docker.compose.container-number".to_owned(),
+            "1".to_owned(),
+        );
+        opts.labels(
+            &labels
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+        );
+
+        for port in &service.ports {
+            let (host_port, container_port, protocol) = parse_port(port)?;
+            opts.expose(container_port, protocol, host_port);
+        }
+        let binds = service
+            .volumes
+            .iter()
+            .map(|volume| self.bind(volume))
+            .collect::<Result<Vec<_>>>()?;
+        if !binds.is_empty() {
+            opts.volumes(binds.iter().map(String::as_str).collect());
+        }
+        if let Some(ref restart) = service.restart {
+            let mut parts = restart.splitn(2, ':');
+            let policy = parts.next().unwrap_or_default();
+            let retries = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            opts.restart_policy(policy, retries);
+        }
+        if let Some(ref working_dir) = service.working_dir {
+            opts.working_dir(working_dir);
+        }
+        if service.privileged {
+            opts.privileged(true);
+        }
+        if service.tty {
+            opts.tty(true);
+        }
+        if !service.cap_add.is_empty() {
+            opts.capabilities(service.cap_add.iter().map(String::as_str).collect());
+        }
+        if !service.extra_hosts.is_empty() {
+            opts.extra_hosts(service.extra_hosts.iter().map(String::as_str).collect());
+        }
+
+        // services are reachable on their networks by their name, as with compose
+        let mut networks = self.service_networks(service_name)?.into_iter();
+        let mut connections = Vec::new();
+        if let Some((network, aliases)) = networks.next() {
+            opts.network_mode(&network);
+            opts.network(&network, aliases.iter().map(String::as_str).collect());
+        }
+        for (network, aliases) in networks {
+            let mut connection = ContainerConnectionOptions::builder(&name);
+            connection.aliases(aliases.iter().map(String::as_str).collect());
+            connections.push((network, connection.build()));
+        }
+
+        Ok(ServiceContainer {
+            service: service_name.to_owned(),
+            options: opts.build(),
+            connections,
+        })
+    }
+
+    /// The networks of a service by their names in the daemon, along with the
+    /// service's aliases on each
+    fn service_networks(
+        &self,
+        service_name: &str,
+    ) -> Result<Vec<(String, Vec<String>)>> {
+        let service = &self.file.services[service_name];
+        let specs = self.network_specs();
+        let joined = if service.networks.is_empty() {
+            vec![(DEFAULT_NETWORK.to_owned(), Vec::new())]
+        } else {
+            service
+                .networks
+                .iter()
+                .map(|(network, aliases)| (network.clone(), aliases.clone()))
+                .collect()
+        };
+        joined
+            .into_iter()
+            .map(|(network, mut aliases)| {
+                let spec = specs.get(&network).ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "service {} joins undefined network {}",
+                        service_name, network
+                    ))
+                })?;
+                aliases.insert(0, service_name.to_owned());
+                let name = match spec.name {
+                    Some(ref name) => name.clone(),
+                    None if spec.external => network.clone(),
+                    None => self.network_name(&network),
+                };
+                Ok((name, aliases))
+            })
+            .collect()
+    }
+
+    /// The networks defined by the file, along with the default network if any
+    /// service joins it
+    fn network_specs(&self) -> BTreeMap<String, NetworkSpec> {
+        let mut specs = self.file.networks.clone();
+        let joins_default = self
+            .file
+            .services
+            .values()
+            .any(|service| service.networks.is_empty());
+        if joins_default {
+            specs.entry(DEFAULT_NETWORK.to_owned()).or_default();
+        }
+        specs
+    }
+
+    fn network_name(
+        &self,
+        network: &str,
+    ) -> String {
+        match self.file.networks.get(network).and_then(|n| n.name.clone()) {
+            Some(name) => name,
+            None => format!("{}_{}", self.name, network),
+        }
+    }
+
+    fn volume_name(
+        &self,
+        volume: &str,
+    ) -> String {
+        match self.file.volumes.get(volume) {
+            Some(VolumeSpec {
+                name: Some(name), ..
+            }) => name.clone(),
+            Some(spec) if spec.external => volume.to_owned(),
+            _ => format!("{}_{}", self.name, volume),
+        }
+    }
+
+    /// Turns a short syntax mount into a bind the daemon accepts, with named
+    /// volumes given their project names and host paths made absolute
+    fn bind(
+        &self,
+        volume: &str,
+    ) -> Result<String> {
+        let mut parts = volume.splitn(2, ':');
+        let source = parts.next().unwrap_or_default();
+        let target = match parts.next() {
+            Some(target) => target,
+            // an anonymous volume
+            None => return Ok(source.to_owned()),
+        };
+        let source = if source.starts_with('.') || source.starts_with('~') {
+            let (base, relative) = if source.starts_with('~') {
+                let home = env::var("HOME")
+                    .map(PathBuf::from)
+                    .map_err(|_| Error::InvalidInput(format!("{} needs HOME set", volume)))?;
+                (home, source.trim_start_matches('~').trim_start_matches('/'))
+            } else {
+                (self.dir.clone(), source.trim_start_matches("./"))
+            };
+            base.join(relative).to_string_lossy().into_owned()
+        } else if source.starts_with('/') {
+            source.to_owned()
+        } else if self.file.volumes.contains_key(source) {
+            self.volume_name(source)
+        } else {
+            return Err(Error::InvalidInput(format!(
+                "mount {} uses undefined volume {}",
+                volume, source
+            )));
+        };
+        Ok(format!("{}:{}", source, target))
+    }
+
+    /// Labels given to every object of the project, along with `labels`
+    fn labels(
+        &self,
+        labels: &BTreeMap<String, Option<String>>,
+    ) -> HashMap<String, String> {
+        let mut all = labels
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone().unwrap_or_default()))
+            .collect::<HashMap<_, _>>();
+        all.insert("com.docker.compose.project".to_owned(), self.name.clone());
+        all
+    }
+
+    /// The services, each after those it depends on
+    fn startup_order(&self) -> Result<Vec<&str>> {
+        fn visit<'a>(
+            project: &'a Project,
+            service: &'a str,
+            visiting: &mut BTreeSet<&'a str>,
+            order: &mut Vec<&'a str>,
+        ) -> Result<()> {
+            if order.contains(&service) {
+                return Ok(());
+            }
+            if !visiting.insert(service) {
+                return Err(Error::InvalidInput(format!(
+                    "service {} depends on itself",
+                    service
+                )));
+            }
+            for dependency in &project.file.services[service].depends_on {
+                if !project.file.services.contains_key(dependency) {
+                    return Err(Error::InvalidInput(format!(
+                        "service {} depends on undefined service {}",
+                        service, dependency
+                    )));
+                }
+                visit(project, dependency, visiting, order)?;
+            }
+            visiting.remove(service);
+            order.push(service);
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        for service in self.file.services.keys() {
+            visit(self, service, &mut BTreeSet::new(), &mut order)?;
+        }
+        Ok(order)
+    }
+}
+
+/// Project names are lowercase alphanumerics, as compose normalizes them
+fn project_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+/// Parses a short syntax port into its host port, container port and protocol,
+/// with a host port of 0 having the daemon pick one
+fn parse_port(port: &str) -> Result<(u32, u32, &str)> {
+    let invalid = || Error::InvalidInput(format!("unsupported port {}", port));
+    let mut parts = port.splitn(2, '/');
+    let ports = parts.next().unwrap_or_default();
+    let protocol = parts.next().unwrap_or("tcp");
+    let ports = ports.split(':').collect::<Vec<_>>();
+    let (host, container) = match ports[..] {
+        [container] => ("0", container),
+        [host, container] => (host, container),
+        // binding to a particular host ip isn't supported by the builder
+        _ => return Err(invalid()),
+    };
+    Ok((
+        host.parse().map_err(|_| invalid())?,
+        container.parse().map_err(|_| invalid())?,
+        protocol,
+    ))
+}
+
+fn scalar(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Either a list of `key=value` strings or a map, as with environments and labels
+fn list_or_map<'de, D>(
+    deserializer: D
+) -> std::result::Result<BTreeMap<String, Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(BTreeMap::new()),
+        Value::Sequence(items) => items
+            .into_iter()
+            .map(|item| {
+                let item = scalar(item).ok_or_else(|| de::Error::custom("expected key=value"))?;
+                let mut parts = item.splitn(2, '=');
+                let key = parts.next().unwrap_or_default().to_owned();
+                Ok((key, parts.next().map(str::to_owned)))
+            })
+            .collect(),
+        Value::Mapping(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let key = scalar(key).ok_or_else(|| de::Error::custom("expected a key"))?;
+                Ok((key, scalar(value)))
+            })
+            .collect(),
+        _ => Err(de::Error::custom("expected a list or a map")),
+    }
+}
+
+/// A list of strings or numbers, as with ports
+fn scalars<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|value| scalar(value).ok_or_else(|| de::Error::custom("expected a string")))
+        .collect()
+}
+
+/// Either a list of names or a map keyed by them, as with `depends_on`
+fn names<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(list_or_map(deserializer)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Either a list of network names or a map of them to their settings, of which
+/// only aliases are kept
+fn service_networks<'de, D>(
+    deserializer: D
+) -> std::result::Result<BTreeMap<String, Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Default, Deserialize)]
+    #[serde(default)]
+    struct Settings {
+        aliases: Vec<String>,
+    }
+
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(BTreeMap::new()),
+        Value::Sequence(items) => items
+            .into_iter()
+            .map(|item| {
+                let name = scalar(item).ok_or_else(|| de::Error::custom("expected a network"))?;
+                Ok((name, Vec::new()))
+            })
+            .collect(),
+        Value::Mapping(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let name = scalar(key).ok_or_else(|| de::Error::custom("expected a network"))?;
+                let settings = match value {
+                    Value::Null => Settings::default(),
+                    value => serde_yaml::from_value(value).map_err(de::Error::custom)?,
+                };
+                Ok((name, settings.aliases))
+            })
+            .collect(),
+        _ => Err(de::Error::custom("expected a list or a map")),
+    }
+}
+
+/// A map whose values may be left empty, as with top level networks and volumes
+fn nullable_values<'de, D, T>(deserializer: D) -> std::result::Result<BTreeMap<String, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(BTreeMap::<String, Option<T>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_port, Command, ComposeFile, ContainerOptions, Project};
+    use crate::{testing::MockTransport, Docker};
+    use hyper::{Method, StatusCode};
+    use serde_json::Value;
+    use std::path::PathBuf;
+    use tokio::runtime::current_thread::block_on_all;
+
+    const STACK: &str = r#"
+version: "3.7"
+services:
+  web:
+    image: nginx:1.19
+    command: nginx -g "daemon off;"
+    environment:
+      MODE: production
+      WORKERS: 4
+    ports:
+      - "8080:80"
+      - 443
+    volumes:
+      - ./static:/usr/share/nginx/html:ro
+    networks:
+      - front
+      - back
+    depends_on:
+      - db
+    restart: on-failure:3
+  db:
+    image: postgres:13
+    environment:
+      - POSTGRES_PASSWORD=secret
+    volumes:
+      - data:/var/lib/postgresql/data
+    networks:
+      back:
+        aliases:
+          - database
+networks:
+  front:
+  back:
+    driver: bridge
+volumes:
+  data:
+"#;
+
+    fn project() -> Project {
+        Project {
+            name: "shop".to_owned(),
+            dir: PathBuf::from("/srv/shop"),
+            file: STACK.parse().unwrap(),
+        }
+    }
+
+    fn options(value: &ContainerOptions) -> Value {
+        serde_json::from_str(&value.serialize().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn services_are_created_after_their_dependencies() {
+        let containers = project().containers().unwrap();
+        assert_eq!(
+            vec!["db", "web"],
+            containers
+                .iter()
+                .map(|c| c.service.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let db = options(&containers[0].options);
+        assert_eq!(Some("shop_db_1"), containers[0].options.name.as_deref());
+        assert_eq!(serde_json::json!(["POSTGRES_PASSWORD=secret"]), db["Env"]);
+        assert_eq!(
+            serde_json::json!(["shop_data:/var/lib/postgresql/data"]),
+            db["HostConfig"]["Binds"]
+        );
+        assert_eq!("shop_back", db["HostConfig"]["NetworkMode"]);
+        assert_eq!(
+            serde_json::json!(["db", "database"]),
+            db["NetworkingConfig"]["EndpointsConfig"]["shop_back"]["Aliases"]
+        );
+        assert_eq!("db", db["Labels"]["com.docker.compose.service"]);
+        assert_eq!("shop", db["Labels"]["com.docker.compose.project"]);
+        assert!(containers[0].connections.is_empty());
+
+        let web = options(&containers[1].options);
+        assert_eq!(
+            serde_json::json!(["nginx", "-g", "daemon off;"]),
+            web["Cmd"]
+        );
+        assert_eq!(
+            serde_json::json!(["MODE=production", "WORKERS=4"]),
+            web["Env"]
+        );
+        assert_eq!(
+            serde_json::json!([{ "HostPort": "8080" }]),
+            web["HostConfig"]["PortBindings"]["80/tcp"]
+        );
+        assert_eq!(
+            serde_json::json!([{ "HostPort": "0" }]),
+            web["HostConfig"]["PortBindings"]["443/tcp"]
+        );
+        assert_eq!(
+            serde_json::json!(["/srv/shop/static:/usr/share/nginx/html:ro"]),
+            web["HostConfig"]["Binds"]
+        );
+        assert_eq!(
+            serde_json::json!({ "Name": "on-failure", "MaximumRetryCount": 3 }),
+            web["HostConfig"]["RestartPolicy"]
+        );
+        assert_eq!("shop_back", web["HostConfig"]["NetworkMode"]);
+        assert_eq!(
+            vec!["shop_front"],
+            containers[1]
+                .connections
+                .iter()
+                .map(|(network, _)| network.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn networks_and_volumes_are_named_after_the_project() {
+        let project = project();
+        let networks = project
+            .networks()
+            .iter()
+            .map(|opts| serde_json::from_str::<Value>(&opts.serialize().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["shop_back", "shop_front"],
+            networks.iter().map(|n| &n["Name"]).collect::<Vec<_>>()
+        );
+        assert_eq!("bridge", networks[0]["Driver"]);
+        assert_eq!("back", networks[0]["Labels"]["com.docker.compose.network"]);
+
+        let volumes = project
+            .volumes()
+            .iter()
+            .map(|opts| serde_json::from_str::<Value>(&opts.serialize().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(1, volumes.len());
+        assert_eq!("shop_data", volumes[0]["Name"]);
+    }
+
+    #[test]
+    fn services_without_networks_join_the_default_network() {
+        let file: ComposeFile = "services:\n  app:\n    image: busybox\n".parse().unwrap();
+        let project = Project::new("My Stack", file).unwrap();
+        assert_eq!("mystack", project.name());
+        let containers = project.containers().unwrap();
+        let app = options(&containers[0].options);
+        assert_eq!("mystack_default", app["HostConfig"]["NetworkMode"]);
+        assert_eq!(1, project.networks().len());
+    }
+
+    #[test]
+    fn invalid_stacks_are_rejected() {
+        let cyclic: ComposeFile = r#"
+services:
+  a: {image: busybox, depends_on: [b]}
+  b: {image: busybox, depends_on: [a]}
+"#
+        .parse()
+        .unwrap();
+        assert!(Project::new("p", cyclic).unwrap().containers().is_err());
+
+        let undefined: ComposeFile = "services:\n  a: {image: busybox, volumes: ['data:/data']}\n"
+            .parse()
+            .unwrap();
+        assert!(Project::new("p", undefined).unwrap().containers().is_err());
+
+        let imageless: ComposeFile = "services:\n  a: {build: .}\n".parse().unwrap();
+        assert!(Project::new("p", imageless).unwrap().containers().is_err());
+    }
+
+    #[test]
+    fn shell_commands_are_split_respecting_quotes() {
+        let args = |command: &str| Command::Shell(command.to_owned()).args().unwrap();
+        assert_eq!(
+            vec!["sh", "-c", "echo hello world"],
+            args(r#"sh -c "echo hello world""#)
+        );
+        assert_eq!(
+            vec!["sh", "-c", "echo 'hi there' $HOME"],
+            args(r#"sh -c 'echo '"'hi there'"' $HOME'"#)
+        );
+        assert_eq!(
+            vec!["echo", "a b", "\"quoted\"", ""],
+            args(r#"echo a\ b "\"quoted\"" ''"#)
+        );
+        assert!(Command::Shell(r#"sh -c "echo"#.to_owned()).args().is_err());
+    }
+
+    #[test]
+    fn entrypoints_are_sent_as_arrays() {
+        let file: ComposeFile = r#"
+services:
+  shell:
+    image: busybox
+    entrypoint: /bin/sh -c "exec app"
+  exec:
+    image: busybox
+    entrypoint: ["/usr/bin/env", "FOO=bar", "app"]
+"#
+        .parse()
+        .unwrap();
+        let containers = Project::new("p", file).unwrap().containers().unwrap();
+        assert_eq!(
+            serde_json::json!(["/usr/bin/env", "FOO=bar", "app"]),
+            options(&containers[0].options)["Entrypoint"]
+        );
+        assert_eq!(
+            serde_json::json!(["/bin/sh", "-c", "exec app"]),
+            options(&containers[1].options)["Entrypoint"]
+        );
+    }
+
+    #[test]
+    fn ports() {
+        assert_eq!((0, 80, "tcp"), parse_port("80").unwrap());
+        assert_eq!((8080, 80, "tcp"), parse_port("8080:80").unwrap());
+        assert_eq!((53, 53, "udp"), parse_port("53:53/udp").unwrap());
+        assert!(parse_port("127.0.0.1:8080:80").is_err());
+        assert!(parse_port("8000-8010:80").is_err());
+    }
+
+    #[test]
+    fn stacks_are_brought_up_in_order() {
+        let mock = MockTransport::new();
+        mock.on(Method::POST, "/networks/create")
+            .respond(StatusCode::CREATED, r#"{"Id":"n1","Warning":""}"#);
+        mock.on(Method::POST, "/volumes/create").respond(
+            StatusCode::CREATED,
+            r#"{"Name":"shop_data","Driver":"local","Mountpoint":"/data","Labels":null}"#,
+        );
+        mock.on(Method::POST, "/containers/create")
+            .respond(StatusCode::CREATED, r#"{"Id":"c1","Warnings":[]}"#);
+        mock.on(Method::POST, "/networks/shop_front/connect")
+            .respond(StatusCode::OK, "");
+        mock.on(Method::POST, "/containers/c1/start")
+            .respond(StatusCode::NO_CONTENT, "");
+        let docker = Docker::from_transport(mock.clone());
+
+        let ids = block_on_all(project().up(&docker)).unwrap();
+        assert_eq!(vec!["c1", "c1"], ids);
+        assert_eq!(
+            vec![
+                "POST /networks/create",
+                "POST /networks/create",
+                "POST /volumes/create",
+                "POST /containers/create?name=shop_db_1",
+                "POST /containers/c1/start",
+                "POST /containers/create?name=shop_web_1",
+                "POST /networks/shop_front/connect",
+                "POST /containers/c1/start",
+            ],
+            mock.requests()
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Docker CLI contexts
+//!
+//! Contexts are how the docker CLI remembers which daemon to talk to. Each one is stored under
+//! `~/.docker/contexts`, keyed by the sha256 digest of its name, with its endpoint in
+//! `meta/<digest>/meta.json` and any TLS material in `tls/<digest>/docker`.
+
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+use crate::tls::TlsConfig;
+use crate::{Error, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the implicit context which uses `DOCKER_HOST` or the default socket
+const DEFAULT_CONTEXT: &str = "default";
+
+/// A docker endpoint stored by the docker CLI
+#[derive(Clone, Debug)]
+#[cfg_attr(not(any(feature = "tls", feature = "tls-rustls")), allow(dead_code))]
+pub struct Context {
+    name: String,
+    host: String,
+    skip_tls_verify: bool,
+    tls_path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct CliConfig {
+    #[serde(rename = "currentContext")]
+    current_context: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Meta {
+    name: String,
+    endpoints: HashMap<String, Endpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Endpoint {
+    host: String,
+    #[serde(default, rename = "SkipTLSVerify")]
+    skip_tls_verify: bool,
+}
+
+impl Context {
+    /// Loads the context selected by `DOCKER_CONTEXT`, falling back on the
+    /// `currentContext` of the docker CLI's config.json.
+    ///
+    /// Returns `None` when the default context is in use.
+    pub fn current() -> Result<Option<Context>> {
+        let name = match env::var("DOCKER_CONTEXT") {
+            Ok(name) => Some(name),
+            Err(_) => current_context(&config_dir())?,
+        };
+        match name {
+            Some(ref name) if !name.is_empty() && name != DEFAULT_CONTEXT => {
+                Context::load(name).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Loads a named context
+    pub fn load(name: &str) -> Result<Context> {
+        let contexts = config_dir().join("contexts");
+        let id = context_id(name);
+        let meta = match fs::read_to_string(contexts.join("meta").join(&id).join("meta.json")) {
+            Ok(meta) => meta,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(Error::ContextNotFound(name.to_owned()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Context::from_meta(&meta, contexts.join("tls").join(&id).join("docker"))
+    }
+
+    fn from_meta(
+        meta: &str,
+        tls_path: PathBuf,
+    ) -> Result<Context> {
+        let mut meta: Meta = serde_json::from_str(meta)?;
+        let endpoint = meta.endpoints.remove("docker").ok_or_else(|| {
+            Error::InvalidResponse(format!("context {} has no docker endpoint", meta.name))
+        })?;
+        Ok(Context {
+            name: meta.name,
+            host: endpoint.host,
+            skip_tls_verify: endpoint.skip_tls_verify,
+            tls_path,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The url of the docker host, e.g. `tcp://docker.example.com:2376`
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The TLS material stored alongside this context, if any
+    #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+    pub fn tls_config(&self) -> Option<TlsConfig> {
+        if !self.tls_path.is_dir() {
+            return None;
+        }
+        Some(TlsConfig::from_cert_path(
+            &self.tls_path,
+            !self.skip_tls_verify,
+        ))
+    }
+}
+
+/// The docker CLI's configuration directory, `DOCKER_CONFIG` or `~/.docker`
+pub(crate) fn config_dir() -> PathBuf {
+    match env::var_os("DOCKER_CONFIG") {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(".docker"),
+    }
+}
+
+fn current_context(config_dir: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(config_dir.join("config.json")) {
+        Ok(config) => Ok(serde_json::from_str::<CliConfig>(&config)?.current_context),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Contexts are stored in directories named after the hex encoded sha256 digest of their name
+fn context_id(name: &str) -> String {
+    Sha256::digest(name.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{context_id, Context};
+    use std::path::PathBuf;
+
+    #[test]
+    fn context_ids() {
+        assert_eq!(
+            "b71199ebd070b36beab7317920c2c2f1d777df8d05e5527d8458fda57cb17a7a",
+            context_id("remote")
+        );
+    }
+
+    #[test]
+    fn context_from_meta() {
+        let meta = r#"{
+            "Name": "remote",
+            "Metadata": {},
+            "Endpoints": {
+                "docker": {
+                    "Host": "tcp://docker.example.com:2376",
+                    "SkipTLSVerify": true
+                }
+            }
+        }"#;
+        let context = Context::from_meta(meta, PathBuf::from("tls")).unwrap();
+        assert_eq!("remote", context.name());
+        assert_eq!("tcp://docker.example.com:2376", context.host());
+        assert!(context.skip_tls_verify);
+    }
+
+    #[test]
+    fn context_without_docker_endpoint() {
+        let meta = r#"{"Name": "remote", "Endpoints": {}}"#;
+        assert!(Context::from_meta(meta, PathBuf::from("tls")).is_err());
+    }
+}
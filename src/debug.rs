@@ -0,0 +1,197 @@
+//! Logging of the requests sent to, and responses received from, the daemon
+//!
+//! Enabled by the `debug-http` feature. Everything is logged at debug level
+//! under the `shiplift::http` target, with credentials redacted and bodies
+//! truncated to their first `MAX_BODY_LOG` bytes. The bodies of endpoints
+//! which exchange credentials or secrets aren't logged at all.
+
+use crate::transport::carries_credentials;
+use futures::Stream;
+use hyper::{header, Body, HeaderMap, Request, Response, StatusCode};
+use log::debug;
+
+const TARGET: &str = "shiplift::http";
+
+/// How many bytes of each body are logged
+const MAX_BODY_LOG: usize = 1024;
+
+/// Headers which carry credentials
+const REDACTED: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-registry-auth",
+    "x-registry-config",
+];
+
+/// Logs a request about to be sent, wrapping its body so that the start of
+/// it is logged as it is written
+pub(crate) fn request(req: Request<Body>) -> Request<Body> {
+    debug!(
+        target: TARGET,
+        "> {} {}{}",
+        req.method(),
+        req.uri(),
+        sanitized(req.headers())
+    );
+    if carries_credentials(req.uri().path()) {
+        debug!(target: TARGET, "> <body redacted>");
+        return req;
+    }
+    let (mut parts, body) = req.into_parts();
+    // wrapping the body loses its length, which would otherwise switch the
+    // request to a chunked transfer encoding
+    if let Some(len) = hyper::body::Payload::content_length(&body) {
+        parts
+            .headers
+            .entry(header::CONTENT_LENGTH)
+            .expect("valid header name")
+            .or_insert_with(|| len.into());
+    }
+    Request::from_parts(parts, logged(body, ">"))
+}
+
+/// Logs a response received from the daemon, wrapping its body so that the
+/// start of it is logged as it is read, unless it may carry credentials
+pub(crate) fn response(
+    res: Response<Body>,
+    redact_body: bool,
+) -> Response<Body> {
+    debug!(
+        target: TARGET,
+        "< {:?} {}{}",
+        res.version(),
+        res.status(),
+        sanitized(res.headers())
+    );
+    // upgraded connections need the original body to take over the socket
+    if res.status() == StatusCode::SWITCHING_PROTOCOLS {
+        return res;
+    }
+    if redact_body {
+        debug!(target: TARGET, "< <body redacted>");
+        return res;
+    }
+    let (parts, body) = res.into_parts();
+    Response::from_parts(parts, logged(body, "<"))
+}
+
+/// Renders headers one per line, with credentials redacted
+fn sanitized(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if REDACTED.contains(&name.as_str()) {
+                "<redacted>".into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            format!("\n| {}: {}", name, value)
+        })
+        .collect()
+}
+
+fn logged(
+    body: Body,
+    direction: &'static str,
+) -> Body {
+    let mut seen = 0;
+    Body::wrap_stream(body.inspect(move |chunk| {
+        if seen < MAX_BODY_LOG {
+            let shown = &chunk[..chunk.len().min(MAX_BODY_LOG - seen)];
+            debug!(
+                target: TARGET,
+                "{} {}{}",
+                direction,
+                String::from_utf8_lossy(shown),
+                if shown.len() < chunk.len() {
+                    "..."
+                } else {
+                    ""
+                }
+            );
+        }
+        seen += chunk.len();
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sanitized;
+    use crate::{testing::MockTransport, Docker, RegistryAuth};
+    use futures::{Future, Stream};
+    use hyper::{header, Body, HeaderMap, Method, Request, StatusCode};
+    use log::{Log, Metadata, Record};
+    use std::sync::Mutex;
+    use tokio::runtime::current_thread::block_on_all;
+
+    /// Keeps every line logged by any test
+    struct Capture(Mutex<Vec<String>>);
+
+    impl Log for Capture {
+        fn enabled(
+            &self,
+            _: &Metadata,
+        ) -> bool {
+            true
+        }
+
+        fn log(
+            &self,
+            record: &Record,
+        ) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGS: Capture = Capture(Mutex::new(Vec::new()));
+
+    fn logged() -> String {
+        LOGS.0.lock().unwrap().join("\n")
+    }
+
+    #[test]
+    fn credentials_are_redacted() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        headers.insert("X-Registry-Auth", "c2VjcmV0".parse().unwrap());
+        headers.insert(header::AUTHORIZATION, "Basic c2VjcmV0".parse().unwrap());
+        let logged = sanitized(&headers);
+        assert!(logged.contains("content-type: application/json"));
+        assert!(logged.contains("x-registry-auth: <redacted>"));
+        assert!(logged.contains("authorization: <redacted>"));
+        assert!(!logged.contains("c2VjcmV0"));
+    }
+
+    #[test]
+    fn auth_bodies_are_not_logged() {
+        let _ = log::set_logger(&LOGS);
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let mock = MockTransport::new();
+        mock.on(Method::POST, "/auth").respond(
+            StatusCode::OK,
+            r#"{"Status":"Login Succeeded","IdentityToken":"token-from-daemon"}"#,
+        );
+        let docker = Docker::from_transport(mock);
+        let auth = RegistryAuth::builder()
+            .username("user")
+            .password("password-for-registry")
+            .build();
+        block_on_all(docker.auth(&auth)).unwrap();
+
+        // the mock never reads request bodies, so send one through as hyper would
+        let req = super::request(
+            Request::post("http://localhost/auth")
+                .body(Body::from(serde_json::to_string(&auth).unwrap()))
+                .unwrap(),
+        );
+        req.into_body().concat2().wait().unwrap();
+
+        let logs = logged();
+        assert!(logs.contains("> POST http://localhost/auth"));
+        assert!(!logs.contains("password-for-registry"));
+        assert!(!logs.contains("token-from-daemon"));
+    }
+}
@@ -1,9 +1,17 @@
 //! Representations of various client errors
 
+use bytes::Bytes;
 use http;
-use hyper::{self, StatusCode};
+use hyper::{self, HeaderMap, Method, StatusCode};
 use serde_json::Error as SerdeError;
-use std::{error::Error as StdError, fmt, io::Error as IoError, string::FromUtf8Error};
+use std::{
+    error::Error as StdError,
+    fmt,
+    io::{Error as IoError, ErrorKind},
+    string::FromUtf8Error,
+    sync::Arc,
+    time::Duration,
+};
 
 #[derive(Debug)]
 pub enum Error {
@@ -13,8 +21,203 @@ pub enum Error {
     IO(IoError),
     Encoding(FromUtf8Error),
     InvalidResponse(String),
-    Fault { code: StatusCode, message: String },
+    /// The daemon responded with an error status not covered by a more specific variant
+    Fault {
+        code: StatusCode,
+        message: String,
+        response: Option<Box<RawResponse>>,
+    },
+    /// `304`, e.g. when starting a container which is already running
+    NotModified {
+        response: Option<Box<RawResponse>>,
+    },
+    /// `401`
+    Unauthorized {
+        message: String,
+        response: Option<Box<RawResponse>>,
+    },
+    /// `404`, e.g. when a container or image doesn't exist
+    NotFound {
+        message: String,
+        response: Option<Box<RawResponse>>,
+    },
+    /// `409`, e.g. when removing a running container
+    Conflict {
+        message: String,
+        response: Option<Box<RawResponse>>,
+    },
+    /// `5xx`
+    ServerError {
+        code: StatusCode,
+        message: String,
+        response: Option<Box<RawResponse>>,
+    },
     ConnectionNotUpgraded,
+    /// A frame of a multiplexed container output stream had an unknown stream type
+    InvalidStreamType(u8),
+    InvalidApiVersion(String),
+    Tls(String),
+    ContextNotFound(String),
+    /// A docker host url which can't be connected to
+    InvalidHost(String),
+    /// Options which the daemon would reject, caught before sending the request
+    InvalidInput(String),
+    /// The docker daemon's Unix socket doesn't exist, or the current user isn't
+    /// allowed to connect to it
+    SocketUnavailable {
+        path: String,
+        error: IoError,
+    },
+    Timeout,
+    /// A stream went longer than its idle timeout without receiving anything
+    StreamIdle(Duration),
+    /// A line or frame of a streamed response was longer than the configured limit,
+    /// in bytes
+    TooLong(usize),
+    /// The daemon reported an error partway through a streamed operation, such
+    /// as a pull or build, after responding with a success status
+    OperationFailed(String),
+    /// An operation was cancelled through its `CancelHandle` before it finished
+    Cancelled,
+    /// A subscription fell behind and missed this many items, such as events
+    /// of an `EventBus`. The subscription carries on after reporting it
+    Lagged(u64),
+    /// An error passed on to several consumers, such as every subscriber of an
+    /// `EventBus` whose connection failed
+    Shared(Arc<Error>),
+    /// An error from a request to the daemon, along with the request it came from
+    Request {
+        method: Method,
+        endpoint: String,
+        error: Box<Error>,
+    },
+}
+
+/// The headers and body of an error response, for logging or parsing details
+/// beyond the message the daemon gave
+#[derive(Clone, Debug, Default)]
+pub struct RawResponse {
+    pub headers: HeaderMap,
+    /// The raw body, which the error's message was extracted from
+    pub body: Bytes,
+}
+
+impl Error {
+    /// Maps an error response from the daemon to its variant, keeping the
+    /// response's headers and body
+    pub(crate) fn from_response(
+        code: StatusCode,
+        headers: HeaderMap,
+        body: Bytes,
+        message: String,
+    ) -> Error {
+        let response = Some(Box::new(RawResponse { headers, body }));
+        match code {
+            StatusCode::NOT_MODIFIED => Error::NotModified { response },
+            StatusCode::UNAUTHORIZED => Error::Unauthorized { message, response },
+            StatusCode::NOT_FOUND => Error::NotFound { message, response },
+            StatusCode::CONFLICT => Error::Conflict { message, response },
+            code if code.is_server_error() => Error::ServerError {
+                code,
+                message,
+                response,
+            },
+            code => Error::Fault {
+                code,
+                message,
+                response,
+            },
+        }
+    }
+
+    /// Maps a failure to connect to a Unix socket to `SocketUnavailable` when
+    /// the docker daemon isn't running or isn't accessible
+    pub(crate) fn from_socket_error(
+        path: String,
+        error: hyper::Error,
+    ) -> Error {
+        if !error.is_connect() {
+            return Error::Hyper(error);
+        }
+        match error.into_cause().map(|cause| cause.downcast::<IoError>()) {
+            Some(Ok(error)) => match error.kind() {
+                ErrorKind::PermissionDenied
+                | ErrorKind::NotFound
+                | ErrorKind::ConnectionRefused => Error::SocketUnavailable {
+                    path,
+                    error: *error,
+                },
+                _ => Error::IO(*error),
+            },
+            Some(Err(cause)) => Error::IO(IoError::new(ErrorKind::Other, cause)),
+            None => Error::IO(IoError::new(
+                ErrorKind::Other,
+                format!("failed to connect to {}", path),
+            )),
+        }
+    }
+
+    /// Attaches the request an error came from, unless it already has one
+    pub(crate) fn context(
+        self,
+        method: &Method,
+        endpoint: &str,
+    ) -> Error {
+        match self {
+            Error::Request { .. } => self,
+            error => Error::Request {
+                method: method.clone(),
+                endpoint: endpoint.to_owned(),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// The underlying error, without the context of the request it came from,
+    /// for matching on
+    ///
+    /// ```no_run
+    /// # use shiplift::Error;
+    /// # fn handle(error: Error) {
+    /// match error.inner() {
+    ///     Error::NotFound { .. } => println!("no such container"),
+    ///     _ => eprintln!("{}", error),
+    /// }
+    /// # }
+    /// ```
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Request { error, .. } => error.inner(),
+            Error::Shared(error) => error.inner(),
+            error => error,
+        }
+    }
+
+    /// The status the daemon responded with, for errors returned by the daemon
+    pub fn status(&self) -> Option<StatusCode> {
+        match self.inner() {
+            Error::Fault { code, .. } | Error::ServerError { code, .. } => Some(*code),
+            Error::NotModified { .. } => Some(StatusCode::NOT_MODIFIED),
+            Error::Unauthorized { .. } => Some(StatusCode::UNAUTHORIZED),
+            Error::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Error::Conflict { .. } => Some(StatusCode::CONFLICT),
+            _ => None,
+        }
+    }
+
+    /// The headers and body of the response the daemon gave, for errors
+    /// returned by the daemon
+    pub fn response(&self) -> Option<&RawResponse> {
+        match self.inner() {
+            Error::Fault { response, .. }
+            | Error::NotModified { response }
+            | Error::Unauthorized { response, .. }
+            | Error::NotFound { response, .. }
+            | Error::Conflict { response, .. }
+            | Error::ServerError { response, .. } => response.as_ref().map(|r| &**r),
+            _ => None,
+        }
+    }
 }
 
 impl From<SerdeError> for Error {
@@ -47,20 +250,91 @@ impl fmt::Display for Error {
         f: &mut fmt::Formatter,
     ) -> fmt::Result {
         write!(f, "Docker Error: ")?;
+        self.fmt_inner(f)
+    }
+}
+
+impl Error {
+    fn fmt_inner(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
         match self {
-            Error::SerdeJsonError(ref err) => err.fmt(f),
-            Error::Http(ref err) => err.fmt(f),
-            Error::Hyper(ref err) => err.fmt(f),
-            Error::IO(ref err) => err.fmt(f),
-            Error::Encoding(ref err) => err.fmt(f),
+            Error::SerdeJsonError(ref err) => write!(f, "{}", err),
+            Error::Http(ref err) => write!(f, "{}", err),
+            Error::Hyper(ref err) => write!(f, "{}", err),
+            Error::IO(ref err) => write!(f, "{}", err),
+            Error::Encoding(ref err) => write!(f, "{}", err),
             Error::InvalidResponse(ref cause) => {
                 write!(f, "Response doesn't have the expected format: {}", cause)
             }
             Error::Fault { code, .. } => write!(f, "{}", code),
+            Error::NotModified { .. } => write!(f, "Not modified"),
+            Error::Unauthorized { ref message, .. } => write!(f, "Unauthorized: {}", message),
+            Error::NotFound { ref message, .. } => write!(f, "Not found: {}", message),
+            Error::Conflict { ref message, .. } => write!(f, "Conflict: {}", message),
+            Error::ServerError {
+                code, ref message, ..
+            } => write!(f, "{}: {}", code, message),
             Error::ConnectionNotUpgraded => write!(
                 f,
                 "expected the docker host to upgrade the HTTP connection but it did not"
             ),
+            Error::InvalidStreamType(stream_type) => write!(
+                f,
+                "Unsupported stream of type {} in container output",
+                stream_type
+            ),
+            Error::InvalidApiVersion(ref version) => {
+                write!(f, "Invalid docker API version: {}", version)
+            }
+            Error::Tls(ref cause) => write!(f, "Invalid TLS configuration: {}", cause),
+            Error::ContextNotFound(ref name) => write!(f, "No docker context named {}", name),
+            Error::InvalidHost(ref cause) => write!(f, "Invalid docker host: {}", cause),
+            Error::InvalidInput(ref cause) => write!(f, "Invalid input: {}", cause),
+            Error::SocketUnavailable {
+                ref path,
+                ref error,
+            } => {
+                write!(
+                    f,
+                    "Cannot connect to the docker daemon at unix://{}: {}",
+                    path, error
+                )?;
+                match error.kind() {
+                    ErrorKind::PermissionDenied => write!(
+                        f,
+                        ". Is the current user in the docker group, or allowed to access the socket?"
+                    ),
+                    _ => write!(f, ". Is the docker daemon running?"),
+                }
+            }
+            Error::Timeout => write!(f, "Timed out waiting on the docker daemon"),
+            Error::StreamIdle(timeout) => {
+                write!(
+                    f,
+                    "Received nothing from the docker daemon for {:?}",
+                    timeout
+                )
+            }
+            Error::TooLong(limit) => write!(
+                f,
+                "Received a line or frame longer than the limit of {} bytes",
+                limit
+            ),
+            Error::OperationFailed(ref message) => write!(f, "{}", message),
+            Error::Cancelled => write!(f, "The operation was cancelled"),
+            Error::Lagged(missed) => write!(f, "Fell behind and missed {} items", missed),
+            Error::Shared(ref error) => error.fmt_inner(f),
+            Error::Request {
+                ref method,
+                ref endpoint,
+                ref error,
+            } => {
+                // skip the "Docker Error: " prefix of the wrapped error
+                write!(f, "{} {}: ", method, endpoint)?;
+                error.fmt_inner(f)
+            }
         }
     }
 }
@@ -74,8 +348,28 @@ impl StdError for Error {
             Error::IO(e) => e.description(),
             Error::Encoding(e) => e.description(),
             Error::InvalidResponse(msg) => msg.as_str(),
-            Error::Fault { message, .. } => message.as_str(),
+            Error::Fault { message, .. }
+            | Error::Unauthorized { message, .. }
+            | Error::NotFound { message, .. }
+            | Error::Conflict { message, .. }
+            | Error::ServerError { message, .. } => message.as_str(),
+            Error::NotModified { .. } => "not modified",
             Error::ConnectionNotUpgraded => "connection not upgraded",
+            Error::InvalidStreamType(_) => "invalid stream type",
+            Error::InvalidApiVersion(_) => "invalid api version",
+            Error::Tls(cause) => cause.as_str(),
+            Error::ContextNotFound(_) => "context not found",
+            Error::InvalidHost(_) => "invalid host",
+            Error::InvalidInput(cause) => cause.as_str(),
+            Error::SocketUnavailable { .. } => "docker socket unavailable",
+            Error::Timeout => "timed out",
+            Error::StreamIdle(_) => "stream idle",
+            Error::TooLong(_) => "too long",
+            Error::OperationFailed(message) => message.as_str(),
+            Error::Cancelled => "cancelled",
+            Error::Lagged(_) => "lagged",
+            Error::Shared(error) => error.description(),
+            Error::Request { error, .. } => error.description(),
         }
     }
 
@@ -84,7 +378,10 @@ impl StdError for Error {
             Error::SerdeJsonError(ref err) => Some(err),
             Error::Http(ref err) => Some(err),
             Error::IO(ref err) => Some(err),
+            Error::SocketUnavailable { ref error, .. } => Some(error),
             Error::Encoding(e) => Some(e),
+            Error::Request { error, .. } => Some(error.as_ref()),
+            Error::Shared(error) => Some(error.as_ref()),
             _ => None,
         }
     }
@@ -0,0 +1,544 @@
+//! Shared subscriptions to the docker events stream
+
+use crate::{builder::EventsOptions, models::Event, retry, Docker, Error};
+use futures::{task::AtomicTask, Async, Future, Poll, Stream};
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+/// How many events each subscription of an `EventBus` buffers by default
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Holds a single connection to the docker events endpoint and fans
+/// each event out to any number of subscribers.
+///
+/// Subscribers only receive events emitted after they subscribed. Each
+/// subscription buffers up to a fixed number of events. A subscription which
+/// falls further behind loses its oldest events, and its next poll fails with
+/// `Error::Lagged` saying how many it missed, after which it carries on with
+/// the events it still has. If the connection fails, every subscription ends
+/// with the error, as an `Error::Shared`.
+///
+/// ```no_run
+/// use shiplift::{events::EventBus, Docker};
+/// use tokio::prelude::{Future, Stream};
+///
+/// let docker = Docker::new();
+/// let (bus, connection) = EventBus::new(&docker, &Default::default());
+/// let printer = bus.subscribe().for_each(|e| {
+///     println!("event -> {:?}", e);
+///     Ok(())
+/// });
+///
+/// tokio::run(
+///     connection
+///         .join(printer)
+///         .map(|_| ())
+///         .map_err(|e| eprintln!("Error: {}", e)),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct EventBus {
+    subscribers: Subscribers<Event>,
+}
+
+impl EventBus {
+    /// Creates a new event bus along with the future driving its connection.
+    ///
+    /// The future must be spawned or otherwise polled for subscribers to
+    /// receive events. It resolves once the events stream ends, at which
+    /// point all subscriptions end too.
+    pub fn new(
+        docker: &Docker,
+        opts: &EventsOptions,
+    ) -> (EventBus, impl Future<Item = (), Error = Error>) {
+        EventBus::with_capacity(docker, opts, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new event bus like `new`, whose subscriptions each buffer up
+    /// to `capacity` events
+    pub fn with_capacity(
+        docker: &Docker,
+        opts: &EventsOptions,
+        capacity: usize,
+    ) -> (EventBus, impl Future<Item = (), Error = Error>) {
+        let subscribers = Subscribers::new(capacity);
+        let connection = drive(docker.events(opts), subscribers.clone());
+        (EventBus { subscribers }, connection)
+    }
+
+    /// Returns a new stream of the events received from now on
+    pub fn subscribe(&self) -> EventSubscription {
+        EventSubscription {
+            queue: self.subscribers.subscribe(),
+        }
+    }
+}
+
+/// A stream of events received by an `EventBus`
+pub struct EventSubscription {
+    queue: Receiver<Event>,
+}
+
+impl Stream for EventSubscription {
+    type Item = Event;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Event>, Error> {
+        self.queue.poll()
+    }
+}
+
+/// A stream of docker events which reconnects whenever its connection is
+/// lost, such as when the daemon restarts, resuming from the last event
+/// received.
+///
+/// Unless the options set an `until` point, the stream only ends with an
+/// error the daemon would return again, such as an invalid filter.
+///
+/// ```no_run
+/// use shiplift::Docker;
+/// use tokio::prelude::{Future, Stream};
+///
+/// let docker = Docker::new();
+/// tokio::run(
+///     docker
+///         .events_reconnecting(&Default::default())
+///         .for_each(|e| {
+///             println!("event -> {:?}", e);
+///             Ok(())
+///         })
+///         .map_err(|e| eprintln!("Error: {}", e)),
+/// );
+/// ```
+pub struct ReconnectingEvents {
+    docker: Docker,
+    opts: EventsOptions,
+    state: ConnectionState,
+    reconnect_delay: Duration,
+    /// The last event received, and how many were received at the same time
+    last: Option<(Event, usize)>,
+    /// How many events sent again by a new connection remain to be skipped
+    replayed: usize,
+}
+
+enum ConnectionState {
+    Connected(Box<dyn Stream<Item = Event, Error = Error> + Send>),
+    Waiting(Delay),
+}
+
+impl ReconnectingEvents {
+    pub(crate) fn new(
+        docker: &Docker,
+        opts: &EventsOptions,
+    ) -> ReconnectingEvents {
+        ReconnectingEvents {
+            docker: docker.clone(),
+            opts: opts.clone(),
+            state: ConnectionState::Connected(Box::new(docker.events(opts))),
+            reconnect_delay: Duration::from_secs(1),
+            last: None,
+            replayed: 0,
+        }
+    }
+
+    /// How long to wait before reconnecting. Defaults to 1s
+    pub fn reconnect_delay(
+        mut self,
+        delay: Duration,
+    ) -> ReconnectingEvents {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    fn connect(&mut self) -> ConnectionState {
+        // `since` includes events emitted at the time of the last one, which
+        // have already been received
+        let opts = match self.last {
+            Some((ref event, seen)) => {
+                self.replayed = seen;
+                self.opts.resume_from(event)
+            }
+            None => self.opts.clone(),
+        };
+        ConnectionState::Connected(Box::new(self.docker.events(&opts)))
+    }
+
+    fn reconnect_after_delay(&self) -> ConnectionState {
+        ConnectionState::Waiting(Delay::new(Instant::now() + self.reconnect_delay))
+    }
+
+    /// Records a received event, returning whether it was already received
+    /// over a previous connection
+    fn received(
+        &mut self,
+        event: &Event,
+    ) -> bool {
+        match self.last {
+            Some((ref last, ref mut seen)) if last.time_nano == event.time_nano => {
+                if self.replayed > 0 {
+                    self.replayed -= 1;
+                    return true;
+                }
+                *seen += 1;
+            }
+            _ => {
+                self.replayed = 0;
+                self.last = Some((event.clone(), 1));
+            }
+        }
+        false
+    }
+}
+
+impl Stream for ReconnectingEvents {
+    type Item = Event;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Event>, Error> {
+        loop {
+            let next = match self.state {
+                ConnectionState::Connected(ref mut events) => match events.poll() {
+                    Ok(Async::Ready(Some(event))) => {
+                        if self.received(&event) {
+                            continue;
+                        }
+                        return Ok(Async::Ready(Some(event)));
+                    }
+                    Ok(Async::Ready(None)) if self.opts.is_bounded() => {
+                        return Ok(Async::Ready(None))
+                    }
+                    Ok(Async::Ready(None)) => self.reconnect_after_delay(),
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(ref e) if retry::is_transient(e) => self.reconnect_after_delay(),
+                    Err(e) => return Err(e),
+                },
+                ConnectionState::Waiting(ref mut delay) => match delay.poll() {
+                    Ok(Async::Ready(())) => self.connect(),
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(e) => {
+                        return Err(Error::IO(io::Error::new(
+                            io::ErrorKind::Other,
+                            e.to_string(),
+                        )))
+                    }
+                },
+            };
+            self.state = next;
+        }
+    }
+}
+
+/// The queues of all live subscriptions, or `None` once the source has ended
+struct Subscribers<T> {
+    queues: Arc<Mutex<Option<Vec<Weak<Queue<T>>>>>>,
+    capacity: usize,
+}
+
+impl<T> Clone for Subscribers<T> {
+    fn clone(&self) -> Self {
+        Subscribers {
+            queues: self.queues.clone(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// The items published to one subscription which it has yet to take
+struct Queue<T> {
+    state: Mutex<QueueState<T>>,
+    task: AtomicTask,
+}
+
+struct QueueState<T> {
+    items: VecDeque<T>,
+    /// How many items were dropped, since it was last reported, to stay
+    /// within capacity
+    lagged: u64,
+    end: End,
+}
+
+enum End {
+    Open,
+    Failed(Arc<Error>),
+    Finished,
+}
+
+impl<T> Queue<T> {
+    fn new(
+        capacity: usize,
+        end: End,
+    ) -> Queue<T> {
+        Queue {
+            state: Mutex::new(QueueState {
+                items: VecDeque::with_capacity(capacity.min(DEFAULT_CAPACITY)),
+                lagged: 0,
+                end,
+            }),
+            task: AtomicTask::new(),
+        }
+    }
+}
+
+impl<T> Subscribers<T>
+where
+    T: Clone,
+{
+    fn new(capacity: usize) -> Self {
+        Subscribers {
+            queues: Arc::new(Mutex::new(Some(Vec::new()))),
+            // a subscription must be able to hold at least the latest item
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn subscribe(&self) -> Receiver<T> {
+        let mut queues = self.queues.lock().unwrap();
+        let queue = match *queues {
+            Some(ref mut queues) => {
+                let queue = Arc::new(Queue::new(self.capacity, End::Open));
+                queues.push(Arc::downgrade(&queue));
+                queue
+            }
+            // the source has already ended
+            None => Arc::new(Queue::new(0, End::Finished)),
+        };
+        Receiver { queue }
+    }
+
+    fn publish(
+        &self,
+        item: &T,
+    ) {
+        if let Some(ref mut queues) = *self.queues.lock().unwrap() {
+            // drop the queues of subscriptions which have gone away
+            queues.retain(|queue| match queue.upgrade() {
+                Some(queue) => {
+                    {
+                        let mut state = queue.state.lock().unwrap();
+                        if state.items.len() == self.capacity {
+                            state.items.pop_front();
+                            state.lagged += 1;
+                        }
+                        state.items.push_back(item.clone());
+                    }
+                    queue.task.notify();
+                    true
+                }
+                None => false,
+            });
+        }
+    }
+
+    /// Ends every subscription once it has taken the items it holds, with
+    /// `error` if the source failed
+    fn close(
+        &self,
+        error: Option<Arc<Error>>,
+    ) {
+        let queues = self.queues.lock().unwrap().take();
+        for queue in queues.iter().flatten().filter_map(Weak::upgrade) {
+            queue.state.lock().unwrap().end = match error {
+                Some(ref error) => End::Failed(error.clone()),
+                None => End::Finished,
+            };
+            queue.task.notify();
+        }
+    }
+}
+
+/// The receiving end of one subscription
+struct Receiver<T> {
+    queue: Arc<Queue<T>>,
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<T>, Error> {
+        self.queue.task.register();
+        let mut state = self.queue.state.lock().unwrap();
+        if state.lagged > 0 {
+            let missed = state.lagged;
+            state.lagged = 0;
+            return Err(Error::Lagged(missed));
+        }
+        if let Some(item) = state.items.pop_front() {
+            return Ok(Async::Ready(Some(item)));
+        }
+        match state.end {
+            End::Open => Ok(Async::NotReady),
+            End::Finished => Ok(Async::Ready(None)),
+            End::Failed(ref error) => {
+                let error = Error::Shared(error.clone());
+                state.end = End::Finished;
+                Err(error)
+            }
+        }
+    }
+}
+
+/// Publishes every item of a stream to a set of subscribers, closing all
+/// subscriptions once the stream ends, and passing on its error if it fails
+fn drive<S, T>(
+    stream: S,
+    subscribers: Subscribers<T>,
+) -> impl Future<Item = (), Error = Error>
+where
+    S: Stream<Item = T, Error = Error>,
+    T: Clone,
+{
+    let publisher = subscribers.clone();
+    stream
+        .for_each(move |item| {
+            publisher.publish(&item);
+            Ok(())
+        })
+        .then(move |result| match result {
+            Ok(()) => {
+                subscribers.close(None);
+                Ok(())
+            }
+            Err(error) => {
+                let error = Arc::new(error);
+                subscribers.close(Some(error.clone()));
+                Err(Error::Shared(error))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{drive, Subscribers};
+    use crate::{testing::MockTransport, Docker, Error};
+    use futures::{stream, Future, Stream};
+    use hyper::{Method, StatusCode};
+    use std::time::Duration;
+    use tokio::runtime::current_thread::block_on_all;
+
+    #[test]
+    fn fans_out_to_every_subscriber() {
+        let subscribers = Subscribers::new(8);
+        let first = subscribers.subscribe();
+        let second = subscribers.subscribe();
+
+        drive(
+            stream::iter_ok::<_, Error>(vec![1, 2, 3]),
+            subscribers.clone(),
+        )
+        .wait()
+        .unwrap();
+
+        assert_eq!(vec![1, 2, 3], first.collect().wait().unwrap());
+        assert_eq!(vec![1, 2, 3], second.collect().wait().unwrap());
+    }
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let subscribers = Subscribers::new(8);
+        let kept = subscribers.subscribe();
+        drop(subscribers.subscribe());
+
+        subscribers.publish(&1);
+
+        assert_eq!(
+            1,
+            subscribers.queues.lock().unwrap().as_ref().unwrap().len()
+        );
+        subscribers.close(None);
+        assert_eq!(vec![1], kept.collect().wait().unwrap());
+    }
+
+    #[test]
+    fn subscribing_after_close_ends_immediately() {
+        let subscribers = Subscribers::<u32>::new(8);
+        subscribers.close(None);
+
+        assert!(subscribers.subscribe().collect().wait().unwrap().is_empty());
+    }
+
+    #[test]
+    fn slow_subscribers_lag_behind() {
+        let subscribers = Subscribers::new(2);
+        let slow = subscribers.subscribe();
+        for item in 1..=5 {
+            subscribers.publish(&item);
+        }
+        subscribers.close(None);
+
+        let mut items = slow.wait();
+        match items.next() {
+            Some(Err(Error::Lagged(3))) => (),
+            other => panic!("expected the subscriber to lag, got {:?}", other),
+        }
+        assert_eq!(vec![4, 5], items.map(Result::unwrap).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn connection_errors_reach_every_subscriber() {
+        let subscribers = Subscribers::new(8);
+        let first = subscribers.subscribe();
+        let second = subscribers.subscribe();
+
+        let source = stream::iter_result(vec![Ok(1), Err(Error::InvalidApiVersion("x".into()))]);
+        assert!(drive(source, subscribers).wait().is_err());
+
+        for subscription in vec![first, second] {
+            let mut items = subscription.wait();
+            assert_eq!(1, items.next().unwrap().unwrap());
+            match items.next() {
+                Some(Err(ref e)) => match e.inner() {
+                    Error::InvalidApiVersion(_) => (),
+                    other => panic!("unexpected error {:?}", other),
+                },
+                other => panic!("expected the connection's error, got {:?}", other),
+            }
+            assert!(items.next().is_none());
+        }
+    }
+
+    fn event(
+        id: &str,
+        time_nano: u64,
+    ) -> String {
+        format!(
+            r#"{{"Type":"container","Action":"start","Actor":{{"ID":"{}","Attributes":{{}}}},"time":0,"timeNano":{}}}"#,
+            id, time_nano
+        ) + "\n"
+    }
+
+    #[test]
+    fn reconnects_from_the_last_event() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/events")
+            .query("since", "0.000000002")
+            .respond_chunks(
+                StatusCode::OK,
+                vec![event("b", 2), event("c", 2), event("d", 3)],
+            );
+        mock.on(Method::GET, "/events")
+            .respond_chunks(StatusCode::OK, vec![event("a", 1), event("b", 2)]);
+        let docker = Docker::from_transport(mock);
+
+        let events = block_on_all(
+            docker
+                .events_reconnecting(&Default::default())
+                .reconnect_delay(Duration::from_millis(0))
+                .take(4)
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["a", "b", "c", "d"],
+            events
+                .iter()
+                .map(|e| e.actor.id.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+}
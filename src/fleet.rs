@@ -0,0 +1,236 @@
+//! Managing several docker daemons at once, such as a fleet of build hosts
+//!
+//! ```no_run
+//! use futures::Future;
+//! use shiplift::{fleet::DockerSet, Docker};
+//!
+//! let mut dockers = DockerSet::new();
+//! dockers.insert("local", Docker::new());
+//! dockers.insert("build-1", Docker::host("tcp://build-1:2375").unwrap());
+//!
+//! let counts = dockers
+//!     .each(|docker| docker.containers().list(&Default::default()))
+//!     .map(|results| {
+//!         for (name, containers) in results {
+//!             match containers {
+//!                 Ok(containers) => println!("{}: {} containers", name, containers.len()),
+//!                 Err(e) => println!("{}: {}", name, e),
+//!             }
+//!         }
+//!     });
+//! tokio::run(counts.map_err(|e| eprintln!("Error: {}", e)));
+//! ```
+
+use crate::{Docker, Error, Result};
+use futures::{future, Future, IntoFuture};
+use std::collections::{btree_map, BTreeMap};
+
+/// Docker clients for different daemons, each known by a name
+#[derive(Clone, Default)]
+pub struct DockerSet {
+    dockers: BTreeMap<String, Docker>,
+}
+
+impl DockerSet {
+    pub fn new() -> DockerSet {
+        DockerSet::default()
+    }
+
+    /// Adds a daemon's client, returning the one it replaces if the name was
+    /// already in use
+    pub fn insert<N>(
+        &mut self,
+        name: N,
+        docker: Docker,
+    ) -> Option<Docker>
+    where
+        N: Into<String>,
+    {
+        self.dockers.insert(name.into(), docker)
+    }
+
+    pub fn remove(
+        &mut self,
+        name: &str,
+    ) -> Option<Docker> {
+        self.dockers.remove(name)
+    }
+
+    pub fn get(
+        &self,
+        name: &str,
+    ) -> Option<&Docker> {
+        self.dockers.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dockers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dockers.is_empty()
+    }
+
+    /// The names of the daemons, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.dockers.keys().map(String::as_str)
+    }
+
+    /// The daemons' clients by name, in order of their names
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.dockers.iter(),
+        }
+    }
+
+    /// Runs a query against every daemon at once, resolving to each daemon's
+    /// result by name once they've all finished. Daemons failing doesn't stop
+    /// the others, so this never fails
+    pub fn each<F, R>(
+        &self,
+        mut query: F,
+    ) -> impl Future<Item = BTreeMap<String, Result<R::Item>>, Error = Error>
+    where
+        F: FnMut(&Docker) -> R,
+        R: IntoFuture<Error = Error>,
+    {
+        let queries = self
+            .dockers
+            .iter()
+            .map(|(name, docker)| {
+                let name = name.clone();
+                query(docker)
+                    .into_future()
+                    .then(move |result| Ok::<_, Error>((name, result)))
+            })
+            .collect::<Vec<_>>();
+        future::join_all(queries).map(|results| results.into_iter().collect())
+    }
+
+    /// Runs a query against every daemon at once, resolving to each daemon's
+    /// result by name, or failing as soon as any daemon does
+    pub fn all<F, R>(
+        &self,
+        mut query: F,
+    ) -> impl Future<Item = BTreeMap<String, R::Item>, Error = Error>
+    where
+        F: FnMut(&Docker) -> R,
+        R: IntoFuture<Error = Error>,
+    {
+        let queries = self
+            .dockers
+            .iter()
+            .map(|(name, docker)| {
+                let name = name.clone();
+                query(docker).into_future().map(move |item| (name, item))
+            })
+            .collect::<Vec<_>>();
+        future::join_all(queries).map(|results| results.into_iter().collect())
+    }
+}
+
+impl<N> Extend<(N, Docker)> for DockerSet
+where
+    N: Into<String>,
+{
+    fn extend<I>(
+        &mut self,
+        dockers: I,
+    ) where
+        I: IntoIterator<Item = (N, Docker)>,
+    {
+        for (name, docker) in dockers {
+            self.insert(name, docker);
+        }
+    }
+}
+
+impl<N> std::iter::FromIterator<(N, Docker)> for DockerSet
+where
+    N: Into<String>,
+{
+    fn from_iter<I>(dockers: I) -> DockerSet
+    where
+        I: IntoIterator<Item = (N, Docker)>,
+    {
+        let mut set = DockerSet::new();
+        set.extend(dockers);
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a DockerSet {
+    type Item = (&'a str, &'a Docker);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the clients of a `DockerSet`, by name
+pub struct Iter<'a> {
+    inner: btree_map::Iter<'a, String, Docker>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a Docker);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(name, docker)| (name.as_str(), docker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DockerSet;
+    use crate::{testing::MockTransport, Docker};
+    use hyper::{Method, StatusCode};
+    use tokio::runtime::current_thread::block_on_all;
+
+    fn daemon(status: StatusCode) -> Docker {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(status, "OK");
+        Docker::from_transport(mock)
+    }
+
+    fn set() -> DockerSet {
+        vec![
+            ("b", daemon(StatusCode::OK)),
+            ("a", daemon(StatusCode::OK)),
+            ("down", daemon(StatusCode::INTERNAL_SERVER_ERROR)),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn each_collects_every_result() {
+        let dockers = set();
+        assert_eq!(vec!["a", "b", "down"], dockers.names().collect::<Vec<_>>());
+
+        let results = block_on_all(dockers.each(Docker::ping)).unwrap();
+        assert_eq!(3, results.len());
+        assert_eq!("OK", results["a"].as_ref().unwrap());
+        assert_eq!("OK", results["b"].as_ref().unwrap());
+        assert!(results["down"].is_err());
+    }
+
+    #[test]
+    fn all_fails_when_any_daemon_does() {
+        let mut dockers = set();
+        assert!(block_on_all(dockers.all(Docker::ping)).is_err());
+
+        dockers.remove("down");
+        let results = block_on_all(dockers.all(Docker::ping)).unwrap();
+        assert_eq!(
+            vec![("a", "OK"), ("b", "OK")],
+            results
+                .iter()
+                .map(|(name, ping)| (name.as_str(), ping.as_str()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
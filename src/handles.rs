@@ -0,0 +1,87 @@
+//! Conversions from listed and created objects to the interfaces for them
+
+use crate::{models, Container, Docker, Image, Network, Plugin, Secret, Volume};
+
+impl models::Image {
+    /// An interface to the listed image
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Image<'a, 'b> {
+        Image::new(docker, self.id.as_str())
+    }
+}
+
+impl models::Container {
+    /// An interface to the listed container, to stop it or follow its logs
+    /// without looking it up again
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Container<'a, 'b> {
+        Container::new(docker, self.id.as_str())
+    }
+}
+
+impl models::ContainerCreateInfo {
+    /// An interface to the created container
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Container<'a, 'b> {
+        Container::new(docker, self.id.as_str())
+    }
+}
+
+impl models::NetworkDetails {
+    /// An interface to the listed network
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Network<'a, 'b> {
+        Network::new(docker, self.id.as_str())
+    }
+}
+
+impl models::Plugin {
+    /// An interface to the listed plugin
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Plugin<'a, 'b> {
+        Plugin::new(docker, self.name.as_str())
+    }
+}
+
+impl models::Volume {
+    /// An interface to the listed volume
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Volume<'a, 'b> {
+        Volume::new(docker, self.name.as_str())
+    }
+}
+
+impl models::Secret {
+    /// An interface to the listed secret
+    pub fn handle<'a, 'b>(
+        &'b self,
+        docker: &'a Docker,
+    ) -> Secret<'a, 'b> {
+        Secret::new(docker, self.id.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::Container, testing::MockTransport, Docker};
+
+    #[test]
+    fn list_items_convert_to_handles() {
+        let docker = Docker::from_transport(MockTransport::new());
+        let containers: Vec<Container> =
+            serde_json::from_str(include_str!("../fixtures/api/v1.41/containers.json")).unwrap();
+        assert_eq!(containers[0].id, containers[0].handle(&docker).id());
+    }
+}
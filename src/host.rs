@@ -0,0 +1,107 @@
+//! Parsing of docker host urls, as given to `DOCKER_HOST` or `docker -H`
+
+use crate::{Error, Result};
+use hyper::Uri;
+
+/// Where a docker daemon listens
+#[derive(Clone, Debug, PartialEq)]
+pub enum DockerHost {
+    /// The path of a Unix socket
+    Unix(String),
+    /// A `tcp://`, `http://` or `https://` url
+    Tcp(Uri),
+}
+
+impl DockerHost {
+    /// Parses a docker host url such as `unix:///var/run/docker.sock` or
+    /// `tcp://127.0.0.1:2375`. As with the docker CLI, urls without a scheme
+    /// are taken to be tcp hosts
+    pub fn parse(host: &str) -> Result<DockerHost> {
+        let host = host.trim();
+        let scheme_end = host.find("://");
+        let scheme = scheme_end.map(|end| &host[..end]);
+        match scheme {
+            Some("unix") => {
+                let path = &host["unix://".len()..];
+                if path.is_empty() {
+                    return Err(invalid(host, "missing socket path"));
+                }
+                Ok(DockerHost::Unix(path.to_owned()))
+            }
+            Some("npipe") => Err(invalid(host, "named pipes are not supported")),
+            Some("ssh") => Err(invalid(host, "ssh hosts are not supported")),
+            Some("tcp") | Some("http") | Some("https") => parse_tcp(host, host),
+            Some(_) => Err(invalid(host, "unsupported scheme")),
+            None => parse_tcp(&format!("tcp://{}", host), host),
+        }
+    }
+}
+
+fn parse_tcp(
+    url: &str,
+    host: &str,
+) -> Result<DockerHost> {
+    match url.parse::<Uri>() {
+        Ok(ref uri) if uri.host().map_or(true, str::is_empty) => {
+            Err(invalid(host, "missing host name"))
+        }
+        Ok(uri) => Ok(DockerHost::Tcp(uri)),
+        Err(e) => Err(invalid(host, &e.to_string())),
+    }
+}
+
+fn invalid(
+    host: &str,
+    reason: &str,
+) -> Error {
+    Error::InvalidHost(format!("{} ({})", host, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DockerHost;
+
+    fn tcp(host: &str) -> (String, String, Option<u16>) {
+        match DockerHost::parse(host).unwrap() {
+            DockerHost::Tcp(uri) => (
+                uri.scheme_part().unwrap().to_string(),
+                uri.host().unwrap().to_owned(),
+                uri.port_u16(),
+            ),
+            other => panic!("unexpected host {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_unix_sockets() {
+        assert_eq!(
+            DockerHost::Unix("/var/run/docker.sock".to_owned()),
+            DockerHost::parse("unix:///var/run/docker.sock").unwrap()
+        );
+        assert!(DockerHost::parse("unix://").is_err());
+    }
+
+    #[test]
+    fn parses_tcp_hosts() {
+        assert_eq!(
+            ("tcp".to_owned(), "127.0.0.1".to_owned(), Some(2375)),
+            tcp("tcp://127.0.0.1:2375")
+        );
+        assert_eq!(
+            ("https".to_owned(), "docker.example.com".to_owned(), None),
+            tcp("https://docker.example.com")
+        );
+        assert_eq!(
+            ("tcp".to_owned(), "localhost".to_owned(), Some(2376)),
+            tcp("localhost:2376")
+        );
+        assert!(DockerHost::parse("tcp://").is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_schemes() {
+        assert!(DockerHost::parse("npipe:////./pipe/docker_engine").is_err());
+        assert!(DockerHost::parse("ssh://user@docker.example.com").is_err());
+        assert!(DockerHost::parse("ftp://docker.example.com").is_err());
+    }
+}
@@ -16,47 +16,109 @@
 //! tokio::run(fut);
 //! ```
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod builder;
+pub mod cancel;
+pub mod codec;
+#[cfg(feature = "compose")]
+pub mod compose;
+pub mod context;
 pub mod errors;
+pub mod events;
+pub mod fleet;
+pub mod metrics;
+pub mod models;
+pub mod prelude;
+pub mod progress;
 pub mod read;
-pub mod rep;
+pub mod registry;
+pub mod retry;
+pub mod testing;
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+pub mod tls;
+#[cfg(feature = "tracing")]
+pub mod trace;
 pub mod transport;
 pub mod tty;
-
+pub mod version;
+
+#[cfg(feature = "debug-http")]
+mod debug;
+mod handles;
+mod host;
+mod proxy;
+mod pull;
 mod tarball;
 
+/// The former home of `models`
+pub use crate::models as rep;
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+pub use crate::tls::TlsConfig;
 pub use crate::{
     builder::{
-        BuildOptions, ContainerConnectionOptions, ContainerFilter, ContainerListOptions,
-        ContainerOptions, EventsOptions, ExecContainerOptions, ImageFilter, ImageListOptions,
-        LogsOptions, NetworkCreateOptions, NetworkListOptions, PullOptions, RegistryAuth,
-        RmContainerOptions, TagOptions, VolumeCreateOptions,
+        ArchiveOptions, BuildOptions, ContainerConnectionOptions, ContainerFilter,
+        ContainerListOptions, ContainerOptions, ContainerPruneOptions, CopyFileOptions,
+        DataUsageOptions, DataUsageType, EventFilter, EventsOptions, ExecContainerOptions,
+        ExportOptions, Filters, ImageFilter, ImageListOptions, ImagePruneOptions, ImportOptions,
+        Isolation, LogsOptions, NetworkCreateOptions, NetworkListOptions, NetworkPruneOptions,
+        PluginFilter, PluginInstallOptions, PluginListOptions, PullManyOptions, PullOptions,
+        RegistryAuth, RmContainerOptions, SecretCreateOptions, TagOptions, Tail,
+        VolumeCreateOptions, VolumeListOptions, VolumePruneOptions, WaitOptions,
     },
-    errors::Error,
+    errors::{Error, RawResponse},
+    version::ApiVersion,
 };
 use crate::{
-    read::StreamReader,
-    rep::{
-        Change, Container as ContainerRep, ContainerCreateInfo, ContainerDetails, Event, Exit,
-        History, Image as ImageRep, ImageDetails, Info, NetworkCreateInfo,
-        NetworkDetails as NetworkInfo, SearchResult, Stats, Status, Top, Version,
-        Volume as VolumeRep, VolumeCreateInfo, Volumes as VolumesRep,
+    cancel::{CancelHandle, Cancellable},
+    codec::{JsonArrayDecoder, LinesDecoder, StreamLimits},
+    context::Context,
+    host::DockerHost,
+    metrics::{Metered, Metrics},
+    models::{
+        AuthStatus, Capabilities, Change, Container as ContainerRep, ContainerCreateInfo,
+        ContainerDetails, ContainerPruneInfo, DataUsage, DistributionInspect, Event,
+        ExecCreateInfo, ExecDetails, Exit, History, Image as ImageRep, ImageDetails,
+        ImagePruneInfo, Info, NetworkCreateInfo, NetworkDetails as NetworkInfo, NetworkPruneInfo,
+        PathStat, PingInfo, Plugin as PluginRep, PluginPrivilege, Progress, SearchResult,
+        Secret as SecretRep, SecretCreateInfo, Stats, Status, Top, Version, Volume as VolumeRep,
+        VolumeCreateInfo, VolumePruneInfo, Volumes as VolumesRep,
     },
-    transport::{tar, Transport},
+    progress::ProgressReporter,
+    retry::RetryPolicy,
+    transport::{deadline, tar, ConcurrencyLimit, HttpTransport, Intercept, Middleware, Transport},
     tty::TtyDecoder,
 };
-use futures::{future::Either, Future, IntoFuture, Stream};
-use hyper::{client::HttpConnector, Body, Client, Method, Uri};
+use bytes::{Bytes, BytesMut};
+use futures::{
+    future::{Either, ExecuteError, Executor},
+    stream, Future, IntoFuture, Stream,
+};
+use hyper::{
+    client::HttpConnector,
+    header::{self, HeaderName, HeaderValue},
+    Body, Chunk, Client, HeaderMap, Method, StatusCode, Uri,
+};
 #[cfg(feature = "tls")]
 use hyper_openssl::HttpsConnector;
 #[cfg(feature = "unix-socket")]
 use hyperlocal::UnixConnector;
 use mime::Mime;
-#[cfg(feature = "tls")]
-use openssl::ssl::{SslConnector, SslFiletype, SslMethod};
 use serde_json::Value;
-use std::{borrow::Cow, env, io::Read, iter, path::Path, time::Duration};
-use tokio_codec::{FramedRead, LinesCodec};
+use std::{
+    borrow::Cow,
+    env,
+    error::Error as StdError,
+    fmt,
+    fs::File,
+    io::{self, Read},
+    iter,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio_codec::{BytesCodec, FramedRead};
+use tokio_io::AsyncRead;
 use url::form_urlencoded;
 
 /// Represents the result of all docker operations
@@ -66,6 +128,9 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Clone)]
 pub struct Docker {
     transport: Transport,
+    version: Option<ApiVersion>,
+    retry: Option<RetryPolicy>,
+    limits: StreamLimits,
 }
 
 /// Interface for accessing and manipulating a named docker image
@@ -95,6 +160,20 @@ impl<'a, 'b> Image<'a, 'b> {
             .get_json(&format!("/images/{}/json", self.name)[..])
     }
 
+    /// Asks the daemon to look the image's reference up in its registry, returning
+    /// the descriptor of the manifest it resolves to and the platforms it's
+    /// available for, without pulling it
+    pub fn distribution(
+        &self,
+        auth: Option<&RegistryAuth>,
+    ) -> impl Future<Item = DistributionInspect, Error = Error> {
+        let headers = auth.map(|a| iter::once(("X-Registry-Auth", a.serialize())));
+        self.docker
+            .stream_get_with_headers(&format!("/distribution/{}/json", self.name), headers)
+            .concat2()
+            .and_then(|body| serde_json::from_slice(&body).map_err(Error::from))
+    }
+
     /// Lists the history of the images set of changes
     pub fn history(&self) -> impl Future<Item = Vec<History>, Error = Error> {
         self.docker
@@ -108,10 +187,41 @@ impl<'a, 'b> Image<'a, 'b> {
     }
 
     /// Export this image to a tarball
-    pub fn export(&self) -> impl Stream<Item = Vec<u8>, Error = Error> {
+    pub fn export(&self) -> impl Stream<Item = Bytes, Error = Error> {
+        self.export_with(&ExportOptions::default())
+    }
+
+    /// Export this image to a tarball like `export`, with options such as a
+    /// timeout of its own
+    pub fn export_with(
+        &self,
+        opts: &ExportOptions,
+    ) -> impl Stream<Item = Bytes, Error = Error> {
         self.docker
+            .timed(opts.timeout)
             .stream_get(&format!("/images/{}/get", self.name)[..])
-            .map(|c| c.to_vec())
+            .map(Chunk::into_bytes)
+    }
+
+    /// Export this image to a tarball, along with a handle which aborts the export
+    pub fn export_cancellable(
+        &self
+    ) -> (
+        Cancellable<impl Stream<Item = Bytes, Error = Error>>,
+        CancelHandle,
+    ) {
+        cancel::cancellable(self.export())
+    }
+
+    /// Export this image to a tarball, reporting the bytes received to `reporter`
+    pub fn export_with_progress<R>(
+        &self,
+        reporter: R,
+    ) -> impl Stream<Item = Bytes, Error = Error>
+    where
+        R: ProgressReporter,
+    {
+        progress::report_bytes(self.export(), reporter)
     }
 
     /// Adds a tag to an image
@@ -125,6 +235,37 @@ impl<'a, 'b> Image<'a, 'b> {
         }
         self.docker.post::<Body>(&path.join("?"), None).map(|_| ())
     }
+
+    /// Pushes the image to the registry its name refers to, returning a stream of
+    /// the push's progress
+    pub fn push(
+        &self,
+        auth: Option<&RegistryAuth>,
+    ) -> impl Stream<Item = Value, Error = Error> {
+        // the daemon refuses pushes without credentials, even to registries
+        // which don't need them
+        let auth = auth
+            .map(RegistryAuth::serialize)
+            .unwrap_or_else(|| base64::encode("{}"));
+        self.docker.stream_post_json::<Body, _>(
+            &format!("/images/{}/push", self.name)[..],
+            None,
+            Some(iter::once(("X-Registry-Auth", auth))),
+        )
+    }
+
+    /// Pushes the image like `push`, passing its progress to `reporter` and
+    /// resolving to the reporter once the push has finished
+    pub fn push_with_progress<R>(
+        &self,
+        auth: Option<&RegistryAuth>,
+        reporter: R,
+    ) -> impl Future<Item = R, Error = Error>
+    where
+        R: ProgressReporter,
+    {
+        progress::report(self.push(auth), reporter)
+    }
 }
 
 /// Interface for docker images
@@ -143,34 +284,90 @@ impl<'a> Images<'a> {
         &self,
         opts: &BuildOptions,
     ) -> impl Stream<Item = Value, Error = Error> {
+        self.build_with_context(opts, tarball::dir_stream(opts.path.clone()))
+    }
+
+    /// Builds a new image like `build`, along with a handle which aborts the
+    /// build by closing its connection.
+    ///
+    /// With BuildKit, a build given a `build_id` can also be stopped on the
+    /// daemon with `cancel_build`
+    pub fn build_cancellable(
+        &self,
+        opts: &BuildOptions,
+    ) -> (
+        Cancellable<impl Stream<Item = Value, Error = Error>>,
+        CancelHandle,
+    ) {
+        cancel::cancellable(self.build(opts))
+    }
+
+    /// Stops a BuildKit build started with the given `build_id`
+    pub fn cancel_build(
+        &self,
+        build_id: &str,
+    ) -> impl Future<Item = (), Error = Error> {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("id", build_id)
+            .finish();
+        self.docker
+            .post::<Body>(&format!("/build/cancel?{}", query)[..], None)
+            .map(|_| ())
+    }
+
+    /// Builds a new image like `build`, passing its progress to `reporter`
+    /// and resolving to the reporter once the build has finished
+    pub fn build_with_progress<R>(
+        &self,
+        opts: &BuildOptions,
+        reporter: R,
+    ) -> impl Future<Item = R, Error = Error>
+    where
+        R: ProgressReporter,
+    {
+        progress::report(self.build(opts), reporter)
+    }
+
+    /// Builds a new image from a build context streamed as a tarball, such as
+    /// one being read from disk or generated on the fly, rather than from a
+    /// directory. The path of `opts` is ignored
+    ///
+    /// An `AsyncRead` can be streamed with
+    /// `tokio_codec::FramedRead::new(reader, tokio_codec::BytesCodec::new())`
+    pub fn build_with_context<S>(
+        &self,
+        opts: &BuildOptions,
+        context: S,
+    ) -> impl Stream<Item = Value, Error = Error>
+    where
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
         let mut path = vec!["/build".to_owned()];
         if let Some(query) = opts.serialize() {
             path.push(query)
         }
 
-        let mut bytes = vec![];
-
-        match tarball::dir(&mut bytes, &opts.path[..]) {
-            Ok(_) => Box::new(
-                self.docker
-                    .stream_post(
-                        &path.join("?"),
-                        Some((Body::from(bytes), tar())),
-                        None::<iter::Empty<_>>,
-                    )
-                    .map(|r| {
-                        futures::stream::iter_result(
-                            serde_json::Deserializer::from_slice(&r[..])
-                                .into_iter::<Value>()
-                                .collect::<Vec<_>>(),
-                        )
-                        .map_err(Error::from)
-                    })
-                    .flatten(),
-            ) as Box<dyn Stream<Item = Value, Error = Error> + Send>,
-            Err(e) => Box::new(futures::future::err(Error::IO(e)).into_stream())
-                as Box<dyn Stream<Item = Value, Error = Error> + Send>,
-        }
+        let headers = opts
+            .registry_config_header()
+            .map(|config| iter::once(("X-Registry-Config", config)));
+        self.docker
+            .timed(opts.timeout)
+            .stream_post(
+                &path.join("?"),
+                Some((Body::wrap_stream(context), tar())),
+                headers,
+            )
+            .map(|r| {
+                futures::stream::iter_result(
+                    serde_json::Deserializer::from_slice(&r[..])
+                        .into_iter::<Value>()
+                        .collect::<Vec<_>>(),
+                )
+                .map_err(Error::from)
+            })
+            .flatten()
     }
 
     /// Lists the docker images on the current docker host
@@ -185,6 +382,19 @@ impl<'a> Images<'a> {
         self.docker.get_json::<Vec<ImageRep>>(&path.join("?"))
     }
 
+    /// Lists the docker images on the current docker host like `list`,
+    /// yielding each as it's received rather than buffering the whole list
+    pub fn list_stream(
+        &self,
+        opts: &ImageListOptions,
+    ) -> impl Stream<Item = ImageRep, Error = Error> {
+        let mut path = vec!["/images/json".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        self.docker.stream_get_json_array(&path.join("?"))
+    }
+
     /// Returns a reference to a set of operations available for a named image
     pub fn get<'b>(
         &self,
@@ -205,6 +415,38 @@ impl<'a> Images<'a> {
             .get_json::<Vec<SearchResult>>(&format!("/images/search?{}", query)[..])
     }
 
+    /// Search for docker images by term like `search`, authenticating with the
+    /// registry so that private images are included
+    pub fn search_with_auth(
+        &self,
+        term: &str,
+        auth: &RegistryAuth,
+    ) -> impl Future<Item = Vec<SearchResult>, Error = Error> {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("term", term)
+            .finish();
+        self.docker
+            .stream_get_with_headers(
+                &format!("/images/search?{}", query),
+                Some(iter::once(("X-Registry-Auth", auth.serialize()))),
+            )
+            .concat2()
+            .and_then(|body| serde_json::from_slice(&body).map_err(Error::from))
+    }
+
+    /// Deletes unused images, resolving to the images untagged and deleted and
+    /// the disk space this reclaimed
+    pub fn prune(
+        &self,
+        opts: &ImagePruneOptions,
+    ) -> impl Future<Item = ImagePruneInfo, Error = Error> {
+        let mut path = vec!["/images/prune".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        self.docker.post_json::<Body, _>(&path.join("?"), None)
+    }
+
     /// Pull and create a new docker images from an existing image
     pub fn pull(
         &self,
@@ -218,6 +460,7 @@ impl<'a> Images<'a> {
             .auth_header()
             .map(|a| iter::once(("X-Registry-Auth", a)));
         self.docker
+            .timed(opts.timeout)
             .stream_post::<Body, _>(&path.join("?"), None, headers)
             // todo: give this a proper enum type
             .map(|r| {
@@ -231,23 +474,146 @@ impl<'a> Images<'a> {
             .flatten()
     }
 
+    /// Pulls an image like `pull`, decoding each record of its progress
+    ///
+    /// ```no_run
+    /// # use shiplift::{prelude::*, PullOptions};
+    /// let docker = Docker::new();
+    /// let pull = docker
+    ///     .images()
+    ///     .pull_progress(&PullOptions::builder().image("busybox").tag("latest").build())
+    ///     .for_each(|progress| {
+    ///         Ok(println!("{:?}: {:?} {:?}", progress.id, progress.status, progress.percent()))
+    ///     });
+    /// ```
+    pub fn pull_progress(
+        &self,
+        opts: &PullOptions,
+    ) -> impl Stream<Item = Progress, Error = Error> {
+        self.pull(opts)
+            .and_then(|value| serde_json::from_value::<Progress>(value).map_err(Error::from))
+    }
+
+    /// Pulls an image like `pull`, along with a handle which aborts the pull
+    pub fn pull_cancellable(
+        &self,
+        opts: &PullOptions,
+    ) -> (
+        Cancellable<impl Stream<Item = Value, Error = Error>>,
+        CancelHandle,
+    ) {
+        cancel::cancellable(self.pull(opts))
+    }
+
+    /// Pulls an image like `pull`, passing its progress to `reporter` and
+    /// resolving to the reporter once the pull has finished
+    pub fn pull_with_progress<R>(
+        &self,
+        opts: &PullOptions,
+        reporter: R,
+    ) -> impl Future<Item = R, Error = Error>
+    where
+        R: ProgressReporter,
+    {
+        progress::report(self.pull(opts), reporter)
+    }
+
+    /// Pulls several images at once, such as to pre-warm a CI runner, merging their
+    /// progress into one stream keyed by image reference
+    ///
+    /// A pull which fails, either outright or with an error record in its
+    /// progress, yields its error keyed by its reference, and the other pulls
+    /// carry on
+    ///
+    /// ```no_run
+    /// # use shiplift::{prelude::*, PullManyOptions};
+    /// let docker = Docker::new();
+    /// let pulls = docker
+    ///     .images()
+    ///     .pull_many(
+    ///         vec!["busybox", "alpine:3.12"],
+    ///         &PullManyOptions::builder().parallelism(2).build(),
+    ///     )
+    ///     .for_each(|(image, progress)| {
+    ///         match progress {
+    ///             Ok(progress) => println!("{}: {}", image, progress),
+    ///             Err(e) => eprintln!("{}: {}", image, e),
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// ```
+    pub fn pull_many<I>(
+        &self,
+        images: I,
+        opts: &PullManyOptions,
+    ) -> impl Stream<Item = (String, Result<Value>), Error = Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let docker = self.docker.clone();
+        let auth = opts.auth.clone();
+        pull::PullMany::new(
+            images.into_iter().map(Into::into).collect(),
+            opts.parallelism,
+            move |image| {
+                let mut opts = PullOptions::builder();
+                opts.image(image);
+                if let Some(ref auth) = auth {
+                    opts.auth(auth.clone());
+                }
+                Images::new(&docker)
+                    .pull(&opts.build())
+                    .and_then(progress::failed_record)
+            },
+        )
+    }
+
     /// exports a collection of named images,
     /// either by name, name:tag, or image id, into a tarball
     pub fn export(
         &self,
         names: Vec<&str>,
-    ) -> impl Stream<Item = Vec<u8>, Error = Error> {
+    ) -> impl Stream<Item = Bytes, Error = Error> {
         let params = names.iter().map(|n| ("names", *n));
         let query = form_urlencoded::Serializer::new(String::new())
             .extend_pairs(params)
             .finish();
         self.docker
             .stream_get(&format!("/images/get?{}", query)[..])
-            .map(|c| c.to_vec())
+            .map(Chunk::into_bytes)
+    }
+
+    /// exports a collection of named images into a tarball like `export`,
+    /// along with a handle which aborts the export
+    pub fn export_cancellable(
+        &self,
+        names: Vec<&str>,
+    ) -> (
+        Cancellable<impl Stream<Item = Bytes, Error = Error>>,
+        CancelHandle,
+    ) {
+        cancel::cancellable(self.export(names))
+    }
+
+    /// exports a collection of named images into a tarball like `export`,
+    /// reporting the bytes received to `reporter`
+    pub fn export_with_progress<R>(
+        &self,
+        names: Vec<&str>,
+        reporter: R,
+    ) -> impl Stream<Item = Bytes, Error = Error>
+    where
+        R: ProgressReporter,
+    {
+        progress::report_bytes(self.export(names), reporter)
     }
 
     /// imports an image or set of images from a given tarball source
     /// source can be uncompressed on compressed via gzip, bzip2 or xz
+    ///
+    /// The whole tarball is read into memory before it's sent. Use
+    /// `import_reader` or `import_stream` for large archives
     pub fn import(
         self,
         mut tarball: Box<dyn Read>,
@@ -255,23 +621,65 @@ impl<'a> Images<'a> {
         let mut bytes = Vec::new();
 
         match tarball.read_to_end(&mut bytes) {
-            Ok(_) => Box::new(
-                self.docker
-                    .stream_post(
-                        "/images/load",
-                        Some((Body::from(bytes), tar())),
-                        None::<iter::Empty<_>>,
-                    )
-                    .and_then(|bytes| {
-                        serde_json::from_slice::<'_, Value>(&bytes[..])
-                            .map_err(Error::from)
-                            .into_future()
-                    }),
-            ) as Box<dyn Stream<Item = Value, Error = Error> + Send>,
+            Ok(_) => Box::new(self.import_stream(stream::once(Ok::<_, io::Error>(bytes))))
+                as Box<dyn Stream<Item = Value, Error = Error> + Send>,
             Err(e) => Box::new(futures::future::err(Error::IO(e)).into_stream())
                 as Box<dyn Stream<Item = Value, Error = Error> + Send>,
         }
     }
+
+    /// imports an image or set of images from a tarball read asynchronously,
+    /// such as a `tokio::fs::File`, and uploaded as it's read. Large archives
+    /// may need `import_stream_with` to allow longer than the request timeout
+    pub fn import_reader<R>(
+        &self,
+        tarball: R,
+    ) -> impl Stream<Item = Value, Error = Error>
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        self.import_stream(FramedRead::new(tarball, BytesCodec::new()).map(BytesMut::freeze))
+    }
+
+    /// imports an image or set of images from a tarball streamed in chunks,
+    /// without buffering it in memory
+    pub fn import_stream<S>(
+        &self,
+        tarball: S,
+    ) -> impl Stream<Item = Value, Error = Error>
+    where
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        self.import_stream_with(&ImportOptions::default(), tarball)
+    }
+
+    /// imports images from a tarball streamed in chunks like `import_stream`,
+    /// with options such as a timeout of its own for large archives
+    pub fn import_stream_with<S>(
+        &self,
+        opts: &ImportOptions,
+        tarball: S,
+    ) -> impl Stream<Item = Value, Error = Error>
+    where
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        self.docker
+            .timed(opts.timeout)
+            .stream_post(
+                "/images/load",
+                Some((Body::wrap_stream(tarball), tar())),
+                None::<iter::Empty<_>>,
+            )
+            .and_then(|bytes| {
+                serde_json::from_slice::<'_, Value>(&bytes[..])
+                    .map_err(Error::from)
+                    .into_future()
+            })
+    }
 }
 
 /// Interface for accessing and manipulating a docker container
@@ -331,10 +739,9 @@ impl<'a, 'b> Container<'a, 'b> {
             path.push(query)
         }
 
-        let decoder = TtyDecoder::new();
-        let chunk_stream = StreamReader::new(self.docker.stream_get(&path.join("?")));
-
-        FramedRead::new(chunk_stream, decoder)
+        let decoder = TtyDecoder::with_limits(self.docker.limits);
+        let chunks = self.docker.timed(opts.timeout).stream_get(&path.join("?"));
+        codec::decoded(chunks, decoder)
     }
 
     /// Attaches to a running container, returning a stream that can
@@ -362,27 +769,79 @@ impl<'a, 'b> Container<'a, 'b> {
     }
 
     /// Exports the current docker container into a tarball
-    pub fn export(&self) -> impl Stream<Item = Vec<u8>, Error = Error> {
+    pub fn export(&self) -> impl Stream<Item = Bytes, Error = Error> {
+        self.export_with(&ExportOptions::default())
+    }
+
+    /// Exports the current docker container into a tarball like `export`, with
+    /// options such as a timeout of its own
+    pub fn export_with(
+        &self,
+        opts: &ExportOptions,
+    ) -> impl Stream<Item = Bytes, Error = Error> {
         self.docker
+            .timed(opts.timeout)
             .stream_get(&format!("/containers/{}/export", self.id)[..])
-            .map(|c| c.to_vec())
+            .map(Chunk::into_bytes)
+    }
+
+    /// Exports only the files added or modified since the container was created,
+    /// as reported by `changes`, into a tarball of their paths from the root.
+    /// Deleted files aren't recorded.
+    ///
+    /// Each changed path is copied out of the container in turn, and buffered
+    /// while its entries are renamed, so this is best suited to containers
+    /// whose changes are small next to their image.
+    pub fn export_changes(&self) -> impl Stream<Item = Bytes, Error = Error> {
+        let docker = self.docker.clone();
+        let id = self.id.clone().into_owned();
+        self.changes()
+            .map(move |changes| {
+                let paths = tarball::changed_paths(&changes)
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>();
+                stream::iter_ok(paths)
+                    .and_then(move |path| {
+                        Container::new(&docker, id.as_str())
+                            .copy_from(&path)
+                            .fold(Vec::new(), |mut archive, chunk| {
+                                archive.extend_from_slice(&chunk);
+                                Ok::<_, Error>(archive)
+                            })
+                            .then(move |archive| {
+                                let archive = match archive {
+                                    Ok(archive) => archive,
+                                    // files may be removed from running containers
+                                    // after their changes are listed
+                                    Err(ref e) if e.status() == Some(StatusCode::NOT_FOUND) => {
+                                        return Ok(Bytes::new())
+                                    }
+                                    Err(e) => return Err(e),
+                                };
+                                let parent = path.parent().unwrap_or_else(|| Path::new("/"));
+                                Ok(tarball::rebase(&archive, parent)?.into())
+                            })
+                    })
+                    .chain(stream::once(Ok(Bytes::from_static(
+                        &tarball::END_OF_ARCHIVE,
+                    ))))
+            })
+            .flatten_stream()
     }
 
     /// Returns a stream of stats specific to this container instance
     pub fn stats(&self) -> impl Stream<Item = Stats, Error = Error> {
-        let decoder = LinesCodec::new();
-        let stream_of_chunks = StreamReader::new(
-            self.docker
-                .stream_get(&format!("/containers/{}/stats", self.id)[..]),
-        );
+        let decoder = LinesDecoder::new(&self.docker.limits);
+        let stream_of_chunks = self
+            .docker
+            .stream_get(&format!("/containers/{}/stats", self.id)[..]);
 
-        FramedRead::new(stream_of_chunks, decoder)
-            .map_err(Error::IO)
-            .and_then(|s| {
-                serde_json::from_str::<Stats>(&s)
-                    .map_err(Error::SerdeJsonError)
-                    .into_future()
-            })
+        codec::decoded(stream_of_chunks, decoder).and_then(|s| {
+            serde_json::from_str::<Stats>(&s)
+                .map_err(Error::SerdeJsonError)
+                .into_future()
+        })
     }
 
     /// Start the container instance
@@ -392,7 +851,8 @@ impl<'a, 'b> Container<'a, 'b> {
             .map(|_| ())
     }
 
-    /// Stop the container instance
+    /// Stop the container instance. The daemon responds once the container
+    /// has stopped, so the request timeout is extended by `wait`
     pub fn stop(
         &self,
         wait: Option<Duration>,
@@ -405,10 +865,14 @@ impl<'a, 'b> Container<'a, 'b> {
 
             path.push(encoded)
         }
-        self.docker.post::<Body>(&path.join("?"), None).map(|_| ())
+        self.docker
+            .extended(wait)
+            .post::<Body>(&path.join("?"), None)
+            .map(|_| ())
     }
 
-    /// Restart the container instance
+    /// Restart the container instance. The daemon responds once the container
+    /// has stopped, so the request timeout is extended by `wait`
     pub fn restart(
         &self,
         wait: Option<Duration>,
@@ -420,7 +884,10 @@ impl<'a, 'b> Container<'a, 'b> {
                 .finish();
             path.push(encoded)
         }
-        self.docker.post::<Body>(&path.join("?"), None).map(|_| ())
+        self.docker
+            .extended(wait)
+            .post::<Body>(&path.join("?"), None)
+            .map(|_| ())
     }
 
     /// Kill the container instance
@@ -470,8 +937,28 @@ impl<'a, 'b> Container<'a, 'b> {
 
     /// Wait until the container stops
     pub fn wait(&self) -> impl Future<Item = Exit, Error = Error> {
-        self.docker
-            .post_json::<Body, Exit>(&format!("/containers/{}/wait", self.id)[..], None)
+        self.wait_with(&WaitOptions::default())
+    }
+
+    /// Wait until the container stops like `wait`, failing with
+    /// `Error::Timeout` if it runs for longer than the options' timeout
+    pub fn wait_with(
+        &self,
+        opts: &WaitOptions,
+    ) -> impl Future<Item = Exit, Error = Error> {
+        // containers may run for longer than the request timeout allows, so only
+        // require the daemon to start responding in time, and bound the whole
+        // wait by its own timeout instead
+        let exit = self
+            .docker
+            .stream_post::<Body, iter::Empty<_>>(
+                &format!("/containers/{}/wait", self.id)[..],
+                None,
+                None,
+            )
+            .concat2()
+            .and_then(|body| serde_json::from_slice::<Exit>(&body).map_err(Error::from));
+        deadline(exit, opts.timeout)
     }
 
     /// Delete the container instance
@@ -495,40 +982,17 @@ impl<'a, 'b> Container<'a, 'b> {
         self.docker.delete(&path.join("?")).map(|_| ())
     }
 
-    // TODO(abusch) fix this
-    /// Exec the specified command in the container
+    /// Exec the specified command in the container, streaming its output.
+    ///
+    /// Use `Docker::execs` to learn the command's exit code once it's done
     pub fn exec(
         &self,
         opts: &ExecContainerOptions,
     ) -> impl Stream<Item = tty::Chunk, Error = Error> {
-        let data = opts.serialize().unwrap(); // TODO fixme
-        let bytes = data.into_bytes();
-        let docker2 = self.docker.clone();
-        self.docker
-            .post(
-                &format!("/containers/{}/exec", self.id)[..],
-                Some((bytes, mime::APPLICATION_JSON)),
-            )
-            .map(move |res| {
-                let data = "{}";
-                let bytes = data.as_bytes();
-                let id = serde_json::from_str::<Value>(res.as_str())
-                    .ok()
-                    .and_then(|v| {
-                        v.as_object()
-                            .and_then(|v| v.get("Id"))
-                            .and_then(|v| v.as_str().map(|v| v.to_string()))
-                    })
-                    .unwrap(); // TODO fixme
-
-                let decoder = TtyDecoder::new();
-                let chunk_stream = StreamReader::new(docker2.stream_post(
-                    &format!("/exec/{}/start", id)[..],
-                    Some((bytes, mime::APPLICATION_JSON)),
-                    None::<iter::Empty<_>>,
-                ));
-                FramedRead::new(chunk_stream, decoder)
-            })
+        let docker = self.docker.clone();
+        Execs::new(self.docker)
+            .create(&self.id, opts)
+            .map(move |info| Exec::new(&docker, info.id).start())
             .flatten_stream()
     }
 
@@ -543,13 +1007,35 @@ impl<'a, 'b> Container<'a, 'b> {
     pub fn copy_from(
         &self,
         path: &Path,
-    ) -> impl Stream<Item = Vec<u8>, Error = Error> {
+    ) -> impl Stream<Item = Bytes, Error = Error> {
         let path_arg = form_urlencoded::Serializer::new(String::new())
             .append_pair("path", &path.to_string_lossy())
             .finish();
         self.docker
             .stream_get(&format!("/containers/{}/archive?{}", self.id, path_arg))
-            .map(|c| c.to_vec())
+            .map(Chunk::into_bytes)
+    }
+
+    /// Returns information about a file or directory in the container,
+    /// without copying it
+    pub fn stat_path(
+        &self,
+        path: &Path,
+    ) -> impl Future<Item = PathStat, Error = Error> {
+        let path_arg = form_urlencoded::Serializer::new(String::new())
+            .append_pair("path", &path.to_string_lossy())
+            .finish();
+        self.docker
+            .head(&format!("/containers/{}/archive?{}", self.id, path_arg))
+            .and_then(|headers| {
+                let stat = headers
+                    .get("X-Docker-Container-Path-Stat")
+                    .and_then(|v| base64::decode(v.as_bytes()).ok())
+                    .ok_or_else(|| {
+                        Error::InvalidResponse("missing or invalid path stat header".into())
+                    })?;
+                Ok(serde_json::from_slice(&stat)?)
+            })
     }
 
     /// Copy a byte slice as file into (see `bytes`) the container.
@@ -560,34 +1046,107 @@ impl<'a, 'b> Container<'a, 'b> {
         &self,
         path: P,
         bytes: &[u8],
+    ) -> impl Future<Item = (), Error = Error> {
+        self.copy_file_into_with(path, bytes, &CopyFileOptions::default())
+    }
+
+    /// Copy a byte slice as file into the container at the given location,
+    /// with the permissions, ownership and modification time given by `opts`
+    pub fn copy_file_into_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        bytes: &[u8],
+        opts: &CopyFileOptions,
     ) -> impl Future<Item = (), Error = Error> {
         let path = path.as_ref();
 
         let mut ar = tar::Builder::new(Vec::new());
         let mut header = tar::Header::new_gnu();
         header.set_size(bytes.len() as u64);
-        header.set_mode(0o0644);
-        ar.append_data(
-            &mut header,
-            path.to_path_buf()
-                .iter()
-                .skip(1)
-                .collect::<std::path::PathBuf>(),
-            bytes,
-        )
-        .unwrap();
-        let data = ar.into_inner().unwrap();
+        header.set_mode(opts.mode);
+        header.set_uid(opts.uid);
+        header.set_gid(opts.gid);
+        header.set_mtime(opts.mtime);
+        let data = ar
+            .append_data(
+                &mut header,
+                path.to_path_buf()
+                    .iter()
+                    .skip(1)
+                    .collect::<std::path::PathBuf>(),
+                bytes,
+            )
+            .and_then(|_| ar.into_inner());
+        let data = match data {
+            Ok(data) => data,
+            Err(e) => return Either::B(futures::future::err(Error::IO(e))),
+        };
 
-        let body = Some((data, "application/x-tar".parse::<Mime>().unwrap()));
+        let body = Some((
+            data,
+            "application/x-tar"
+                .parse::<Mime>()
+                .expect("valid mime literal"),
+        ));
 
-        let path_arg = form_urlencoded::Serializer::new(String::new())
+        let mut path_arg = form_urlencoded::Serializer::new(String::new())
             .append_pair("path", "/")
             .finish();
+        if let Some(query) = opts.serialize() {
+            path_arg = format!("{}&{}", path_arg, query);
+        }
+
+        Either::A(
+            self.docker
+                .put(
+                    &format!("/containers/{}/archive?{}", self.id, path_arg),
+                    body,
+                )
+                .map(|_| ()),
+        )
+    }
+
+    /// Extracts a tar archive streamed in chunks into the directory at
+    /// `path` in the container
+    pub fn copy_to<P, S>(
+        &self,
+        path: P,
+        archive: S,
+    ) -> impl Future<Item = (), Error = Error>
+    where
+        P: AsRef<Path>,
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        self.copy_to_with(path, archive, &ArchiveOptions::default())
+    }
+
+    /// Extracts a tar archive streamed in chunks into the directory at
+    /// `path` in the container, as controlled by `opts`
+    pub fn copy_to_with<P, S>(
+        &self,
+        path: P,
+        archive: S,
+        opts: &ArchiveOptions,
+    ) -> impl Future<Item = (), Error = Error>
+    where
+        P: AsRef<Path>,
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        let mut path_arg = form_urlencoded::Serializer::new(String::new())
+            .append_pair("path", &path.as_ref().to_string_lossy())
+            .finish();
+        if let Some(query) = opts.serialize() {
+            path_arg = format!("{}&{}", path_arg, query);
+        }
 
         self.docker
             .put(
                 &format!("/containers/{}/archive?{}", self.id, path_arg),
-                body,
+                Some((Body::wrap_stream(archive), tar())),
             )
             .map(|_| ())
     }
@@ -616,6 +1175,19 @@ impl<'a> Containers<'a> {
         self.docker.get_json::<Vec<ContainerRep>>(&path.join("?"))
     }
 
+    /// Lists the container instances on the docker host like `list`, yielding
+    /// each as it's received rather than buffering the whole list
+    pub fn list_stream(
+        &self,
+        opts: &ContainerListOptions,
+    ) -> impl Stream<Item = ContainerRep, Error = Error> {
+        let mut path = vec!["/containers/json".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query)
+        }
+        self.docker.stream_get_json_array(&path.join("?"))
+    }
+
     /// Returns a reference to a set of operations available to a specific container instance
     pub fn get<'b>(
         &self,
@@ -650,25 +1222,147 @@ impl<'a> Containers<'a> {
                 .post_json(&path.join("?"), Some((bytes, mime::APPLICATION_JSON))),
         )
     }
+
+    /// Deletes stopped containers, resolving to the ids of those deleted and the
+    /// disk space this reclaimed
+    pub fn prune(
+        &self,
+        opts: &ContainerPruneOptions,
+    ) -> impl Future<Item = ContainerPruneInfo, Error = Error> {
+        let mut path = vec!["/containers/prune".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        self.docker.post_json::<Body, _>(&path.join("?"), None)
+    }
 }
 
-/// Interface for docker network
-pub struct Networks<'a> {
+/// Interface for creating exec instances, which run commands in containers
+pub struct Execs<'a> {
     docker: &'a Docker,
 }
 
-impl<'a> Networks<'a> {
-    /// Exports an interface for interacting with docker Networks
-    pub fn new(docker: &'a Docker) -> Networks<'a> {
-        Networks { docker }
+impl<'a> Execs<'a> {
+    /// Exports an interface for interacting with exec instances
+    pub fn new(docker: &'a Docker) -> Execs<'a> {
+        Execs { docker }
     }
 
-    /// List the docker networks on the current docker host
-    pub fn list(
+    /// Creates an exec instance to run a command in a running container,
+    /// without starting it
+    pub fn create(
         &self,
-        opts: &NetworkListOptions,
-    ) -> impl Future<Item = Vec<NetworkInfo>, Error = Error> {
-        let mut path = vec!["/networks".to_owned()];
+        container: &str,
+        opts: &ExecContainerOptions,
+    ) -> impl Future<Item = ExecCreateInfo, Error = Error> {
+        let data = match opts.serialize() {
+            Ok(data) => data,
+            Err(e) => return Either::A(futures::future::err(e)),
+        };
+        Either::B(self.docker.post_json(
+            &format!("/containers/{}/exec", container)[..],
+            Some((data.into_bytes(), mime::APPLICATION_JSON)),
+        ))
+    }
+
+    /// Returns a reference to a set of operations available for an exec instance
+    pub fn get<'b>(
+        &self,
+        id: &'b str,
+    ) -> Exec<'a, 'b> {
+        Exec::new(self.docker, id)
+    }
+}
+
+/// Interface for starting and inspecting an exec instance
+pub struct Exec<'a, 'b> {
+    docker: &'a Docker,
+    id: Cow<'b, str>,
+}
+
+impl<'a, 'b> Exec<'a, 'b> {
+    /// Exports an interface for operations that may be performed against an
+    /// exec instance
+    pub fn new<S>(
+        docker: &'a Docker,
+        id: S,
+    ) -> Exec<'a, 'b>
+    where
+        S: Into<Cow<'b, str>>,
+    {
+        Exec {
+            docker,
+            id: id.into(),
+        }
+    }
+
+    /// a getter for the exec instance id
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Starts the exec instance, streaming its output until the command exits
+    pub fn start(&self) -> impl Stream<Item = tty::Chunk, Error = Error> {
+        let decoder = TtyDecoder::with_limits(self.docker.limits);
+        let chunks = self.docker.stream_post(
+            &format!("/exec/{}/start", self.id)[..],
+            Some(("{}", mime::APPLICATION_JSON)),
+            None::<iter::Empty<_>>,
+        );
+        codec::decoded(chunks, decoder)
+    }
+
+    /// Starts the exec instance without attaching to it, resolving once the
+    /// command has started. Its exit code is given by `inspect` once it's done
+    pub fn start_detached(&self) -> impl Future<Item = (), Error = Error> {
+        self.docker
+            .post(
+                &format!("/exec/{}/start", self.id)[..],
+                Some((r#"{"Detach":true}"#, mime::APPLICATION_JSON)),
+            )
+            .map(|_| ())
+    }
+
+    /// Inspects the exec instance, including the command's exit code once it
+    /// has exited
+    pub fn inspect(&self) -> impl Future<Item = ExecDetails, Error = Error> {
+        self.docker
+            .get_json::<ExecDetails>(&format!("/exec/{}/json", self.id)[..])
+    }
+
+    /// Resizes the TTY of an exec instance created with `tty`
+    pub fn resize(
+        &self,
+        height: u64,
+        width: u64,
+    ) -> impl Future<Item = (), Error = Error> {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("h", &height.to_string())
+            .append_pair("w", &width.to_string())
+            .finish();
+        self.docker
+            .post::<Body>(&format!("/exec/{}/resize?{}", self.id, query)[..], None)
+            .map(|_| ())
+    }
+}
+
+/// Interface for docker network
+pub struct Networks<'a> {
+    docker: &'a Docker,
+}
+
+impl<'a> Networks<'a> {
+    /// Exports an interface for interacting with docker Networks
+    pub fn new(docker: &'a Docker) -> Networks<'a> {
+        Networks { docker }
+    }
+
+    /// List the docker networks on the current docker host
+    pub fn list(
+        &self,
+        opts: &NetworkListOptions,
+    ) -> impl Future<Item = Vec<NetworkInfo>, Error = Error> {
+        let mut path = vec!["/networks".to_owned()];
         if let Some(query) = opts.serialize() {
             path.push(query);
         }
@@ -700,6 +1394,19 @@ impl<'a> Networks<'a> {
                 .post_json(&path.join("?"), Some((bytes, mime::APPLICATION_JSON))),
         )
     }
+
+    /// Deletes networks which no containers are connected to, resolving to the
+    /// names of those deleted
+    pub fn prune(
+        &self,
+        opts: &NetworkPruneOptions,
+    ) -> impl Future<Item = NetworkPruneInfo, Error = Error> {
+        let mut path = vec!["/networks/prune".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        self.docker.post_json::<Body, _>(&path.join("?"), None)
+    }
 }
 
 /// Interface for accessing and manipulating a docker network
@@ -809,7 +1516,18 @@ impl<'a> Volumes<'a> {
 
     /// Lists the docker volumes on the current docker host
     pub fn list(&self) -> impl Future<Item = Vec<VolumeRep>, Error = Error> {
-        let path = vec!["/volumes".to_owned()];
+        self.list_with(&VolumeListOptions::default())
+    }
+
+    /// Lists the docker volumes on the current docker host matching the given options
+    pub fn list_with(
+        &self,
+        opts: &VolumeListOptions,
+    ) -> impl Future<Item = Vec<VolumeRep>, Error = Error> {
+        let mut path = vec!["/volumes".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
 
         self.docker
             .get_json::<VolumesRep>(&path.join("?"))
@@ -819,6 +1537,19 @@ impl<'a> Volumes<'a> {
             })
     }
 
+    /// Deletes volumes which no containers use, resolving to the names of those
+    /// deleted and the disk space this reclaimed
+    pub fn prune(
+        &self,
+        opts: &VolumePruneOptions,
+    ) -> impl Future<Item = VolumePruneInfo, Error = Error> {
+        let mut path = vec!["/volumes/prune".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        self.docker.post_json::<Body, _>(&path.join("?"), None)
+    }
+
     /// Returns a reference to a set of operations available for a named volume
     pub fn get<'b>(
         &self,
@@ -857,240 +1588,1122 @@ impl<'a, 'b> Volume<'a, 'b> {
     }
 }
 
-fn get_http_connector() -> HttpConnector {
-    let mut http = HttpConnector::new(1);
-    http.enforce_http(false);
-
-    http
+/// Interface for docker swarm secrets
+pub struct Secrets<'a> {
+    docker: &'a Docker,
 }
 
-#[cfg(feature = "tls")]
-fn get_docker_for_tcp(tcp_host_str: String) -> Docker {
-    let http = get_http_connector();
-    if let Ok(ref certs) = env::var("DOCKER_CERT_PATH") {
-        // fixme: don't unwrap before you know what's in the box
-        // https://github.com/hyperium/hyper/blob/master/src/net.rs#L427-L428
-        let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
-        connector.set_cipher_list("DEFAULT").unwrap();
-        let cert = &format!("{}/cert.pem", certs);
-        let key = &format!("{}/key.pem", certs);
-        connector
-            .set_certificate_file(&Path::new(cert), SslFiletype::PEM)
-            .unwrap();
-        connector
-            .set_private_key_file(&Path::new(key), SslFiletype::PEM)
-            .unwrap();
-        if env::var("DOCKER_TLS_VERIFY").is_ok() {
-            let ca = &format!("{}/ca.pem", certs);
-            connector.set_ca_file(&Path::new(ca)).unwrap();
-        }
+impl<'a> Secrets<'a> {
+    /// Exports an interface for interacting with docker secrets
+    pub fn new(docker: &'a Docker) -> Secrets<'a> {
+        Secrets { docker }
+    }
 
-        // If we are attempting to connec to the docker daemon via tcp
-        // we need to convert the scheme to `https` to let hyper connect.
-        // Otherwise, hyper will reject the connection since it does not
-        // recongnize `tcp` as a valid `http` scheme.
-        let tcp_host_str = if tcp_host_str.contains("tcp://") {
-            tcp_host_str.replace("tcp://", "https://")
-        } else {
-            tcp_host_str
+    /// Creates a secret, returning its id
+    pub fn create(
+        &self,
+        opts: &SecretCreateOptions,
+    ) -> impl Future<Item = SecretCreateInfo, Error = Error> {
+        let data = match opts.serialize() {
+            Ok(data) => data,
+            Err(e) => return Either::A(futures::future::err(e)),
         };
 
-        Docker {
-            transport: Transport::EncryptedTcp {
-                client: Client::builder()
-                    .build(HttpsConnector::with_connector(http, connector).unwrap()),
-                host: tcp_host_str,
-            },
-        }
-    } else {
-        Docker {
-            transport: Transport::Tcp {
-                client: Client::builder().build(http),
-                host: tcp_host_str,
-            },
-        }
-    }
-}
-
-#[cfg(not(feature = "tls"))]
-fn get_docker_for_tcp(tcp_host_str: String) -> Docker {
-    let http = get_http_connector();
-    Docker {
-        transport: Transport::Tcp {
-            client: Client::builder().build(http),
-            host: tcp_host_str,
-        },
+        Either::B(self.docker.post_json(
+            "/secrets/create",
+            Some((data.into_bytes(), mime::APPLICATION_JSON)),
+        ))
     }
-}
 
-// https://docs.docker.com/reference/api/docker_remote_api_v1.17/
-impl Docker {
-    /// constructs a new Docker instance for a docker host listening at a url specified by an env var `DOCKER_HOST`,
-    /// falling back on unix:///var/run/docker.sock
-    pub fn new() -> Docker {
-        match env::var("DOCKER_HOST").ok() {
-            Some(host) => {
-                let host = host.parse().expect("invalid url");
-                Docker::host(host)
-            }
-            #[cfg(feature = "unix-socket")]
-            None => Docker::unix("/var/run/docker.sock"),
-            #[cfg(not(feature = "unix-socket"))]
-            None => panic!("Unix socket support is disabled"),
+    /// Creates a secret named `name` from the content of a file
+    pub fn create_from_path<P>(
+        &self,
+        name: &str,
+        path: P,
+    ) -> impl Future<Item = SecretCreateInfo, Error = Error>
+    where
+        P: AsRef<Path>,
+    {
+        match File::open(path) {
+            Ok(file) => Either::A(self.create_from_reader(name, file)),
+            Err(e) => Either::B(futures::future::err(Error::IO(e))),
         }
     }
 
-    /// Creates a new docker instance for a docker host
-    /// listening on a given Unix socket.
-    #[cfg(feature = "unix-socket")]
-    pub fn unix<S>(socket_path: S) -> Docker
+    /// Creates a secret named `name` from everything read from `reader`.
+    ///
+    /// Fails with `Error::InvalidInput` without contacting the daemon if the
+    /// content is larger than `builder::MAX_SECRET_SIZE`
+    pub fn create_from_reader<R>(
+        &self,
+        name: &str,
+        reader: R,
+    ) -> impl Future<Item = SecretCreateInfo, Error = Error>
     where
-        S: Into<String>,
+        R: Read,
     {
-        Docker {
-            transport: Transport::Unix {
-                client: Client::builder().keep_alive(false).build(UnixConnector),
-                path: socket_path.into(),
-            },
+        // read one byte past the limit to tell when it's exceeded
+        let mut data = Vec::new();
+        match reader
+            .take(builder::MAX_SECRET_SIZE as u64 + 1)
+            .read_to_end(&mut data)
+        {
+            Ok(_) => {
+                Either::A(self.create(&SecretCreateOptions::builder(name).data(&data).build()))
+            }
+            Err(e) => Either::B(futures::future::err(Error::IO(e))),
         }
     }
 
-    /// constructs a new Docker instance for docker host listening at the given host url
-    pub fn host(host: Uri) -> Docker {
-        let tcp_host_str = format!(
-            "{}://{}:{}",
-            host.scheme_part().map(|s| s.as_str()).unwrap(),
-            host.host().unwrap().to_owned(),
-            host.port_u16().unwrap_or(80)
-        );
-
-        match host.scheme_part().map(|s| s.as_str()) {
-            #[cfg(feature = "unix-socket")]
-            Some("unix") => Docker {
-                transport: Transport::Unix {
-                    client: Client::builder().build(UnixConnector),
-                    path: host.path().to_owned(),
-                },
-            },
-
-            #[cfg(not(feature = "unix-socket"))]
-            Some("unix") => panic!("Unix socket support is disabled"),
-
-            _ => get_docker_for_tcp(tcp_host_str),
-        }
+    /// Lists the secrets in the swarm
+    pub fn list(&self) -> impl Future<Item = Vec<SecretRep>, Error = Error> {
+        self.docker.get_json::<Vec<SecretRep>>("/secrets")
     }
 
-    /// Exports an interface for interacting with docker images
-    pub fn images(&self) -> Images {
-        Images::new(self)
+    /// Returns a reference to a set of operations available for a secret
+    pub fn get<'b>(
+        &self,
+        id: &'b str,
+    ) -> Secret<'a, 'b> {
+        Secret::new(self.docker, id)
     }
+}
 
-    /// Exports an interface for interacting with docker containers
-    pub fn containers(&self) -> Containers {
-        Containers::new(self)
-    }
+/// Interface for accessing and manipulating a docker swarm secret
+pub struct Secret<'a, 'b> {
+    docker: &'a Docker,
+    id: Cow<'b, str>,
+}
 
-    pub fn networks(&self) -> Networks {
-        Networks::new(self)
+impl<'a, 'b> Secret<'a, 'b> {
+    /// Exports an interface for operations that may be performed against a secret,
+    /// given its id or name
+    pub fn new<S>(
+        docker: &'a Docker,
+        id: S,
+    ) -> Secret<'a, 'b>
+    where
+        S: Into<Cow<'b, str>>,
+    {
+        Secret {
+            docker,
+            id: id.into(),
+        }
     }
 
-    pub fn volumes(&self) -> Volumes {
-        Volumes::new(self)
+    /// Inspects the secret. Its content is never returned
+    pub fn inspect(&self) -> impl Future<Item = SecretRep, Error = Error> {
+        self.docker
+            .get_json::<SecretRep>(&format!("/secrets/{}", self.id)[..])
     }
 
-    /// Returns version information associated with the docker daemon
-    pub fn version(&self) -> impl Future<Item = Version, Error = Error> {
-        self.get_json("/version")
+    /// Deletes the secret
+    pub fn delete(&self) -> impl Future<Item = (), Error = Error> {
+        self.docker
+            .delete(&format!("/secrets/{}", self.id)[..])
+            .map(|_| ())
     }
+}
 
-    /// Returns information associated with the docker daemon
-    pub fn info(&self) -> impl Future<Item = Info, Error = Error> {
-        self.get_json("/info")
-    }
+/// Interface for docker plugins
+pub struct Plugins<'a> {
+    docker: &'a Docker,
+}
 
-    /// Returns a simple ping response indicating the docker daemon is accessible
-    pub fn ping(&self) -> impl Future<Item = String, Error = Error> {
-        self.get("/_ping")
+impl<'a> Plugins<'a> {
+    /// Exports an interface for interacting with docker plugins
+    pub fn new(docker: &'a Docker) -> Plugins<'a> {
+        Plugins { docker }
     }
 
-    /// Returns a stream of docker events
-    pub fn events(
+    /// Lists the plugins installed on the docker host
+    pub fn list(
         &self,
-        opts: &EventsOptions,
-    ) -> impl Stream<Item = Event, Error = Error> {
-        let mut path = vec!["/events".to_owned()];
+        opts: &PluginListOptions,
+    ) -> impl Future<Item = Vec<PluginRep>, Error = Error> {
+        let mut path = vec!["/plugins".to_owned()];
         if let Some(query) = opts.serialize() {
             path.push(query);
         }
-        let stream_of_chunks = self.stream_get(&path.join("?")[..]);
-        let reader = StreamReader::new(stream_of_chunks);
-        FramedRead::new(reader, LinesCodec::new())
-            .map_err(Error::IO)
-            .and_then(|line| serde_json::from_str::<Event>(&line).map_err(Error::from))
+        self.docker.get_json(&path.join("?"))
     }
 
-    //
-    // Utility functions to make requests
-    //
-
-    fn get(
+    /// Returns the privileges a plugin will require on the docker host once installed.
+    ///
+    /// These should be shown to and accepted by the user before passing them on to `install`.
+    pub fn privileges(
         &self,
-        endpoint: &str,
-    ) -> impl Future<Item = String, Error = Error> {
-        self.transport.request::<Body>(Method::GET, endpoint, None)
+        remote: &str,
+    ) -> impl Future<Item = Vec<PluginPrivilege>, Error = Error> {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("remote", remote)
+            .finish();
+        self.docker
+            .get_json(&format!("/plugins/privileges?{}", query)[..])
     }
 
-    fn get_json<T: serde::de::DeserializeOwned>(
+    /// Pulls and installs a plugin, returning a stream of the pull's progress.
+    ///
+    /// The plugin is disabled once installed, and must be enabled before it can be used.
+    pub fn install(
         &self,
-        endpoint: &str,
-    ) -> impl Future<Item = T, Error = Error> {
-        self.transport
-            .request::<Body>(Method::GET, endpoint, None)
-            .and_then(|v| {
-                serde_json::from_str::<T>(&v)
-                    .map_err(Error::SerdeJsonError)
-                    .into_future()
-            })
+        opts: &PluginInstallOptions,
+    ) -> impl Stream<Item = Value, Error = Error> {
+        pull_plugin(self.docker, "/plugins/pull", opts)
     }
 
-    fn post<B>(
+    /// Creates a plugin named `name` from a local directory.
+    ///
+    /// The directory must contain the plugin's `config.json` and its root filesystem in
+    /// a `rootfs` subdirectory. The plugin is disabled once created.
+    pub fn create(
         &self,
-        endpoint: &str,
-        body: Option<(B, Mime)>,
-    ) -> impl Future<Item = String, Error = Error>
-    where
-        B: Into<Body>,
-    {
-        self.transport.request(Method::POST, endpoint, body)
+        name: &str,
+        path: &str,
+    ) -> impl Future<Item = (), Error = Error> {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("name", name)
+            .finish();
+
+        self.docker
+            .post(
+                &format!("/plugins/create?{}", query)[..],
+                Some((
+                    Body::wrap_stream(tarball::dir_stream(path.to_owned())),
+                    tar(),
+                )),
+            )
+            .map(|_| ())
     }
 
-    fn put<B>(
+    /// Returns a reference to a set of operations available for a named plugin
+    pub fn get<'b>(
         &self,
-        endpoint: &str,
-        body: Option<(B, Mime)>,
-    ) -> impl Future<Item = String, Error = Error>
-    where
-        B: Into<Body>,
-    {
-        self.transport.request(Method::PUT, endpoint, body)
+        name: &'b str,
+    ) -> Plugin<'a, 'b> {
+        Plugin::new(self.docker, name)
     }
+}
 
-    fn post_json<B, T>(
-        &self,
-        endpoint: &str,
-        body: Option<(B, Mime)>,
-    ) -> impl Future<Item = T, Error = Error>
-    where
+/// Interface for accessing and manipulating a named docker plugin
+pub struct Plugin<'a, 'b> {
+    docker: &'a Docker,
+    name: Cow<'b, str>,
+}
+
+impl<'a, 'b> Plugin<'a, 'b> {
+    /// Exports an interface for operations that may be performed against a named plugin
+    pub fn new<S>(
+        docker: &'a Docker,
+        name: S,
+    ) -> Plugin<'a, 'b>
+    where
+        S: Into<Cow<'b, str>>,
+    {
+        Plugin {
+            docker,
+            name: name.into(),
+        }
+    }
+
+    /// a getter for the plugin name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Inspects a named plugin's details
+    pub fn inspect(&self) -> impl Future<Item = PluginRep, Error = Error> {
+        self.docker
+            .get_json(&format!("/plugins/{}/json", self.name)[..])
+    }
+
+    /// Enable the plugin, waiting up to `timeout` for it to come up
+    pub fn enable(
+        &self,
+        timeout: Option<Duration>,
+    ) -> impl Future<Item = (), Error = Error> {
+        let mut path = vec![format!("/plugins/{}/enable", self.name)];
+        if let Some(t) = timeout {
+            let encoded = form_urlencoded::Serializer::new(String::new())
+                .append_pair("timeout", &t.as_secs().to_string())
+                .finish();
+            path.push(encoded)
+        }
+        self.docker.post::<Body>(&path.join("?"), None).map(|_| ())
+    }
+
+    /// Disable the plugin.
+    ///
+    /// Unless `force` is set this fails while the plugin is in use, e.g. by a volume.
+    pub fn disable(
+        &self,
+        force: bool,
+    ) -> impl Future<Item = (), Error = Error> {
+        let mut path = vec![format!("/plugins/{}/disable", self.name)];
+        if force {
+            path.push("force=true".to_owned())
+        }
+        self.docker.post::<Body>(&path.join("?"), None).map(|_| ())
+    }
+
+    /// Upgrades the plugin in place to a new remote reference, returning a stream of the
+    /// pull's progress.
+    ///
+    /// The plugin keeps its current name, so the `name` option is ignored. The plugin must
+    /// be disabled while it is upgraded.
+    pub fn upgrade(
+        &self,
+        opts: &PluginInstallOptions,
+    ) -> impl Stream<Item = Value, Error = Error> {
+        pull_plugin(
+            self.docker,
+            &format!("/plugins/{}/upgrade", self.name)[..],
+            opts,
+        )
+    }
+
+    /// Pushes the plugin to the registry its name refers to, returning a stream of the
+    /// push's progress
+    pub fn push(
+        &self,
+        auth: Option<&RegistryAuth>,
+    ) -> impl Stream<Item = Value, Error = Error> {
+        let headers = auth.map(|a| iter::once(("X-Registry-Auth", a.serialize())));
+        self.docker.stream_post_json::<Body, _>(
+            &format!("/plugins/{}/push", self.name)[..],
+            None,
+            headers,
+        )
+    }
+
+    /// Pushes the plugin like `push`, passing its progress to `reporter` and
+    /// resolving to the reporter once the push has finished
+    pub fn push_with_progress<R>(
+        &self,
+        auth: Option<&RegistryAuth>,
+        reporter: R,
+    ) -> impl Future<Item = R, Error = Error>
+    where
+        R: ProgressReporter,
+    {
+        progress::report(self.push(auth), reporter)
+    }
+
+    /// Change settings of the plugin, given in the form `ENV=value`, `mount.source=/path`,
+    /// `device.path=/dev/x` or `args=value`.
+    ///
+    /// The plugin must be disabled while its settings change.
+    pub fn set(
+        &self,
+        args: Vec<&str>,
+    ) -> impl Future<Item = (), Error = Error> {
+        let data = match serde_json::to_string(&args) {
+            Ok(data) => data,
+            Err(e) => return Either::A(futures::future::err(Error::from(e))),
+        };
+
+        Either::B(
+            self.docker
+                .post(
+                    &format!("/plugins/{}/set", self.name)[..],
+                    Some((data.into_bytes(), mime::APPLICATION_JSON)),
+                )
+                .map(|_| ()),
+        )
+    }
+
+    /// Remove the plugin, returning its final details.
+    ///
+    /// Unless `force` is set the plugin must be disabled first.
+    pub fn remove(
+        &self,
+        force: bool,
+    ) -> impl Future<Item = PluginRep, Error = Error> {
+        let mut path = vec![format!("/plugins/{}", self.name)];
+        if force {
+            path.push("force=true".to_owned())
+        }
+        self.docker.delete_json(&path.join("?"))
+    }
+}
+
+/// Pulls a plugin as described by `opts`, granting it the requested privileges
+fn pull_plugin(
+    docker: &Docker,
+    endpoint: &str,
+    opts: &PluginInstallOptions,
+) -> impl Stream<Item = Value, Error = Error> {
+    let mut path = vec![endpoint.to_owned()];
+    if let Some(query) = opts.serialize() {
+        path.push(query);
+    }
+    let headers = opts
+        .auth_header()
+        .map(|a| iter::once(("X-Registry-Auth", a)));
+
+    match opts.serialize_privileges() {
+        Ok(data) => Box::new(docker.stream_post_json(
+            &path.join("?"),
+            Some((data.into_bytes(), mime::APPLICATION_JSON)),
+            headers,
+        )) as Box<dyn Stream<Item = Value, Error = Error> + Send>,
+        Err(e) => Box::new(futures::future::err(e).into_stream())
+            as Box<dyn Stream<Item = Value, Error = Error> + Send>,
+    }
+}
+
+/// Converts a docker host url such as `tcp://127.0.0.1:2375` into one hyper can
+/// connect to, with an `http` or `https` scheme.
+///
+/// As with the docker CLI, `tcp://` urls default to port 2375, or 2376 over TLS.
+fn get_tcp_host_str(
+    host: &Uri,
+    tls: bool,
+) -> Result<String> {
+    let (scheme, hostname) = match (host.scheme_part(), host.host()) {
+        (Some(scheme), Some(hostname)) => (scheme.as_str(), hostname),
+        _ => {
+            return Err(Error::InvalidHost(format!(
+                "{} (expected a url such as tcp://127.0.0.1:2375)",
+                host
+            )))
+        }
+    };
+    let default_port = match scheme {
+        "http" => 80,
+        "https" => 443,
+        _ if tls => 2376,
+        _ => 2375,
+    };
+    Ok(format!(
+        "{}://{}:{}",
+        if tls { "https" } else { "http" },
+        hostname,
+        host.port_u16().unwrap_or(default_port)
+    ))
+}
+
+/// Whether a docker host url calls for TLS by its scheme
+fn is_https(host: &Uri) -> bool {
+    host.scheme_part().map(|s| s.as_str()) == Some("https")
+}
+
+#[cfg(not(feature = "unix-socket"))]
+fn unix_socket_disabled(path: &str) -> Error {
+    Error::InvalidHost(format!("unix://{} (Unix socket support is disabled)", path))
+}
+
+/// Settings for the http client used to reach the docker daemon
+#[derive(Clone, Debug)]
+struct ClientOptions {
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    /// Proxy for tcp connections, overriding any `*_PROXY` env vars
+    proxy: Option<Uri>,
+    // connection pool settings, hyper's defaults are used when unset
+    keep_alive: Option<bool>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    /// Sent with every request
+    headers: HeaderMap,
+    /// Spawns the background tasks driving connections, hyper uses the
+    /// default tokio executor when unset
+    executor: Option<SharedExecutor>,
+}
+
+/// The background tasks hyper spawns to drive connections
+type Background = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// An executor shared by every client built from the same options
+#[derive(Clone)]
+struct SharedExecutor(Arc<dyn Executor<Background> + Send + Sync>);
+
+impl Executor<Background> for SharedExecutor {
+    fn execute(
+        &self,
+        future: Background,
+    ) -> std::result::Result<(), ExecuteError<Background>> {
+        self.0.execute(future)
+    }
+}
+
+impl fmt::Debug for SharedExecutor {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        f.write_str("SharedExecutor")
+    }
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            connect_timeout: Some(Duration::from_secs(10)),
+            request_timeout: Some(Duration::from_secs(120)),
+            proxy: None,
+            keep_alive: None,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            headers: iter::once((
+                header::USER_AGENT,
+                HeaderValue::from_static(concat!("shiplift/", env!("CARGO_PKG_VERSION"))),
+            ))
+            .collect(),
+            executor: None,
+        }
+    }
+}
+
+impl ClientOptions {
+    fn client_builder(&self) -> hyper::client::Builder {
+        let mut builder = Client::builder();
+        if let Some(keep_alive) = self.keep_alive {
+            builder.keep_alive(keep_alive);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder.keep_alive_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder.max_idle_per_host(max);
+        }
+        if let Some(ref executor) = self.executor {
+            builder.executor(executor.clone());
+        }
+        builder
+    }
+}
+
+fn get_http_connector(options: &ClientOptions) -> proxy::Connector {
+    let mut http = HttpConnector::new(1);
+    http.enforce_http(false);
+    http.set_connect_timeout(options.connect_timeout);
+
+    proxy::connector(http, options.proxy.as_ref())
+}
+
+/// Connects to a tcp host configured by the environment the way the docker CLI is:
+/// TLS is used for `https://` urls or when `DOCKER_TLS_VERIFY` is set, with the
+/// certificates in `DOCKER_CERT_PATH`, which defaults to `~/.docker`
+fn get_docker_for_tcp(host: &Uri) -> Result<Docker> {
+    let options = ClientOptions::default();
+    let verify = env::var("DOCKER_TLS_VERIFY")
+        .map(|v| !v.is_empty())
+        .unwrap_or(false);
+    if !verify && !is_https(host) {
+        return Ok(get_docker_for_plain_tcp(
+            get_tcp_host_str(host, false)?,
+            &options,
+        ));
+    }
+    get_docker_for_tls_from_env(host, verify, &options)
+}
+
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+fn get_docker_for_tls_from_env(
+    host: &Uri,
+    verify: bool,
+    options: &ClientOptions,
+) -> Result<Docker> {
+    let certs = env::var_os("DOCKER_CERT_PATH")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(context::config_dir);
+    let config = TlsConfig::from_cert_path(certs, verify);
+    get_docker_for_tls(get_tcp_host_str(host, true)?, &config, options)
+}
+
+#[cfg(not(any(feature = "tls", feature = "tls-rustls")))]
+fn get_docker_for_tls_from_env(
+    host: &Uri,
+    _: bool,
+    _: &ClientOptions,
+) -> Result<Docker> {
+    Err(tls_disabled(host))
+}
+
+#[cfg(not(any(feature = "tls", feature = "tls-rustls")))]
+fn tls_disabled(host: &Uri) -> Error {
+    Error::Tls(format!(
+        "{} requires TLS, but TLS support is disabled",
+        host
+    ))
+}
+
+#[cfg(feature = "tls")]
+fn get_docker_for_tls(
+    tcp_host_str: String,
+    config: &TlsConfig,
+    options: &ClientOptions,
+) -> Result<Docker> {
+    let mut connector =
+        HttpsConnector::with_connector(get_http_connector(options), config.openssl_connector()?)
+            .map_err(|e| Error::Tls(e.to_string()))?;
+    if let Some(name) = config.server_name() {
+        let name = name.to_owned();
+        connector.set_callback(move |ssl, _| {
+            // stop openssl using the host connected to in place of `name`
+            ssl.set_use_server_name_indication(false);
+            ssl.set_verify_hostname(false);
+            ssl.set_hostname(&name)?;
+            ssl.param_mut().set_host(&name)
+        });
+    }
+    Ok(Docker {
+        transport: Transport::EncryptedTcp {
+            client: options.client_builder().build(connector),
+            host: tcp_host_str,
+            timeout: options.request_timeout,
+            headers: options.headers.clone(),
+        },
+        version: None,
+        retry: None,
+        limits: StreamLimits::default(),
+    })
+}
+
+// openssl takes precedence when both tls features are enabled
+#[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+fn get_docker_for_tls(
+    tcp_host_str: String,
+    config: &TlsConfig,
+    options: &ClientOptions,
+) -> Result<Docker> {
+    let connector =
+        hyper_rustls::HttpsConnector::from((get_http_connector(options), config.rustls_config()?));
+    Ok(Docker {
+        transport: Transport::RustlsTcp {
+            client: options.client_builder().build(connector),
+            host: tcp_host_str,
+            timeout: options.request_timeout,
+            headers: options.headers.clone(),
+        },
+        version: None,
+        retry: None,
+        limits: StreamLimits::default(),
+    })
+}
+
+fn get_docker_for_plain_tcp(
+    tcp_host_str: String,
+    options: &ClientOptions,
+) -> Docker {
+    Docker {
+        transport: Transport::Tcp {
+            client: options.client_builder().build(get_http_connector(options)),
+            host: tcp_host_str,
+            timeout: options.request_timeout,
+            headers: options.headers.clone(),
+        },
+        version: None,
+        retry: None,
+        limits: StreamLimits::default(),
+    }
+}
+
+#[cfg(feature = "unix-socket")]
+fn get_docker_for_unix(
+    path: String,
+    options: &ClientOptions,
+) -> Docker {
+    Docker {
+        transport: Transport::Unix {
+            client: options.client_builder().build(UnixConnector),
+            path,
+            timeout: options.request_timeout,
+            headers: options.headers.clone(),
+        },
+        version: None,
+        retry: None,
+        limits: StreamLimits::default(),
+    }
+}
+
+// https://docs.docker.com/reference/api/docker_remote_api_v1.17/
+impl Docker {
+    /// constructs a new Docker instance for a docker host listening at a url specified by an env var `DOCKER_HOST`,
+    /// then the docker CLI's current context, falling back on unix:///var/run/docker.sock
+    ///
+    /// If the env var `DOCKER_API_VERSION` is set, requests are pinned to that API version
+    ///
+    /// # Panics
+    ///
+    /// When the environment describes an invalid docker host, see `Docker::try_new`
+    pub fn new() -> Docker {
+        Docker::try_new().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// constructs a new Docker instance the same way as `Docker::new`, returning an
+    /// error rather than panicking when the environment describes an invalid docker host
+    pub fn try_new() -> Result<Docker> {
+        let docker = match env::var("DOCKER_HOST").ok() {
+            Some(host) => Docker::host(host)?,
+            None => match Context::current()? {
+                Some(context) => Docker::context(&context)?,
+                #[cfg(feature = "unix-socket")]
+                None => Docker::unix("/var/run/docker.sock"),
+                #[cfg(not(feature = "unix-socket"))]
+                None => return Err(unix_socket_disabled("/var/run/docker.sock")),
+            },
+        };
+
+        match env::var("DOCKER_API_VERSION") {
+            Ok(ref version) if !version.is_empty() => Ok(docker.with_api_version(version.parse()?)),
+            _ => Ok(docker),
+        }
+    }
+
+    /// constructs a new Docker instance the same way as `Docker::try_new`, resolving
+    /// once the daemon has been reached and the pinned API version checked, see
+    /// `Docker::negotiate`. Fails early on a daemon that's misconfigured or down,
+    /// rather than on the first real call
+    pub fn connect() -> impl Future<Item = Docker, Error = Error> {
+        Docker::try_new().into_future().and_then(Docker::negotiate)
+    }
+
+    /// constructs a new Docker instance for the endpoint of a docker CLI context,
+    /// using its TLS material if it has any
+    pub fn context(context: &Context) -> Result<Docker> {
+        let mut builder = Docker::builder();
+        builder.host(context.host());
+        #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+        {
+            if let Some(config) = context.tls_config() {
+                builder.tls(config);
+            }
+        }
+        builder.build()
+    }
+
+    /// return a new instance of a builder for configuring a docker instance explicitly,
+    /// without reading any `DOCKER_*` env vars
+    pub fn builder() -> DockerBuilder {
+        DockerBuilder::default()
+    }
+
+    /// Creates a new docker instance for a docker host
+    /// listening on a given Unix socket.
+    #[cfg(feature = "unix-socket")]
+    pub fn unix<S>(socket_path: S) -> Docker
+    where
+        S: Into<String>,
+    {
+        get_docker_for_unix(
+            socket_path.into(),
+            &ClientOptions {
+                keep_alive: Some(false),
+                ..ClientOptions::default()
+            },
+        )
+    }
+
+    /// Creates a new docker instance which sends requests with a user supplied transport
+    pub fn from_transport<T>(transport: T) -> Docker
+    where
+        T: HttpTransport,
+    {
+        let options = ClientOptions::default();
+        Docker {
+            transport: Transport::Custom {
+                transport: Arc::new(transport),
+                timeout: options.request_timeout,
+                headers: options.headers,
+            },
+            version: None,
+            retry: None,
+            limits: StreamLimits::default(),
+        }
+    }
+
+    /// constructs a new Docker instance for docker host listening at the given host url,
+    /// either a `Uri` or a string such as `unix:///var/run/docker.sock`, `tcp://127.0.0.1:2375`
+    /// or `https://docker.example.com`
+    pub fn host<H>(host: H) -> Result<Docker>
+    where
+        H: ToString,
+    {
+        match DockerHost::parse(&host.to_string())? {
+            #[cfg(feature = "unix-socket")]
+            DockerHost::Unix(path) => Ok(get_docker_for_unix(path, &ClientOptions::default())),
+
+            #[cfg(not(feature = "unix-socket"))]
+            DockerHost::Unix(path) => Err(unix_socket_disabled(&path)),
+
+            DockerHost::Tcp(host) => get_docker_for_tcp(&host),
+        }
+    }
+
+    /// constructs a new Docker instance for a docker host listening over tcp at the given
+    /// host url, connecting with the given TLS configuration
+    #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+    pub fn tls(
+        host: Uri,
+        config: &TlsConfig,
+    ) -> Result<Docker> {
+        get_docker_for_tls(
+            get_tcp_host_str(&host, true)?,
+            config,
+            &ClientOptions::default(),
+        )
+    }
+
+    /// Pins the docker remote API version used for every request made by this instance,
+    /// so that behavior doesn't drift as the daemon's default API version changes.
+    ///
+    /// Requests are unversioned by default.
+    pub fn with_api_version(
+        mut self,
+        version: ApiVersion,
+    ) -> Docker {
+        self.version = Some(version);
+        self
+    }
+
+    /// Pings the daemon, then checks the API version requests are pinned to against
+    /// the versions it supports. A version newer than the daemon's is lowered to the
+    /// daemon's, while one older than it accepts is an error.
+    ///
+    /// Unpinned instances are left unpinned.
+    pub fn negotiate(self) -> impl Future<Item = Docker, Error = Error> {
+        let mut unpinned = self.clone();
+        unpinned.version = None;
+        unpinned
+            .ping()
+            .and_then(move |_| unpinned.version())
+            .and_then(move |daemon| {
+                let mut docker = self;
+                if let Some(pinned) = docker.version {
+                    if let Some(ref min) = daemon.min_api_version {
+                        let min = min.parse::<ApiVersion>()?;
+                        if pinned < min {
+                            return Err(Error::InvalidApiVersion(format!(
+                                "{}, the daemon requires at least {}",
+                                pinned, min
+                            )));
+                        }
+                    }
+                    let max = daemon.api_version.parse::<ApiVersion>()?;
+                    docker.version = Some(pinned.min(max));
+                }
+                Ok(docker)
+            })
+    }
+
+    /// Retries idempotent requests which fail due to connection errors or server errors
+    /// according to the given policy.
+    ///
+    /// Requests are not retried by default.
+    pub fn with_retry(
+        mut self,
+        policy: RetryPolicy,
+    ) -> Docker {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Bounds the lines and frames decoded from streamed responses, such as
+    /// stats, events, logs and exec output. Unlimited by default
+    pub fn with_stream_limits(
+        mut self,
+        limits: StreamLimits,
+    ) -> Docker {
+        self.limits = limits;
+        self
+    }
+
+    /// This client with its request timeout replaced by a request's own, if it
+    /// has one
+    fn timed(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Cow<Docker> {
+        match timeout {
+            Some(timeout) => {
+                let mut docker = self.clone();
+                docker.transport.set_timeout(Some(timeout));
+                Cow::Owned(docker)
+            }
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    /// This client with its request timeout, if any, extended by the time the
+    /// daemon waits before responding to a request
+    fn extended(
+        &self,
+        wait: Option<Duration>,
+    ) -> Cow<Docker> {
+        match (self.transport.timeout(), wait) {
+            (Some(timeout), Some(wait)) => self.timed(Some(timeout + wait)),
+            _ => Cow::Borrowed(self),
+        }
+    }
+
+    /// Returns the docker remote API version requests are pinned to, if any
+    pub fn api_version(&self) -> Option<ApiVersion> {
+        self.version
+    }
+
+    /// Exports an interface for interacting with docker images
+    pub fn images(&self) -> Images {
+        Images::new(self)
+    }
+
+    /// Exports an interface for interacting with docker containers
+    pub fn containers(&self) -> Containers {
+        Containers::new(self)
+    }
+
+    pub fn networks(&self) -> Networks {
+        Networks::new(self)
+    }
+
+    pub fn volumes(&self) -> Volumes {
+        Volumes::new(self)
+    }
+
+    /// Exports an interface for interacting with docker plugins
+    pub fn plugins(&self) -> Plugins {
+        Plugins::new(self)
+    }
+
+    /// Exports an interface for interacting with docker swarm secrets
+    pub fn secrets(&self) -> Secrets {
+        Secrets::new(self)
+    }
+
+    /// Exports an interface for interacting with exec instances
+    pub fn execs(&self) -> Execs {
+        Execs::new(self)
+    }
+
+    /// Returns version information associated with the docker daemon
+    pub fn version(&self) -> impl Future<Item = Version, Error = Error> {
+        self.get_json("/version")
+    }
+
+    /// Returns information associated with the docker daemon
+    pub fn info(&self) -> impl Future<Item = Info, Error = Error> {
+        self.get_json("/info")
+    }
+
+    /// Checks credentials against a registry without performing any other operation.
+    ///
+    /// Useful for failing fast before starting a long running push or pull.
+    pub fn auth(
+        &self,
+        auth: &RegistryAuth,
+    ) -> impl Future<Item = AuthStatus, Error = Error> {
+        let data = match serde_json::to_string(auth) {
+            Ok(data) => data,
+            Err(e) => return Either::A(futures::future::err(Error::from(e))),
+        };
+
+        // checking credentials has no side effects, so it's safe to retry
+        Either::B(self.post_json_idempotent("/auth", data.into_bytes(), mime::APPLICATION_JSON))
+    }
+
+    /// Returns disk space used by images, containers, volumes and the build cache
+    pub fn df(
+        &self,
+        opts: &DataUsageOptions,
+    ) -> impl Future<Item = DataUsage, Error = Error> {
+        let mut path = vec!["/system/df".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        self.get_json(&path.join("?"))
+    }
+
+    /// Returns a simple ping response indicating the docker daemon is accessible
+    pub fn ping(&self) -> impl Future<Item = String, Error = Error> {
+        self.get("/_ping")
+    }
+
+    /// Pings the docker daemon with a `HEAD` request, returning the
+    /// metadata it reports in the response headers
+    pub fn ping_info(&self) -> impl Future<Item = PingInfo, Error = Error> {
+        self.head("/_ping").map(|headers| {
+            let header = |name: &str| {
+                headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+            };
+            PingInfo {
+                api_version: header("API-Version"),
+                builder_version: header("Builder-Version"),
+                experimental: header("Docker-Experimental")
+                    .map(|v| v == "true")
+                    .unwrap_or(false),
+            }
+        })
+    }
+
+    /// Probes the docker daemon for the features it supports, so callers can
+    /// branch on them without parsing ping headers and version info themselves
+    pub fn capabilities(&self) -> impl Future<Item = Capabilities, Error = Error> {
+        self.ping_info()
+            .join(self.version())
+            .map(|(ping, version)| Capabilities {
+                experimental: ping.experimental,
+                buildkit: ping.builder_version.as_ref().map(|v| v.as_str()) == Some("2"),
+                os_type: version.os.clone(),
+                version,
+            })
+    }
+
+    /// Returns a stream of docker events
+    pub fn events(
+        &self,
+        opts: &EventsOptions,
+    ) -> impl Stream<Item = Event, Error = Error> {
+        let mut path = vec!["/events".to_owned()];
+        if let Some(query) = opts.serialize() {
+            path.push(query);
+        }
+        let stream_of_chunks = self.stream_get(&path.join("?")[..]);
+        codec::decoded(stream_of_chunks, LinesDecoder::new(&self.limits))
+            .and_then(|line| serde_json::from_str::<Event>(&line).map_err(Error::from))
+    }
+
+    /// Returns a stream of docker events which reconnects whenever its
+    /// connection is lost, resuming from the last event received
+    pub fn events_reconnecting(
+        &self,
+        opts: &EventsOptions,
+    ) -> events::ReconnectingEvents {
+        events::ReconnectingEvents::new(self, opts)
+    }
+
+    //
+    // Utility functions to make requests
+    //
+
+    /// Prefixes an endpoint with the pinned API version, if any
+    fn path<'e>(
+        &self,
+        endpoint: &'e str,
+    ) -> Cow<'e, str> {
+        match self.version {
+            Some(ref version) => Cow::Owned(version.prefix(endpoint)),
+            None => Cow::Borrowed(endpoint),
+        }
+    }
+
+    /// Makes a request with the retry policy, if any. The request must be idempotent
+    fn retrying<F, R>(
+        &self,
+        request: F,
+    ) -> impl Future<Item = R::Item, Error = Error>
+    where
+        F: Fn() -> R,
+        R: Future<Error = Error>,
+    {
+        match self.retry {
+            Some(policy) => Either::A(policy.retry(request)),
+            None => Either::B(request()),
+        }
+    }
+
+    fn get(
+        &self,
+        endpoint: &str,
+    ) -> impl Future<Item = String, Error = Error> {
+        let transport = self.transport.clone();
+        let endpoint = self.path(endpoint).into_owned();
+        self.retrying(move || transport.request::<Body>(Method::GET, &endpoint, None))
+    }
+
+    /// Makes a `HEAD` request, returning the headers of the response
+    fn head(
+        &self,
+        endpoint: &str,
+    ) -> impl Future<Item = HeaderMap, Error = Error> {
+        let transport = self.transport.clone();
+        let endpoint = self.path(endpoint).into_owned();
+        self.retrying(move || transport.request_headers(Method::HEAD, &endpoint))
+    }
+
+    fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+    ) -> impl Future<Item = T, Error = Error> {
+        let path = self.path(endpoint).into_owned();
+        self.get(endpoint)
+            .and_then(|v| {
+                serde_json::from_str::<T>(&v)
+                    .map_err(Error::SerdeJsonError)
+                    .into_future()
+            })
+            .map_err(move |e| e.context(&Method::GET, &path))
+    }
+
+    fn post<B>(
+        &self,
+        endpoint: &str,
+        body: Option<(B, Mime)>,
+    ) -> impl Future<Item = String, Error = Error>
+    where
+        B: Into<Body>,
+    {
+        self.transport
+            .request(Method::POST, &self.path(endpoint), body)
+    }
+
+    fn put<B>(
+        &self,
+        endpoint: &str,
+        body: Option<(B, Mime)>,
+    ) -> impl Future<Item = String, Error = Error>
+    where
+        B: Into<Body>,
+    {
+        self.transport
+            .request(Method::PUT, &self.path(endpoint), body)
+    }
+
+    fn post_json<B, T>(
+        &self,
+        endpoint: &str,
+        body: Option<(B, Mime)>,
+    ) -> impl Future<Item = T, Error = Error>
+    where
         B: Into<Body>,
         T: serde::de::DeserializeOwned,
     {
+        let path = self.path(endpoint).into_owned();
         self.transport
-            .request(Method::POST, endpoint, body)
+            .request(Method::POST, &path, body)
             .and_then(|v| {
                 serde_json::from_str::<T>(&v)
                     .map_err(Error::SerdeJsonError)
                     .into_future()
             })
+            .map_err(move |e| e.context(&Method::POST, &path))
+    }
+
+    /// Makes a POST request which is safe to retry, parsing the response as JSON
+    fn post_json_idempotent<T>(
+        &self,
+        endpoint: &str,
+        body: Vec<u8>,
+        mime: Mime,
+    ) -> impl Future<Item = T, Error = Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let transport = self.transport.clone();
+        let endpoint = self.path(endpoint).into_owned();
+        let path = endpoint.clone();
+        self.retrying(move || {
+            transport.request(Method::POST, &endpoint, Some((body.clone(), mime.clone())))
+        })
+        .and_then(|v| {
+            serde_json::from_str::<T>(&v)
+                .map_err(Error::SerdeJsonError)
+                .into_future()
+        })
+        .map_err(move |e| e.context(&Method::POST, &path))
     }
 
     fn delete(
@@ -1098,20 +2711,22 @@ impl Docker {
         endpoint: &str,
     ) -> impl Future<Item = String, Error = Error> {
         self.transport
-            .request::<Body>(Method::DELETE, endpoint, None)
+            .request::<Body>(Method::DELETE, &self.path(endpoint), None)
     }
 
     fn delete_json<T: serde::de::DeserializeOwned>(
         &self,
         endpoint: &str,
     ) -> impl Future<Item = T, Error = Error> {
+        let path = self.path(endpoint).into_owned();
         self.transport
-            .request::<Body>(Method::DELETE, endpoint, None)
+            .request::<Body>(Method::DELETE, &path, None)
             .and_then(|v| {
                 serde_json::from_str::<T>(&v)
                     .map_err(Error::SerdeJsonError)
                     .into_future()
             })
+            .map_err(move |e| e.context(&Method::DELETE, &path))
     }
 
     fn stream_post<B, H>(
@@ -1125,15 +2740,61 @@ impl Docker {
         H: IntoIterator<Item = (&'static str, String)>,
     {
         self.transport
-            .stream_chunks(Method::POST, endpoint, body, headers)
+            .stream_chunks(Method::POST, &self.path(endpoint), body, headers)
+    }
+
+    /// Makes a POST request whose response is a stream of JSON messages, such as progress
+    /// reports
+    fn stream_post_json<B, H>(
+        &self,
+        endpoint: &str,
+        body: Option<(B, Mime)>,
+        headers: Option<H>,
+    ) -> impl Stream<Item = Value, Error = Error>
+    where
+        B: Into<Body>,
+        H: IntoIterator<Item = (&'static str, String)>,
+    {
+        self.stream_post(endpoint, body, headers)
+            .map(|r| {
+                futures::stream::iter_result(
+                    serde_json::Deserializer::from_slice(&r[..])
+                        .into_iter::<Value>()
+                        .collect::<Vec<_>>(),
+                )
+                .map_err(Error::from)
+            })
+            .flatten()
     }
 
     fn stream_get(
         &self,
         endpoint: &str,
     ) -> impl Stream<Item = hyper::Chunk, Error = Error> {
+        self.stream_get_with_headers(endpoint, None::<iter::Empty<_>>)
+    }
+
+    fn stream_get_with_headers<H>(
+        &self,
+        endpoint: &str,
+        headers: Option<H>,
+    ) -> impl Stream<Item = hyper::Chunk, Error = Error>
+    where
+        H: IntoIterator<Item = (&'static str, String)>,
+    {
         self.transport
-            .stream_chunks::<Body, iter::Empty<_>>(Method::GET, endpoint, None, None)
+            .stream_chunks::<Body, H>(Method::GET, &self.path(endpoint), None, headers)
+    }
+
+    /// Decodes the elements of a json array response as they arrive
+    fn stream_get_json_array<T>(
+        &self,
+        endpoint: &str,
+    ) -> impl Stream<Item = T, Error = Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        codec::decoded(self.stream_get(endpoint), JsonArrayDecoder::new())
     }
 
     fn stream_post_upgrade_multiplexed<B>(
@@ -1145,7 +2806,7 @@ impl Docker {
         B: Into<Body> + 'static,
     {
         self.transport
-            .stream_upgrade_multiplexed(Method::POST, endpoint, body)
+            .stream_upgrade_multiplexed(Method::POST, &self.path(endpoint), body)
     }
 }
 
@@ -1154,3 +2815,468 @@ impl Default for Docker {
         Self::new()
     }
 }
+
+/// Builder for docker instances configured explicitly rather than from the environment
+///
+/// ```no_run
+/// use shiplift::{ApiVersion, Docker};
+///
+/// let docker = Docker::builder()
+///     .host("tcp://127.0.0.1:2375")
+///     .api_version(ApiVersion::new(1, 40))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct DockerBuilder {
+    host: Option<String>,
+    #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+    tls: Option<TlsConfig>,
+    version: Option<ApiVersion>,
+    options: ClientOptions,
+    retry: Option<RetryPolicy>,
+    limits: StreamLimits,
+    headers: Vec<(String, String)>,
+    max_concurrent_requests: Option<usize>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    metrics: Option<Arc<dyn Metrics>>,
+    transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl DockerBuilder {
+    /// Connect to a docker host at the given url, either a `Uri` or a string.
+    /// `unix://` urls connect over a Unix socket, anything else over tcp.
+    /// Defaults to unix:///var/run/docker.sock
+    pub fn host<H>(
+        &mut self,
+        host: H,
+    ) -> &mut Self
+    where
+        H: ToString,
+    {
+        // parsed by `build`, which reports invalid urls
+        self.host = Some(host.to_string());
+        self
+    }
+
+    /// Connect to a docker host listening on the given Unix socket
+    #[cfg(feature = "unix-socket")]
+    pub fn unix<S>(
+        &mut self,
+        socket_path: S,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.host = Some(format!("unix://{}", socket_path.into()));
+        self
+    }
+
+    /// Sends requests with a user supplied transport rather than connecting to a
+    /// host, such as for tests or tunnels. The host, TLS and connection settings
+    /// are then ignored, while the rest still apply
+    pub fn transport<T>(
+        &mut self,
+        transport: T,
+    ) -> &mut Self
+    where
+        T: HttpTransport,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Connect to a tcp host over TLS. Ignored for Unix sockets
+    #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+    pub fn tls(
+        &mut self,
+        config: TlsConfig,
+    ) -> &mut Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Pin requests to a docker remote API version
+    pub fn api_version(
+        &mut self,
+        version: ApiVersion,
+    ) -> &mut Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Connect to tcp hosts through the given http proxy. Defaults to the proxy
+    /// configured by the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` env vars
+    pub fn proxy(
+        &mut self,
+        proxy: Uri,
+    ) -> &mut Self {
+        self.options.proxy = Some(proxy);
+        self
+    }
+
+    /// Whether to reuse connections for multiple requests. Enabled by default for tcp hosts
+    pub fn keep_alive(
+        &mut self,
+        keep_alive: bool,
+    ) -> &mut Self {
+        self.options.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// How long idle connections are kept open for reuse. Defaults to 90 seconds
+    pub fn pool_idle_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.options.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// The maximum number of idle connections kept open for reuse. Unlimited by default
+    pub fn pool_max_idle_per_host(
+        &mut self,
+        max: usize,
+    ) -> &mut Self {
+        self.options.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// The `User-Agent` sent with every request. Defaults to `shiplift/<version>`
+    pub fn user_agent<S>(
+        &mut self,
+        user_agent: S,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.header(header::USER_AGENT.as_str(), user_agent)
+    }
+
+    /// Sends a header with every request, e.g. for proxies in front of the docker
+    /// socket which require authentication. Setting a header again replaces it
+    pub fn header<K, V>(
+        &mut self,
+        name: K,
+        value: V,
+    ) -> &mut Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Caps the number of requests waiting on the daemon at once. Further requests
+    /// wait their turn. Unlimited by default, and a cap of zero allows one
+    pub fn max_concurrent_requests(
+        &mut self,
+        max: usize,
+    ) -> &mut Self {
+        self.max_concurrent_requests = Some(max);
+        self
+    }
+
+    /// Runs the given middleware around every request. Middleware runs in the
+    /// order it's added, with the first added seeing requests first
+    pub fn middleware<M>(
+        &mut self,
+        middleware: M,
+    ) -> &mut Self
+    where
+        M: Middleware,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Reports every request sent to the daemon to the given metrics hooks
+    pub fn metrics<M>(
+        &mut self,
+        metrics: M,
+    ) -> &mut Self
+    where
+        M: Metrics,
+    {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Retry idempotent requests according to the given policy
+    pub fn retry(
+        &mut self,
+        policy: RetryPolicy,
+    ) -> &mut Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Bound the lines and frames decoded from streamed responses
+    pub fn stream_limits(
+        &mut self,
+        limits: StreamLimits,
+    ) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// How long to wait for a tcp connection to be established. Defaults to 10 seconds
+    pub fn connect_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.options.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// How long to wait for the docker daemon to respond to a request. Defaults to 2 minutes
+    ///
+    /// Buffered responses must be received in full within this time, while streamed
+    /// responses such as logs or events only need to start.
+    pub fn request_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.options.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Spawns the background tasks driving connections to the daemon on the given
+    /// executor, such as a handle to a runtime the application manages itself.
+    /// Defaults to the tokio executor the requests are made on
+    ///
+    /// ```no_run
+    /// # use shiplift::Docker;
+    /// let runtime = tokio::runtime::Runtime::new().unwrap();
+    /// let docker = Docker::builder().executor(runtime.executor()).build();
+    /// ```
+    pub fn executor<E>(
+        &mut self,
+        executor: E,
+    ) -> &mut Self
+    where
+        E: Executor<Box<dyn Future<Item = (), Error = ()> + Send>> + Send + Sync + 'static,
+    {
+        self.options.executor = Some(SharedExecutor(Arc::new(executor)));
+        self
+    }
+
+    pub fn build(&self) -> Result<Docker> {
+        let mut options = self.options.clone();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+            let value = HeaderValue::from_str(value).map_err(http::Error::from)?;
+            options.headers.insert(name, value);
+        }
+        let mut transport = match self.transport {
+            Some(ref transport) => Transport::Custom {
+                transport: transport.clone(),
+                timeout: options.request_timeout,
+                headers: options.headers.clone(),
+            },
+            None => self.connect(&options)?,
+        };
+        // wrapping transports hand the timeout to the outermost one, so that it can be
+        // overridden by a request's options and covers waiting for a permit
+        let wrapped = !self.middleware.is_empty()
+            || self.max_concurrent_requests.is_some()
+            || self.metrics.is_some();
+        if wrapped {
+            transport.set_timeout(None);
+        }
+        // requests are measured as they're sent, after middleware has run
+        if let Some(ref metrics) = self.metrics {
+            transport = Transport::Custom {
+                transport: Arc::new(Metered::new(transport, metrics.clone())),
+                timeout: None,
+                headers: options.headers.clone(),
+            };
+        }
+        for middleware in self.middleware.iter().rev() {
+            transport = Transport::Custom {
+                transport: Arc::new(Intercept::new(transport, middleware.clone())),
+                timeout: None,
+                headers: options.headers.clone(),
+            };
+        }
+        if let Some(max) = self.max_concurrent_requests {
+            transport = Transport::Custom {
+                transport: Arc::new(ConcurrencyLimit::new(transport, max)),
+                timeout: None,
+                headers: options.headers.clone(),
+            };
+        }
+        if wrapped {
+            transport.set_timeout(options.request_timeout);
+        }
+        Ok(Docker {
+            transport,
+            version: self.version,
+            retry: self.retry,
+            limits: self.limits,
+        })
+    }
+
+    /// Connects to the configured host, over TLS if need be
+    fn connect(
+        &self,
+        options: &ClientOptions,
+    ) -> Result<Transport> {
+        let host = match self.host {
+            Some(ref host) => DockerHost::parse(host)?,
+            None => DockerHost::Unix("/var/run/docker.sock".to_owned()),
+        };
+        let docker = match host {
+            #[cfg(feature = "unix-socket")]
+            // keep alive is disabled over Unix sockets by default, as with `Docker::unix`
+            DockerHost::Unix(path) => get_docker_for_unix(
+                path,
+                &ClientOptions {
+                    keep_alive: options.keep_alive.or(Some(false)),
+                    ..options.clone()
+                },
+            ),
+            #[cfg(not(feature = "unix-socket"))]
+            DockerHost::Unix(path) => return Err(unix_socket_disabled(&path)),
+            DockerHost::Tcp(host) => self.tcp(&host, options)?,
+        };
+        Ok(docker.transport)
+    }
+
+    /// Connects over TLS when configured with `tls` or given an `https://` url
+    #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+    fn tcp(
+        &self,
+        host: &Uri,
+        options: &ClientOptions,
+    ) -> Result<Docker> {
+        match self.tls {
+            Some(ref config) => get_docker_for_tls(get_tcp_host_str(host, true)?, config, options),
+            None if is_https(host) => get_docker_for_tls(
+                get_tcp_host_str(host, true)?,
+                &TlsConfig::builder().build(),
+                options,
+            ),
+            None => Ok(get_docker_for_plain_tcp(
+                get_tcp_host_str(host, false)?,
+                options,
+            )),
+        }
+    }
+
+    #[cfg(not(any(feature = "tls", feature = "tls-rustls")))]
+    fn tcp(
+        &self,
+        host: &Uri,
+        options: &ClientOptions,
+    ) -> Result<Docker> {
+        if is_https(host) {
+            return Err(tls_disabled(host));
+        }
+        Ok(get_docker_for_plain_tcp(
+            get_tcp_host_str(host, false)?,
+            options,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Checks at compile time that what the api returns can be spawned on a
+    //! multi-threaded runtime
+
+    use crate::{
+        events::EventBus, testing::MockTransport, tty, ContainerListOptions, ContainerOptions,
+        ContainerPruneOptions, Docker, EventsOptions, ExecContainerOptions, ImageListOptions,
+        ImagePruneOptions, LogsOptions, NetworkListOptions, NetworkPruneOptions, PullManyOptions,
+        PullOptions, RegistryAuth, VolumePruneOptions,
+    };
+    use futures::stream;
+    use std::{path::Path, time::Duration};
+
+    fn assert_send<T: Send + 'static>(_: T) {}
+
+    fn docker() -> Docker {
+        Docker::from_transport(MockTransport::new())
+    }
+
+    #[test]
+    fn docker_futures_are_send() {
+        let docker = docker();
+        assert_send(docker.version());
+        assert_send(docker.info());
+        assert_send(docker.ping());
+        assert_send(docker.ping_info());
+        assert_send(docker.clone().negotiate());
+        assert_send(docker.events(&EventsOptions::builder().build()));
+        assert_send(docker.events_reconnecting(&EventsOptions::builder().build()));
+        let (bus, connection) = EventBus::new(&docker, &EventsOptions::builder().build());
+        assert_send(connection);
+        assert_send(bus.subscribe());
+    }
+
+    #[test]
+    fn image_futures_are_send() {
+        let docker = docker();
+        let images = docker.images();
+        assert_send(images.list(&ImageListOptions::builder().build()));
+        assert_send(images.list_stream(&ImageListOptions::builder().build()));
+        assert_send(images.pull(&PullOptions::builder().image("busybox").build()));
+        assert_send(images.pull_progress(&PullOptions::builder().image("busybox").build()));
+        assert_send(images.pull_many(vec!["busybox"], &PullManyOptions::default()));
+        assert_send(images.prune(&ImagePruneOptions::builder().build()));
+        assert_send(images.search("busybox"));
+        assert_send(images.search_with_auth("busybox", &RegistryAuth::token("abc")));
+        assert_send(images.import_stream(stream::empty::<Vec<u8>, std::io::Error>()));
+        assert_send(images.import_reader(&b""[..]));
+        assert_send(images.get("busybox").inspect());
+        assert_send(images.get("busybox").distribution(None));
+        assert_send(images.get("busybox").export());
+        assert_send(images.get("busybox").push(None));
+    }
+
+    #[test]
+    fn container_futures_are_send() {
+        let docker = docker();
+        let containers = docker.containers();
+        assert_send(containers.list(&ContainerListOptions::builder().build()));
+        assert_send(containers.prune(&ContainerPruneOptions::builder().build()));
+        assert_send(containers.list_stream(&ContainerListOptions::builder().build()));
+        assert_send(containers.create(&ContainerOptions::builder("busybox").build()));
+        let container = containers.get("busybox");
+        assert_send(container.inspect());
+        assert_send(container.logs(&LogsOptions::builder().build()));
+        assert_send(container.stats());
+        assert_send(container.stop(Some(Duration::from_secs(1))));
+        assert_send(container.wait());
+        assert_send(container.exec(&ExecContainerOptions::builder().cmd(vec!["ls"]).build()));
+        assert_send(container.export_changes());
+        let execs = docker.execs();
+        assert_send(execs.create("web", &ExecContainerOptions::builder().build()));
+        assert_send(execs.get("abc").start());
+        assert_send(execs.get("abc").start_detached());
+        assert_send(execs.get("abc").inspect());
+        assert_send(execs.get("abc").resize(24, 80));
+        assert_send(container.copy_from(Path::new("/etc")));
+        assert_send(container.stat_path(Path::new("/etc")));
+        assert_send(container.attach());
+    }
+
+    #[test]
+    fn network_futures_are_send() {
+        let docker = docker();
+        assert_send(docker.networks().list(&NetworkListOptions::default()));
+        assert_send(docker.networks().get("bridge").inspect());
+        assert_send(docker.networks().prune(&NetworkPruneOptions::default()));
+        assert_send(docker.volumes().list());
+        assert_send(docker.volumes().prune(&VolumePruneOptions::default()));
+    }
+
+    #[test]
+    fn attached_streams_are_send() {
+        fn assert_send_type<T: Send + 'static>() {}
+        assert_send_type::<tty::Multiplexed>();
+        assert_send_type::<tty::MultiplexedBlocking>();
+    }
+}
@@ -0,0 +1,346 @@
+//! Hooks for collecting metrics on the requests sent to the daemon, such as to
+//! export them to Prometheus or statsd
+//!
+//! ```no_run
+//! use hyper::{Method, StatusCode};
+//! use shiplift::{metrics::Metrics, Docker};
+//! use std::{
+//!     sync::atomic::{AtomicUsize, Ordering},
+//!     time::Duration,
+//! };
+//!
+//! #[derive(Default)]
+//! struct Counters {
+//!     requests: AtomicUsize,
+//!     errors: AtomicUsize,
+//! }
+//!
+//! impl Metrics for Counters {
+//!     fn request_finished(
+//!         &self,
+//!         _method: &Method,
+//!         _path: &str,
+//!         status: StatusCode,
+//!         _latency: Duration,
+//!     ) {
+//!         self.requests.fetch_add(1, Ordering::Relaxed);
+//!         if !status.is_success() {
+//!             self.errors.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! let docker = Docker::builder().metrics(Counters::default()).build().unwrap();
+//! ```
+
+use crate::{transport::HttpTransport, Error};
+use futures::{Future, Stream};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Callbacks made for every request sent to the daemon, all of which do nothing
+/// by default
+///
+/// Requests are identified by their method and path, such as
+/// `/containers/4fa6e0f0c678/json`. As paths contain the ids and names of
+/// objects, exporters will usually want to group them before using them as labels.
+pub trait Metrics: Send + Sync + 'static {
+    /// A request is about to be sent
+    fn request_started(
+        &self,
+        _method: &Method,
+        _path: &str,
+    ) {
+    }
+
+    /// The daemon responded with `status`, `latency` after the request was sent.
+    /// For streamed responses, this is when the stream starts
+    fn request_finished(
+        &self,
+        _method: &Method,
+        _path: &str,
+        _status: StatusCode,
+        _latency: Duration,
+    ) {
+    }
+
+    /// The request failed without a response, such as when the daemon can't be
+    /// reached
+    fn request_failed(
+        &self,
+        _method: &Method,
+        _path: &str,
+        _error: &Error,
+        _latency: Duration,
+    ) {
+    }
+
+    /// Part of a response's body was received
+    fn bytes_received(
+        &self,
+        _method: &Method,
+        _path: &str,
+        _bytes: usize,
+    ) {
+    }
+}
+
+impl<M> Metrics for Arc<M>
+where
+    M: Metrics + ?Sized,
+{
+    fn request_started(
+        &self,
+        method: &Method,
+        path: &str,
+    ) {
+        (**self).request_started(method, path)
+    }
+
+    fn request_finished(
+        &self,
+        method: &Method,
+        path: &str,
+        status: StatusCode,
+        latency: Duration,
+    ) {
+        (**self).request_finished(method, path, status, latency)
+    }
+
+    fn request_failed(
+        &self,
+        method: &Method,
+        path: &str,
+        error: &Error,
+        latency: Duration,
+    ) {
+        (**self).request_failed(method, path, error, latency)
+    }
+
+    fn bytes_received(
+        &self,
+        method: &Method,
+        path: &str,
+        bytes: usize,
+    ) {
+        (**self).bytes_received(method, path, bytes)
+    }
+}
+
+/// Reports the requests sent with another transport to a `Metrics`
+///
+/// Installed with `DockerBuilder::metrics`, which measures requests as they're
+/// sent to the daemon, after any middleware has run.
+pub struct Metered<T> {
+    inner: Arc<T>,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl<T> Metered<T>
+where
+    T: HttpTransport,
+{
+    pub fn new<M>(
+        inner: T,
+        metrics: M,
+    ) -> Metered<T>
+    where
+        M: Metrics,
+    {
+        Metered {
+            inner: Arc::new(inner),
+            metrics: Arc::new(metrics),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Metered<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        f.debug_struct("Metered")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T> HttpTransport for Metered<T>
+where
+    T: HttpTransport,
+{
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        self.inner.uri(endpoint)
+    }
+
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+        let method = req.method().clone();
+        let path = req.uri().path().to_owned();
+        let metrics = self.metrics.clone();
+        metrics.request_started(&method, &path);
+        let started = Instant::now();
+        Box::new(self.inner.request(req).then(move |result| {
+            let latency = started.elapsed();
+            let res = match result {
+                Ok(res) => res,
+                Err(e) => {
+                    metrics.request_failed(&method, &path, &e, latency);
+                    return Err(e);
+                }
+            };
+            metrics.request_finished(&method, &path, res.status(), latency);
+            // the body of an upgraded connection must be left as it is
+            if res.status() == StatusCode::SWITCHING_PROTOCOLS {
+                return Ok(res);
+            }
+            let (parts, body) = res.into_parts();
+            let body =
+                body.inspect(move |chunk| metrics.bytes_received(&method, &path, chunk.len()));
+            Ok(Response::from_parts(parts, Body::wrap_stream(body)))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metered, Metrics};
+    use crate::{testing::MockTransport, Docker, Error};
+    use futures::Stream;
+    use hyper::{Method, StatusCode};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tokio::runtime::current_thread::block_on_all;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Metrics for Recorder {
+        fn request_started(
+            &self,
+            method: &Method,
+            path: &str,
+        ) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("started {} {}", method, path));
+        }
+
+        fn request_finished(
+            &self,
+            method: &Method,
+            path: &str,
+            status: StatusCode,
+            _latency: Duration,
+        ) {
+            self.0.lock().unwrap().push(format!(
+                "finished {} {} {}",
+                method,
+                path,
+                status.as_u16()
+            ));
+        }
+
+        fn request_failed(
+            &self,
+            method: &Method,
+            path: &str,
+            _error: &Error,
+            _latency: Duration,
+        ) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("failed {} {}", method, path));
+        }
+
+        fn bytes_received(
+            &self,
+            method: &Method,
+            path: &str,
+            bytes: usize,
+        ) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("received {} {} {}", method, path, bytes));
+        }
+    }
+
+    #[test]
+    fn requests_are_measured() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        mock.on(Method::GET, "/images/missing/json")
+            .respond(StatusCode::NOT_FOUND, r#"{"message":"no such image"}"#);
+        mock.on(Method::GET, "/containers/web/logs")
+            .respond_chunks(StatusCode::OK, vec!["abc", "de"]);
+        let recorder = Arc::new(Recorder::default());
+        let docker = Docker::from_transport(Metered::new(mock, recorder.clone()));
+
+        block_on_all(docker.ping()).unwrap();
+        block_on_all(docker.images().get("missing").inspect()).unwrap_err();
+        block_on_all(
+            docker
+                .containers()
+                .get("web")
+                .logs(&Default::default())
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                "started GET /_ping",
+                "finished GET /_ping 200",
+                "received GET /_ping 2",
+                "started GET /images/missing/json",
+                "finished GET /images/missing/json 404",
+                "received GET /images/missing/json 27",
+                "started GET /containers/web/logs",
+                "finished GET /containers/web/logs 200",
+                "received GET /containers/web/logs 3",
+                "received GET /containers/web/logs 2",
+            ],
+            *recorder.0.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn builder_measures_requests_sent_through_a_custom_transport() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        let recorder = Arc::new(Recorder::default());
+        let docker = Docker::builder()
+            .transport(mock.clone())
+            .metrics(recorder.clone())
+            .max_concurrent_requests(1)
+            .build()
+            .unwrap();
+
+        block_on_all(docker.ping()).unwrap();
+        assert_eq!(vec!["GET /_ping"], mock.requests());
+        assert_eq!(
+            vec![
+                "started GET /_ping",
+                "finished GET /_ping 200",
+                "received GET /_ping 2",
+            ],
+            *recorder.0.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,1669 @@
+//! Rust representations of docker json structures
+//!
+//! These depend only on serde and serde_json (and chrono, when enabled), not on the client,
+//! so tools which talk to docker by other means can reuse them.
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchResult {
+    pub description: String,
+    pub is_official: bool,
+    pub is_automated: bool,
+    pub name: String,
+    pub star_count: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Image {
+    #[cfg(feature = "chrono")]
+    #[serde(deserialize_with = "datetime_from_unix_timestamp")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: u64,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub parent_id: String,
+    pub labels: Option<HashMap<String, String>>,
+    pub repo_tags: Option<Vec<String>>,
+    pub repo_digests: Option<Vec<String>>,
+    #[serde(default)]
+    pub virtual_size: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ImageDetails {
+    #[serde(default)]
+    pub architecture: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(default)]
+    pub config: Config,
+    #[cfg(feature = "chrono")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: String,
+    #[serde(default)]
+    pub docker_version: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub os: String,
+    #[serde(default)]
+    pub parent: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub virtual_size: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Container {
+    #[cfg(feature = "chrono")]
+    #[serde(deserialize_with = "datetime_from_unix_timestamp")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: u64,
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default, deserialize_with = "nullable")]
+    pub labels: HashMap<String, String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub names: Vec<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub ports: Vec<Port>,
+    #[serde(default)]
+    pub status: String,
+    pub size_rw: Option<u64>,
+    pub size_root_fs: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerDetails {
+    #[serde(default)]
+    pub app_armor_profile: String,
+    #[serde(default, deserialize_with = "nullable")]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub config: Config,
+    #[cfg(feature = "chrono")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: String,
+    #[serde(default)]
+    pub driver: String,
+    // pub ExecIDs: ??
+    #[serde(default)]
+    pub host_config: HostConfig,
+    #[serde(default)]
+    pub hostname_path: String,
+    #[serde(default)]
+    pub hosts_path: String,
+    #[serde(default)]
+    pub log_path: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub mount_label: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub network_settings: NetworkSettings,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub process_label: String,
+    #[serde(default)]
+    pub resolv_conf_path: String,
+    #[serde(default)]
+    pub restart_count: u64,
+    pub state: State,
+    #[serde(default, deserialize_with = "nullable")]
+    pub mounts: Vec<Mount>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Mount {
+    pub source: String,
+    pub destination: String,
+    pub mode: String,
+    #[serde(rename = "RW")]
+    pub rw: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct State {
+    #[serde(default)]
+    pub error: String,
+    #[serde(default)]
+    pub exit_code: u64,
+    #[cfg(feature = "chrono")]
+    pub finished_at: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub finished_at: String,
+    #[serde(default, rename = "OOMKilled")]
+    pub oom_killed: bool,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub pid: u64,
+    #[serde(default)]
+    pub restarting: bool,
+    #[serde(default)]
+    pub running: bool,
+    #[cfg(feature = "chrono")]
+    pub started_at: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub started_at: String,
+    /// The results of the container's healthcheck, if it has one
+    pub health: Option<Health>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Health {
+    /// `starting`, `healthy` or `unhealthy`
+    pub status: String,
+    pub failing_streak: u64,
+}
+
+type PortDescription = HashMap<String, Option<Vec<HashMap<String, String>>>>;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct NetworkSettings {
+    pub bridge: String,
+    pub gateway: String,
+    #[serde(rename = "IPAddress")]
+    pub ip_address: String,
+    #[serde(rename = "IPPrefixLen")]
+    pub ip_prefix_len: u64,
+    pub mac_address: String,
+    pub ports: Option<PortDescription>,
+    #[serde(deserialize_with = "nullable")]
+    pub networks: HashMap<String, NetworkEntry>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct NetworkEntry {
+    #[serde(rename = "NetworkID")]
+    pub network_id: String,
+    #[serde(rename = "EndpointID")]
+    pub endpoint_id: String,
+    pub gateway: String,
+    #[serde(rename = "IPAddress")]
+    pub ip_address: String,
+    #[serde(rename = "IPPrefixLen")]
+    pub ip_prefix_len: u64,
+    #[serde(rename = "IPv6Gateway")]
+    pub ipv6_gateway: String,
+    #[serde(rename = "GlobalIPv6Address")]
+    pub global_ipv6_address: String,
+    #[serde(rename = "GlobalIPv6PrefixLen")]
+    pub global_ipv6_prefix_len: u64,
+    pub mac_address: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct HostConfig {
+    pub cgroup_parent: Option<String>,
+    #[serde(rename = "ContainerIDFile")]
+    pub container_id_file: String,
+    pub cpu_shares: Option<u64>,
+    pub cpuset_cpus: Option<String>,
+    pub isolation: Option<String>,
+    pub memory: Option<u64>,
+    pub memory_swap: Option<i64>,
+    pub network_mode: String,
+    pub pid_mode: Option<String>,
+    pub port_bindings: Option<HashMap<String, Vec<HashMap<String, String>>>>,
+    pub privileged: bool,
+    pub publish_all_ports: bool,
+    pub readonly_rootfs: Option<bool>, /* pub RestartPolicy: ???
+                                        * pub SecurityOpt: Option<???>,
+                                        * pub Ulimits: Option<???>
+                                        * pub VolumesFrom: Option<??/> */
+    pub runtime: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Config {
+    pub attach_stderr: bool,
+    pub attach_stdin: bool,
+    pub attach_stdout: bool,
+    pub cmd: Option<Vec<String>>,
+    pub domainname: String,
+    pub entrypoint: Option<Vec<String>>,
+    pub env: Option<Vec<String>>,
+    pub exposed_ports: Option<HashMap<String, HashMap<String, String>>>,
+    pub hostname: String,
+    pub image: String,
+    pub labels: Option<HashMap<String, String>>,
+    // pub MacAddress: String,
+    pub on_build: Option<Vec<String>>,
+    // pub NetworkDisabled: bool,
+    pub open_stdin: bool,
+    pub stdin_once: bool,
+    pub tty: bool,
+    pub user: String,
+    pub working_dir: String,
+}
+
+impl Config {
+    pub fn env(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        if let Some(ref vars) = self.env {
+            for e in vars {
+                let pair: Vec<&str> = e.split('=').collect();
+                map.insert(pair[0].to_owned(), pair[1].to_owned());
+            }
+        }
+        map
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Port {
+    pub ip: Option<String>,
+    pub private_port: u64,
+    pub public_port: Option<u64>,
+    #[serde(rename = "Type")]
+    pub typ: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stats {
+    #[cfg(feature = "chrono")]
+    pub read: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub read: String,
+    #[serde(default, deserialize_with = "nullable")]
+    pub networks: HashMap<String, Network>,
+    #[serde(default)]
+    pub memory_stats: MemoryStats,
+    #[serde(default)]
+    pub blkio_stats: BlkioStats,
+    #[serde(default)]
+    pub cpu_stats: CpuStats,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Network {
+    pub rx_dropped: u64,
+    pub rx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_packets: u64,
+    pub tx_dropped: u64,
+    pub rx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_bytes: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct IPAM {
+    pub driver: String,
+    #[serde(deserialize_with = "nullable")]
+    pub config: Vec<HashMap<String, String>>,
+    pub options: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct NetworkDetails {
+    pub name: String,
+    pub id: String,
+    pub scope: String,
+    pub driver: String,
+    #[serde(rename = "EnableIPv6")]
+    pub enable_ipv6: bool,
+    #[serde(rename = "IPAM")]
+    pub ipam: IPAM,
+    pub internal: bool,
+    pub attachable: bool,
+    #[serde(deserialize_with = "nullable")]
+    pub containers: HashMap<String, NetworkContainerDetails>,
+    pub options: Option<HashMap<String, String>>,
+    pub labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct NetworkContainerDetails {
+    #[serde(rename = "EndpointID")]
+    pub endpoint_id: String,
+    pub mac_address: String,
+    #[serde(rename = "IPv4Address")]
+    pub ipv4_address: String,
+    #[serde(rename = "IPv6Address")]
+    pub ipv6_address: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct NetworkCreateInfo {
+    pub id: String,
+    pub warning: String,
+}
+
+/// The result of pruning unused networks
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct NetworkPruneInfo {
+    /// The names of the deleted networks
+    pub networks_deleted: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryStats {
+    pub max_usage: u64,
+    pub usage: u64,
+    pub failcnt: Option<u64>,
+    pub limit: u64,
+    pub stats: MemoryStat,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryStat {
+    pub total_pgmajfault: u64,
+    pub cache: u64,
+    pub mapped_file: u64,
+    pub total_inactive_file: u64,
+    pub pgpgout: u64,
+    pub rss: u64,
+    pub total_mapped_file: u64,
+    pub writeback: u64,
+    pub unevictable: u64,
+    pub pgpgin: u64,
+    pub total_unevictable: u64,
+    pub pgmajfault: u64,
+    pub total_rss: u64,
+    pub total_rss_huge: u64,
+    pub total_writeback: u64,
+    pub total_inactive_anon: u64,
+    pub rss_huge: u64,
+    pub hierarchical_memory_limit: u64,
+    pub hierarchical_memsw_limit: u64,
+    pub total_pgfault: u64,
+    pub total_active_file: u64,
+    pub active_anon: u64,
+    pub total_active_anon: u64,
+    pub total_pgpgout: u64,
+    pub total_cache: u64,
+    pub inactive_anon: u64,
+    pub active_file: u64,
+    pub pgfault: u64,
+    pub inactive_file: u64,
+    pub total_pgpgin: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CpuStats {
+    pub cpu_usage: CpuUsage,
+    pub system_cpu_usage: u64,
+    pub throttling_data: ThrottlingData,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CpuUsage {
+    #[serde(deserialize_with = "nullable")]
+    pub percpu_usage: Vec<u64>,
+    pub usage_in_usermode: u64,
+    pub total_usage: u64,
+    pub usage_in_kernelmode: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThrottlingData {
+    pub periods: u64,
+    pub throttled_periods: u64,
+    pub throttled_time: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlkioStats {
+    #[serde(deserialize_with = "nullable")]
+    pub io_service_bytes_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub io_serviced_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub io_queue_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub io_service_time_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub io_wait_time_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub io_merged_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub io_time_recursive: Vec<BlkioStat>,
+    #[serde(deserialize_with = "nullable")]
+    pub sectors_recursive: Vec<BlkioStat>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlkioStat {
+    pub major: u64,
+    pub minor: u64,
+    pub op: String,
+    pub value: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Plugin {
+    pub id: Option<String>,
+    pub name: String,
+    pub enabled: bool,
+    pub settings: PluginSettings,
+    pub plugin_reference: Option<String>,
+    pub config: PluginConfig,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginSettings {
+    #[serde(deserialize_with = "nullable")]
+    pub mounts: Vec<PluginMount>,
+    #[serde(deserialize_with = "nullable")]
+    pub env: Vec<String>,
+    #[serde(deserialize_with = "nullable")]
+    pub args: Vec<String>,
+    #[serde(deserialize_with = "nullable")]
+    pub devices: Vec<PluginDevice>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginConfig {
+    pub docker_version: Option<String>,
+    pub description: String,
+    pub documentation: String,
+    pub interface: PluginInterface,
+    pub entrypoint: Option<Vec<String>>,
+    pub work_dir: String,
+    pub user: Option<PluginUser>,
+    pub network: PluginNetwork,
+    pub linux: PluginLinux,
+    pub propagated_mount: String,
+    pub ipc_host: bool,
+    pub pid_host: bool,
+    pub mounts: Option<Vec<PluginMount>>,
+    pub env: Option<Vec<PluginEnv>>,
+    pub args: PluginArgs,
+    #[serde(rename = "rootfs")]
+    pub rootfs: Option<PluginRootfs>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginInterface {
+    /// Plugin types implemented, e.g. `docker.volumedriver/1.0`
+    #[serde(deserialize_with = "nullable")]
+    pub types: Vec<String>,
+    pub socket: String,
+    pub protocol_scheme: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginUser {
+    #[serde(rename = "UID")]
+    pub uid: Option<u32>,
+    #[serde(rename = "GID")]
+    pub gid: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginNetwork {
+    #[serde(rename = "Type")]
+    pub typ: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginLinux {
+    pub capabilities: Option<Vec<String>>,
+    pub allow_all_devices: bool,
+    pub devices: Option<Vec<PluginDevice>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginMount {
+    pub name: String,
+    pub description: String,
+    pub settable: Option<Vec<String>>,
+    pub source: Option<String>,
+    pub destination: String,
+    #[serde(rename = "Type")]
+    pub typ: String,
+    pub options: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginDevice {
+    pub name: String,
+    pub description: String,
+    pub settable: Option<Vec<String>>,
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginEnv {
+    pub name: String,
+    pub description: String,
+    pub settable: Option<Vec<String>>,
+    pub value: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginArgs {
+    pub name: String,
+    pub description: String,
+    pub settable: Option<Vec<String>>,
+    pub value: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginRootfs {
+    #[serde(rename = "type")]
+    pub typ: Option<String>,
+    pub diff_ids: Option<Vec<String>>,
+}
+
+/// A permission a plugin requires on the docker host
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct PluginPrivilege {
+    pub name: String,
+    pub description: String,
+    #[serde(deserialize_with = "nullable")]
+    pub value: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Change {
+    pub kind: u64,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Top {
+    #[serde(deserialize_with = "nullable")]
+    pub titles: Vec<String>,
+    #[serde(deserialize_with = "nullable")]
+    pub processes: Vec<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Version {
+    pub api_version: String,
+    #[serde(rename = "MinAPIVersion")]
+    pub min_api_version: Option<String>,
+    pub version: String,
+    pub git_commit: String,
+    pub go_version: String,
+    pub os: String,
+    pub arch: String,
+    pub kernel_version: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Info {
+    pub containers: u64,
+    pub images: u64,
+    pub driver: String,
+    pub docker_root_dir: String,
+    #[serde(deserialize_with = "nullable")]
+    pub driver_status: Vec<Vec<String>>,
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub kernel_version: String,
+    // pub Labels: Option<???>,
+    pub mem_total: u64,
+    pub memory_limit: bool,
+    #[serde(rename = "NCPU")]
+    pub n_cpu: u64,
+    pub n_events_listener: u64,
+    pub n_goroutines: u64,
+    pub name: String,
+    pub operating_system: String,
+    // pub RegistryConfig:???
+    pub swap_limit: bool,
+    #[cfg(feature = "chrono")]
+    pub system_time: Option<DateTime<Utc>>,
+    #[cfg(not(feature = "chrono"))]
+    pub system_time: Option<String>,
+}
+
+/// Information about a file or directory in a container, reported in the
+/// `X-Docker-Container-Path-Stat` header of archive responses
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathStat {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub size: i64,
+    /// Go `os.FileMode` bits, which include the file type as well as permissions
+    #[serde(default)]
+    pub mode: u32,
+    #[cfg(feature = "chrono")]
+    pub mtime: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub mtime: String,
+    /// The target of a symlink, or empty
+    #[serde(default)]
+    pub link_target: String,
+}
+
+/// Daemon metadata reported in the headers of a ping response
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PingInfo {
+    /// Default API version of the daemon
+    pub api_version: Option<String>,
+    /// Default builder, `1` for the classic builder and `2` for BuildKit
+    pub builder_version: Option<String>,
+    /// Whether the daemon runs with experimental features enabled
+    pub experimental: bool,
+}
+
+/// Features of a docker daemon, gathered from its ping response and version
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Capabilities {
+    /// Whether the daemon runs with experimental features enabled
+    pub experimental: bool,
+    /// Whether the daemon builds images with BuildKit by default
+    pub buildkit: bool,
+    /// Operating system the daemon runs on, `linux` or `windows`
+    pub os_type: String,
+    pub version: Version,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct AuthStatus {
+    pub status: String,
+    /// A token which may be used in place of the credentials for subsequent
+    /// registry requests, if the registry issued one
+    pub identity_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct DataUsage {
+    pub layers_size: Option<i64>,
+    pub images: Option<Vec<ImageUsage>>,
+    pub containers: Option<Vec<ContainerUsage>>,
+    pub volumes: Option<Vec<VolumeUsage>>,
+    pub build_cache: Option<Vec<BuildCache>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ImageUsage {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub parent_id: String,
+    pub repo_tags: Option<Vec<String>>,
+    pub repo_digests: Option<Vec<String>>,
+    #[cfg(feature = "chrono")]
+    #[serde(deserialize_with = "datetime_from_unix_timestamp")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: u64,
+    #[serde(default)]
+    pub size: i64,
+    /// Size shared with other images, or `-1` if it was not calculated
+    #[serde(default)]
+    pub shared_size: i64,
+    pub virtual_size: Option<i64>,
+    pub labels: Option<HashMap<String, String>>,
+    /// Number of containers using this image, or `-1` if it was not calculated
+    #[serde(default)]
+    pub containers: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerUsage {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, deserialize_with = "nullable")]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default, rename = "ImageID")]
+    pub image_id: String,
+    #[serde(default)]
+    pub command: String,
+    #[cfg(feature = "chrono")]
+    #[serde(deserialize_with = "datetime_from_unix_timestamp")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: u64,
+    pub size_rw: Option<i64>,
+    pub size_root_fs: Option<i64>,
+    pub labels: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct VolumeUsage {
+    pub name: String,
+    pub driver: String,
+    pub mountpoint: String,
+    pub labels: Option<HashMap<String, String>>,
+    pub scope: String,
+    pub options: Option<HashMap<String, String>>,
+    pub usage_data: Option<VolumeUsageData>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct VolumeUsageData {
+    /// Disk space used by the volume in bytes, or `-1` if it is not available
+    pub size: i64,
+    /// Number of containers referencing the volume, or `-1` if it is not available
+    pub ref_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BuildCache {
+    #[serde(default, rename = "ID")]
+    pub id: String,
+    pub parent: Option<String>,
+    #[serde(default, rename = "Type")]
+    pub typ: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub in_use: bool,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub size: i64,
+    #[cfg(feature = "chrono")]
+    pub created_at: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created_at: String,
+    #[cfg(feature = "chrono")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[cfg(not(feature = "chrono"))]
+    pub last_used_at: Option<String>,
+    #[serde(default)]
+    pub usage_count: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ContainerCreateInfo {
+    pub id: String,
+    pub warnings: Option<Vec<String>>,
+}
+
+/// The result of pruning stopped containers
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ContainerPruneInfo {
+    /// The ids of the deleted containers
+    pub containers_deleted: Option<Vec<String>>,
+    /// Disk space reclaimed, in bytes
+    pub space_reclaimed: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ExecCreateInfo {
+    pub id: String,
+}
+
+/// An exec instance, as returned by `Exec::inspect`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ExecDetails {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(rename = "ContainerID")]
+    pub container_id: String,
+    pub running: bool,
+    /// The exit code of the command, once it has exited
+    pub exit_code: Option<i64>,
+    pub process_config: ProcessConfig,
+    pub open_stdin: bool,
+    pub open_stdout: bool,
+    pub open_stderr: bool,
+    pub can_remove: bool,
+    pub detach_keys: String,
+    /// The command's pid on the host, or 0 until it's started
+    pub pid: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProcessConfig {
+    pub entrypoint: String,
+    #[serde(deserialize_with = "nullable")]
+    pub arguments: Vec<String>,
+    pub privileged: bool,
+    pub tty: bool,
+    pub user: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct History {
+    #[serde(default)]
+    pub id: String,
+    #[cfg(feature = "chrono")]
+    #[serde(deserialize_with = "datetime_from_unix_timestamp")]
+    pub created: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created: u64,
+    #[serde(default)]
+    pub created_by: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Exit {
+    /// The container's exit code, which may be negative or out of the usual
+    /// range when it was killed or failed to start
+    pub status_code: i64,
+    /// Why waiting on the container failed, if it did
+    pub error: Option<ExitError>,
+}
+
+impl Exit {
+    /// Whether the container exited successfully
+    pub fn success(&self) -> bool {
+        self.status_code == 0 && self.error.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ExitError {
+    pub message: String,
+}
+
+/// A record from the progress stream of an image pull, push, build or load
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Progress {
+    /// The layer or image this record refers to. Records about the same
+    /// layer share an id
+    pub id: Option<String>,
+    pub status: Option<String>,
+    /// A preformatted progress bar, as printed by the docker cli
+    pub progress: Option<String>,
+    pub progress_detail: Option<ProgressDetail>,
+    /// Output of a build step
+    pub stream: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Progress {
+    /// How far along this layer is, between `0.0` and `1.0`, if known
+    pub fn fraction(&self) -> Option<f64> {
+        self.progress_detail
+            .as_ref()
+            .and_then(ProgressDetail::fraction)
+    }
+
+    /// How far along this layer is, between `0.0` and `100.0`, if known
+    pub fn percent(&self) -> Option<f64> {
+        self.fraction().map(|fraction| fraction * 100.0)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressDetail {
+    pub current: Option<u64>,
+    pub total: Option<u64>,
+}
+
+impl ProgressDetail {
+    /// `current` as a fraction of `total`, between `0.0` and `1.0`. Returns
+    /// `None` unless both are known and `total` is non-zero
+    pub fn fraction(&self) -> Option<f64> {
+        match (self.current, self.total) {
+            (Some(current), Some(total)) if total > 0 => {
+                Some((current as f64 / total as f64).min(1.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// `current` as a percentage of `total`, between `0.0` and `100.0`
+    pub fn percent(&self) -> Option<f64> {
+        self.fraction().map(|fraction| fraction * 100.0)
+    }
+}
+
+/// An event emitted by the daemon.
+///
+/// Missing timestamps default to the unix epoch, so events from newer daemons
+/// which leave them out still decode.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct Event {
+    #[serde(rename = "Type")]
+    pub typ: EventType,
+    #[serde(default, rename = "Action")]
+    pub action: String,
+    #[serde(default, rename = "Actor")]
+    pub actor: Actor,
+    #[cfg(feature = "chrono")]
+    #[serde(
+        default = "unix_epoch",
+        deserialize_with = "datetime_from_unix_timestamp"
+    )]
+    pub time: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    #[serde(default)]
+    pub time: u64,
+    #[cfg(feature = "chrono")]
+    #[serde(
+        default = "unix_epoch",
+        deserialize_with = "datetime_from_nano_timestamp",
+        rename = "timeNano"
+    )]
+    pub time_nano: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    #[serde(default, rename = "timeNano")]
+    pub time_nano: u64,
+    /// The event as the daemon sent it, for events of a type this crate doesn't
+    /// know of, so their other fields can still be inspected
+    #[serde(skip)]
+    pub raw: Option<Value>,
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = Value::deserialize(deserializer)?;
+        let mut event = Event::deserialize(&raw).map_err(serde::de::Error::custom)?;
+        if let EventType::Unknown(_) = event.typ {
+            event.raw = Some(raw);
+        }
+        Ok(event)
+    }
+}
+
+impl Serialize for Event {
+    fn serialize<S>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Event::serialize(self, serializer)
+    }
+}
+
+/// The kind of object an `Event` was emitted for
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum EventType {
+    Builder,
+    Config,
+    Container,
+    Daemon,
+    Image,
+    Network,
+    Node,
+    Plugin,
+    Secret,
+    Service,
+    Volume,
+    /// A kind of object added in a newer version of docker than this crate knows of
+    Unknown(String),
+}
+
+impl EventType {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            EventType::Builder => "builder",
+            EventType::Config => "config",
+            EventType::Container => "container",
+            EventType::Daemon => "daemon",
+            EventType::Image => "image",
+            EventType::Network => "network",
+            EventType::Node => "node",
+            EventType::Plugin => "plugin",
+            EventType::Secret => "secret",
+            EventType::Service => "service",
+            EventType::Volume => "volume",
+            EventType::Unknown(typ) => typ,
+        }
+    }
+}
+
+impl From<String> for EventType {
+    fn from(typ: String) -> Self {
+        match typ.as_str() {
+            "builder" => EventType::Builder,
+            "config" => EventType::Config,
+            "container" => EventType::Container,
+            "daemon" => EventType::Daemon,
+            "image" => EventType::Image,
+            "network" => EventType::Network,
+            "node" => EventType::Node,
+            "plugin" => EventType::Plugin,
+            "secret" => EventType::Secret,
+            "service" => EventType::Service,
+            "volume" => EventType::Volume,
+            _ => EventType::Unknown(typ),
+        }
+    }
+}
+
+impl From<EventType> for String {
+    fn from(typ: EventType) -> Self {
+        match typ {
+            EventType::Unknown(typ) => typ,
+            typ => typ.as_str().to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Actor {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(deserialize_with = "nullable", rename = "Attributes")]
+    pub attributes: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Status {
+    Untagged(String),
+    Deleted(String),
+}
+
+/// The result of pruning unused images
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ImagePruneInfo {
+    /// The images untagged and deleted
+    pub images_deleted: Option<Vec<Status>>,
+    /// Disk space reclaimed, in bytes
+    pub space_reclaimed: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct VolumeCreateInfo {
+    pub name: String,
+}
+
+/// The result of pruning unused volumes
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct VolumePruneInfo {
+    /// The names of the deleted volumes
+    pub volumes_deleted: Option<Vec<String>>,
+    /// Disk space reclaimed, in bytes
+    pub space_reclaimed: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Volumes {
+    pub volumes: Option<Vec<Volume>>,
+    pub warnings: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Volume {
+    #[cfg(feature = "chrono")]
+    pub created_at: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created_at: String,
+    #[serde(default)]
+    pub driver: String,
+    pub labels: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mountpoint: String,
+    pub options: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub scope: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Secret {
+    #[serde(default, rename = "ID")]
+    pub id: String,
+    #[serde(default)]
+    pub version: ObjectVersion,
+    #[cfg(feature = "chrono")]
+    pub created_at: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub created_at: String,
+    #[cfg(feature = "chrono")]
+    pub updated_at: DateTime<Utc>,
+    #[cfg(not(feature = "chrono"))]
+    pub updated_at: String,
+    #[serde(default)]
+    pub spec: SecretSpec,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SecretSpec {
+    pub name: String,
+    #[serde(deserialize_with = "nullable")]
+    pub labels: HashMap<String, String>,
+}
+
+/// The version of a swarm object, which changes whenever it's updated
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ObjectVersion {
+    pub index: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretCreateInfo {
+    #[serde(rename = "ID")]
+    pub id: String,
+}
+
+/// Media types of the manifests and configs found in image registries
+pub mod media_types {
+    pub const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+    pub const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+    pub const OCI_CONFIG: &str = "application/vnd.oci.image.config.v1+json";
+    pub const DOCKER_MANIFEST: &str = "application/vnd.docker.distribution.manifest.v2+json";
+    pub const DOCKER_MANIFEST_LIST: &str =
+        "application/vnd.docker.distribution.manifest.list.v2+json";
+    pub const DOCKER_CONFIG: &str = "application/vnd.docker.container.image.v1+json";
+}
+
+/// A reference to content in a registry by its digest, such as a manifest,
+/// config or layer
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    /// e.g. `sha256:...`
+    pub digest: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty", deserialize_with = "nullable")]
+    pub urls: Vec<String>,
+    #[serde(
+        skip_serializing_if = "HashMap::is_empty",
+        deserialize_with = "nullable"
+    )]
+    pub annotations: HashMap<String, String>,
+    /// The platform a manifest is for, within a manifest list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Platform {
+    pub architecture: String,
+    pub os: String,
+    #[serde(rename = "os.version", skip_serializing_if = "Option::is_none")]
+    pub os_version: Option<String>,
+    #[serde(
+        rename = "os.features",
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "nullable"
+    )]
+    pub os_features: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+}
+
+/// The manifest of a single image, listing its config and layers
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageManifest {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub media_type: String,
+    pub config: Descriptor,
+    pub layers: Vec<Descriptor>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        deserialize_with = "nullable"
+    )]
+    pub annotations: HashMap<String, String>,
+}
+
+impl ImageManifest {
+    /// The digests of the image's layers, from the bottom up
+    pub fn layer_digests(&self) -> Vec<&str> {
+        self.layers.iter().map(|l| l.digest.as_str()).collect()
+    }
+}
+
+/// A manifest list, or OCI image index, pointing to a manifest per platform
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestList {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub media_type: String,
+    pub manifests: Vec<Descriptor>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        deserialize_with = "nullable"
+    )]
+    pub annotations: HashMap<String, String>,
+}
+
+impl ManifestList {
+    /// The manifest for an os and architecture, such as `linux` and `amd64`
+    pub fn manifest_for(
+        &self,
+        os: &str,
+        architecture: &str,
+    ) -> Option<&Descriptor> {
+        self.manifests.iter().find(|m| {
+            m.platform
+                .as_ref()
+                .map_or(false, |p| p.os == os && p.architecture == architecture)
+        })
+    }
+}
+
+/// Either kind of manifest a registry serves for an image reference, told
+/// apart by whether it lists manifests or layers
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Manifest {
+    List(ManifestList),
+    Image(ImageManifest),
+}
+
+/// The config an image's manifest points to, describing how to run it and
+/// the layers it's built from
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub architecture: String,
+    pub os: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<ImageRunConfig>,
+    pub rootfs: RootFs,
+    #[serde(skip_serializing_if = "Vec::is_empty", deserialize_with = "nullable")]
+    pub history: Vec<ImageConfigHistory>,
+}
+
+/// The defaults containers of an image run with
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ImageRunConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposed_ports: Option<HashMap<String, HashMap<String, String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cmd: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<HashMap<String, HashMap<String, String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_signal: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RootFs {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The digests of the uncompressed layers, from the bottom up
+    #[serde(deserialize_with = "nullable")]
+    pub diff_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageConfigHistory {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Whether this step left the filesystem unchanged, so has no layer
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub empty_layer: bool,
+}
+
+/// What the daemon finds out about an image reference from its registry
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct DistributionInspect {
+    /// The manifest, or manifest list, the reference resolves to
+    pub descriptor: Descriptor,
+    #[serde(deserialize_with = "nullable")]
+    pub platforms: Vec<Platform>,
+}
+
+#[cfg(feature = "chrono")]
+fn datetime_from_unix_timestamp<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let timestamp = chrono::NaiveDateTime::from_timestamp(i64::deserialize(deserializer)?, 0);
+    Ok(DateTime::<Utc>::from_utc(timestamp, Utc))
+}
+
+#[cfg(feature = "chrono")]
+fn datetime_from_nano_timestamp<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let timestamp_nano = u64::deserialize(deserializer)?;
+    let timestamp = chrono::NaiveDateTime::from_timestamp(
+        (timestamp_nano / 1_000_000_000) as i64,
+        (timestamp_nano % 1_000_000_000) as u32,
+    );
+    Ok(DateTime::<Utc>::from_utc(timestamp, Utc))
+}
+
+#[cfg(feature = "chrono")]
+fn unix_epoch() -> DateTime<Utc> {
+    DateTime::<Utc>::from_utc(chrono::NaiveDateTime::from_timestamp(0, 0), Utc)
+}
+
+/// Deserializes `null` as the type's default, as the daemon sends for some
+/// empty lists and maps
+fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        media_types, Container, ContainerDetails, Event, EventType, ExecDetails, Exit, ImageConfig,
+        Manifest, Progress, Stats,
+    };
+
+    #[test]
+    fn exec_details() {
+        let running: ExecDetails = serde_json::from_str(
+            r#"{
+                "ID": "f33bbfb3",
+                "ContainerID": "b53ee82b",
+                "Running": true,
+                "ExitCode": null,
+                "ProcessConfig": {
+                    "tty": true,
+                    "entrypoint": "sh",
+                    "arguments": ["-c", "sleep 60"],
+                    "privileged": false,
+                    "user": ""
+                },
+                "OpenStdin": false,
+                "OpenStderr": true,
+                "OpenStdout": true,
+                "CanRemove": false,
+                "DetachKeys": "",
+                "Pid": 42000
+            }"#,
+        )
+        .unwrap();
+        assert!(running.running);
+        assert_eq!(None, running.exit_code);
+        assert_eq!(vec!["-c", "sleep 60"], running.process_config.arguments);
+
+        let exited: ExecDetails =
+            serde_json::from_str(r#"{"ID":"f33bbfb3","Running":false,"ExitCode":3}"#).unwrap();
+        assert_eq!(Some(3), exited.exit_code);
+    }
+
+    #[test]
+    fn deserializes_container_details_across_api_versions() {
+        let old: ContainerDetails =
+            serde_json::from_str(include_str!("../fixtures/api/v1.24/container_inspect.json"))
+                .unwrap();
+        assert!(old.args.is_empty());
+        assert!(old.mounts.is_empty());
+        assert!(old.log_path.is_empty());
+
+        let new: ContainerDetails =
+            serde_json::from_str(include_str!("../fixtures/api/v1.41/container_inspect.json"))
+                .unwrap();
+        assert_eq!(vec!["infinity"], new.args);
+        assert_eq!("/data", new.mounts[0].destination);
+    }
+
+    #[test]
+    fn deserializes_container_lists_across_api_versions() {
+        let old: Vec<Container> =
+            serde_json::from_str(include_str!("../fixtures/api/v1.24/containers.json")).unwrap();
+        assert!(old[0].labels.is_empty());
+
+        let new: Vec<Container> =
+            serde_json::from_str(include_str!("../fixtures/api/v1.41/containers.json")).unwrap();
+        assert_eq!(2, new[0].ports.len());
+    }
+
+    #[test]
+    fn deserializes_stats_across_cgroup_versions() {
+        let v1: Stats =
+            serde_json::from_str(include_str!("../fixtures/api/v1.24/stats.json")).unwrap();
+        assert_eq!(4, v1.cpu_stats.cpu_usage.percpu_usage.len());
+
+        // cgroup v2 hosts report no per cpu usage, and null block io stats
+        let v2: Stats =
+            serde_json::from_str(include_str!("../fixtures/api/v1.41/stats.json")).unwrap();
+        assert!(v2.cpu_stats.cpu_usage.percpu_usage.is_empty());
+        assert!(v2.blkio_stats.io_service_bytes_recursive.is_empty());
+        assert_eq!(401_408, v2.memory_stats.usage);
+        #[cfg(feature = "chrono")]
+        assert_eq!("2021-03-04T13:42:11.374372528+00:00", v2.read.to_rfc3339());
+    }
+
+    #[test]
+    fn deserializes_exits() {
+        let exit: Exit = serde_json::from_str(r#"{"StatusCode":0,"Error":null}"#).unwrap();
+        assert!(exit.success());
+
+        let exit: Exit = serde_json::from_str(
+            r#"{"StatusCode":-1,"Error":{"Message":"container was removed"}}"#,
+        )
+        .unwrap();
+        assert!(!exit.success());
+        assert_eq!(-1, exit.status_code);
+        assert_eq!("container was removed", exit.error.unwrap().message);
+    }
+
+    #[test]
+    fn progress_reports_fraction_of_layer() {
+        let progress: Progress = serde_json::from_str(
+            r#"{"status":"Downloading","progressDetail":{"current":512,"total":2048},"progress":"[===>  ]","id":"a3ed95caeb02"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Some("a3ed95caeb02"),
+            progress.id.as_ref().map(String::as_str)
+        );
+        assert_eq!(Some(0.25), progress.fraction());
+        assert_eq!(Some(25.0), progress.percent());
+
+        let progress: Progress = serde_json::from_str(
+            r#"{"status":"Pull complete","progressDetail":{},"id":"a3ed95caeb02"}"#,
+        )
+        .unwrap();
+        assert_eq!(None, progress.percent());
+    }
+
+    #[test]
+    fn deserializes_events_of_unknown_types() {
+        let event: Event = serde_json::from_str(
+            r#"{"Type":"sandbox","Action":"create","Actor":{"ID":"abc","Attributes":{}},"time":1614865331,"timeNano":1614865331374372528}"#,
+        )
+        .unwrap();
+        assert_eq!(EventType::Unknown("sandbox".to_owned()), event.typ);
+        assert_eq!("create", event.action);
+        assert_eq!("abc", event.raw.unwrap()["Actor"]["ID"]);
+
+        let event: Event =
+            serde_json::from_str(r#"{"Type":"sandbox","Action":"create","scope":"local"}"#)
+                .unwrap();
+        assert_eq!("local", event.raw.unwrap()["scope"]);
+
+        let event: Event = serde_json::from_str(
+            r#"{"Type":"container","Action":"start","time":1614865331,"timeNano":1614865331374372528}"#,
+        )
+        .unwrap();
+        assert_eq!(EventType::Container, event.typ);
+        assert!(event.raw.is_none());
+    }
+
+    #[test]
+    fn deserializes_manifests_of_either_kind() {
+        let list: Manifest = serde_json::from_str(
+            r#"{
+                "schemaVersion": 2,
+                "mediaType": "application/vnd.docker.distribution.manifest.list.v2+json",
+                "manifests": [
+                    {"mediaType": "application/vnd.docker.distribution.manifest.v2+json", "digest": "sha256:aaa", "size": 527, "platform": {"architecture": "arm64", "os": "linux", "variant": "v8"}},
+                    {"mediaType": "application/vnd.docker.distribution.manifest.v2+json", "digest": "sha256:bbb", "size": 527, "platform": {"architecture": "amd64", "os": "linux"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        match list {
+            Manifest::List(list) => {
+                assert_eq!(media_types::DOCKER_MANIFEST_LIST, list.media_type);
+                let amd64 = list.manifest_for("linux", "amd64").unwrap();
+                assert_eq!("sha256:bbb", amd64.digest);
+                assert!(list.manifest_for("windows", "amd64").is_none());
+            }
+            other => panic!("unexpected manifest {:?}", other),
+        }
+
+        let image: Manifest = serde_json::from_str(
+            r#"{
+                "schemaVersion": 2,
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "config": {"mediaType": "application/vnd.oci.image.config.v1+json", "digest": "sha256:ccc", "size": 1469},
+                "layers": [
+                    {"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": "sha256:ddd", "size": 2811478},
+                    {"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": "sha256:eee", "size": 120}
+                ]
+            }"#,
+        )
+        .unwrap();
+        match image {
+            Manifest::Image(image) => {
+                assert_eq!(media_types::OCI_CONFIG, image.config.media_type);
+                assert_eq!(vec!["sha256:ddd", "sha256:eee"], image.layer_digests());
+            }
+            other => panic!("unexpected manifest {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deserializes_image_configs() {
+        let config: ImageConfig = serde_json::from_str(
+            r#"{
+                "architecture": "amd64",
+                "os": "linux",
+                "config": {"Env": ["PATH=/bin"], "Cmd": ["/bin/sh"], "ExposedPorts": {"80/tcp": {}}},
+                "rootfs": {"type": "layers", "diff_ids": ["sha256:fff"]},
+                "history": [{"created_by": "ADD file:abc in /"}, {"created_by": "CMD [\"/bin/sh\"]", "empty_layer": true}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!("layers", config.rootfs.kind);
+        assert_eq!(vec!["sha256:fff"], config.rootfs.diff_ids);
+        let run = config.config.unwrap();
+        assert_eq!(Some(vec!["/bin/sh".to_owned()]), run.cmd);
+        assert!(run.exposed_ports.unwrap().contains_key("80/tcp"));
+        assert_eq!(
+            vec![false, true],
+            config
+                .history
+                .iter()
+                .map(|h| h.empty_layer)
+                .collect::<Vec<_>>()
+        );
+    }
+}
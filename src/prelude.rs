@@ -0,0 +1,30 @@
+//! Convenience re-exports for the types most programs need
+//!
+//! ```no_run
+//! use shiplift::prelude::*;
+//!
+//! let docker = Docker::new();
+//! let fut = docker
+//!     .containers()
+//!     .list(&ContainerListOptions::builder().all().build())
+//!     .map(|containers| println!("{} containers", containers.len()))
+//!     .map_err(|e| eprintln!("Error: {}", e));
+//!
+//! tokio::run(fut);
+//! ```
+
+pub use crate::{
+    builder::{
+        BuildOptions, ContainerListOptions, ContainerOptions, ContainerPruneOptions, EventsOptions,
+        ExecContainerOptions, Filters, ImageListOptions, ImagePruneOptions, LogsOptions,
+        NetworkCreateOptions, NetworkListOptions, NetworkPruneOptions, PullManyOptions,
+        PullOptions, RegistryAuth, RmContainerOptions, Tail, VolumeCreateOptions,
+        VolumeListOptions, VolumePruneOptions,
+    },
+    cancel::CancelHandle,
+    progress::ProgressReporter,
+    tty::{Chunk as TtyChunk, StreamType},
+    Container, Containers, Docker, Error, Exec, Execs, Image, Images, Network, Networks, Plugin,
+    Plugins, Secret, Secrets, Volume, Volumes,
+};
+pub use futures::{Future, Stream};
@@ -0,0 +1,227 @@
+//! Reporting the progress of pulls, pushes, builds and exports as they happen
+
+use crate::{models::Progress, Error};
+use bytes::Bytes;
+use futures::{try_ready, Async, Future, Poll, Stream};
+use serde_json::Value;
+
+/// Receives progress as a streamed operation runs, e.g. to drive a progress
+/// bar or write to a log. Every method does nothing by default
+pub trait ProgressReporter {
+    /// Called with each progress record of a pull, push or build. A record
+    /// carrying an `error` is reported like any other, and ends the operation
+    /// with an `Error::OperationFailed`
+    fn progress(
+        &mut self,
+        _progress: &Progress,
+    ) {
+    }
+
+    /// Called as each chunk of an export arrives, with the number of bytes
+    /// received so far
+    fn transferred(
+        &mut self,
+        _bytes: u64,
+    ) {
+    }
+
+    /// Called once the operation has finished successfully
+    fn finished(&mut self) {}
+}
+
+impl<R: ProgressReporter + ?Sized> ProgressReporter for &mut R {
+    fn progress(
+        &mut self,
+        progress: &Progress,
+    ) {
+        (**self).progress(progress)
+    }
+
+    fn transferred(
+        &mut self,
+        bytes: u64,
+    ) {
+        (**self).transferred(bytes)
+    }
+
+    fn finished(&mut self) {
+        (**self).finished()
+    }
+}
+
+impl<R: ProgressReporter + ?Sized> ProgressReporter for Box<R> {
+    fn progress(
+        &mut self,
+        progress: &Progress,
+    ) {
+        (**self).progress(progress)
+    }
+
+    fn transferred(
+        &mut self,
+        bytes: u64,
+    ) {
+        (**self).transferred(bytes)
+    }
+
+    fn finished(&mut self) {
+        (**self).finished()
+    }
+}
+
+/// Passes each record of a progress stream to `reporter`, resolving to the
+/// reporter once the stream ends. The daemon reports a failed pull, push or
+/// build with a record carrying an `error`, which fails the returned future
+/// without calling `finished`
+pub fn report<S, R>(
+    stream: S,
+    reporter: R,
+) -> impl Future<Item = R, Error = Error>
+where
+    S: Stream<Item = Value, Error = Error>,
+    R: ProgressReporter,
+{
+    stream
+        .fold(reporter, |mut reporter, value| {
+            let progress = serde_json::from_value::<Progress>(value)?;
+            reporter.progress(&progress);
+            match progress.error {
+                Some(message) => Err(Error::OperationFailed(message)),
+                None => Ok(reporter),
+            }
+        })
+        .map(|mut reporter| {
+            reporter.finished();
+            reporter
+        })
+}
+
+/// Fails on a progress record carrying an `error`, as the daemon sends when a
+/// pull, push or build fails part way
+pub(crate) fn failed_record(value: Value) -> Result<Value, Error> {
+    match value.get("error").and_then(Value::as_str) {
+        Some(message) => Err(Error::OperationFailed(message.to_owned())),
+        None => Ok(value),
+    }
+}
+
+/// Passes the running total of bytes received from a stream to `reporter`,
+/// yielding each chunk unchanged
+pub fn report_bytes<S, R>(
+    stream: S,
+    reporter: R,
+) -> impl Stream<Item = Bytes, Error = Error>
+where
+    S: Stream<Item = Bytes, Error = Error>,
+    R: ProgressReporter,
+{
+    ReportBytes {
+        stream,
+        reporter,
+        received: 0,
+    }
+}
+
+struct ReportBytes<S, R> {
+    stream: S,
+    reporter: R,
+    received: u64,
+}
+
+impl<S, R> Stream for ReportBytes<S, R>
+where
+    S: Stream<Item = Bytes, Error = Error>,
+    R: ProgressReporter,
+{
+    type Item = Bytes;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Bytes>, Error> {
+        match try_ready!(self.stream.poll()) {
+            Some(bytes) => {
+                self.received += bytes.len() as u64;
+                self.reporter.transferred(self.received);
+                Ok(Async::Ready(Some(bytes)))
+            }
+            None => {
+                self.reporter.finished();
+                Ok(Async::Ready(None))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{report, report_bytes, ProgressReporter};
+    use crate::{models::Progress, Error};
+    use bytes::Bytes;
+    use futures::{stream, Future, Stream};
+    use serde_json::json;
+
+    #[derive(Default)]
+    struct Recorder {
+        percents: Vec<Option<f64>>,
+        transferred: Vec<u64>,
+        finished: bool,
+    }
+
+    impl ProgressReporter for Recorder {
+        fn progress(
+            &mut self,
+            progress: &Progress,
+        ) {
+            self.percents.push(progress.percent())
+        }
+
+        fn transferred(
+            &mut self,
+            bytes: u64,
+        ) {
+            self.transferred.push(bytes)
+        }
+
+        fn finished(&mut self) {
+            self.finished = true
+        }
+    }
+
+    #[test]
+    fn reports_each_progress_record() {
+        let records = stream::iter_ok(vec![
+            json!({"status": "Pulling fs layer", "id": "a3ed95caeb02"}),
+            json!({"status": "Downloading", "id": "a3ed95caeb02", "progressDetail": {"current": 1, "total": 4}}),
+        ]);
+        let recorder = report(records, Recorder::default()).wait().unwrap();
+        assert_eq!(vec![None, Some(25.0)], recorder.percents);
+        assert!(recorder.finished);
+    }
+
+    #[test]
+    fn error_records_fail_the_operation() {
+        let mut recorder = Recorder::default();
+        let records = stream::iter_ok(vec![
+            json!({"status": "Pulling from library/nginx", "id": "latest"}),
+            json!({"error": "manifest unknown", "errorDetail": {"message": "manifest unknown"}}),
+        ]);
+        match report(records, &mut recorder).wait() {
+            Err(Error::OperationFailed(message)) => assert_eq!("manifest unknown", message),
+            other => panic!("expected the pull to fail, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(2, recorder.percents.len());
+        assert!(!recorder.finished);
+    }
+
+    #[test]
+    fn reports_bytes_received() {
+        let mut recorder = Recorder::default();
+        let chunks = stream::iter_ok(vec![Bytes::from(&b"abc"[..]), Bytes::from(&b"de"[..])]);
+        let received = report_bytes(chunks, &mut recorder)
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(2, received.len());
+        assert_eq!(vec![3, 5], recorder.transferred);
+        assert!(recorder.finished);
+    }
+}
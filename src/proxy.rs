@@ -0,0 +1,148 @@
+use futures::{Future, Poll};
+use hyper::{
+    client::{
+        connect::{Connect, Connected, Destination},
+        HttpConnector,
+    },
+    Uri,
+};
+use hyper_proxy::{Intercept, Proxy, ProxyConnector};
+use std::{
+    env, fmt,
+    io::{self, Read, Write},
+};
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Connects through a proxy when one applies, or else directly. Connections to
+/// https urls are tunnelled, leaving tls to the connector wrapping this one
+pub struct Connector(ProxyConnector<HttpConnector>);
+
+impl Connect for Connector {
+    type Transport = Connection;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = (Connection, Connected), Error = io::Error> + Send>;
+
+    fn connect(
+        &self,
+        dst: Destination,
+    ) -> Self::Future {
+        Box::new(
+            self.0
+                .connect(dst)
+                .map(|(stream, connected)| (Connection(stream), connected)),
+        )
+    }
+}
+
+/// A connection made by a `Connector`, which unlike hyper-proxy's own can be
+/// wrapped by `hyper_openssl`, as that needs it to be `Debug`
+pub struct Connection(<ProxyConnector<HttpConnector> as Connect>::Transport);
+
+impl fmt::Debug for Connection {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        f.write_str("Connection")
+    }
+}
+
+impl Read for Connection {
+    fn read(
+        &mut self,
+        buf: &mut [u8],
+    ) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Connection {
+    fn write(
+        &mut self,
+        buf: &[u8],
+    ) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl AsyncRead for Connection {}
+
+impl AsyncWrite for Connection {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.0.shutdown()
+    }
+}
+
+/// Wraps a connector to go through the given proxy, or else the proxies
+/// configured by the environment, as curl and the docker CLI do
+pub fn connector(
+    http: HttpConnector,
+    proxy: Option<&Uri>,
+) -> Connector {
+    let mut connector = ProxyConnector::unsecured(http);
+    match proxy {
+        Some(proxy) => connector.add_proxy(Proxy::new(Intercept::All, proxy.clone())),
+        None => {
+            let no_proxy = env_var("NO_PROXY").unwrap_or_default();
+            for &(scheme, var) in &[("http", "HTTP_PROXY"), ("https", "HTTPS_PROXY")] {
+                let proxy = match env_var(var).and_then(|uri| uri.parse::<Uri>().ok()) {
+                    Some(proxy) => proxy,
+                    None => continue,
+                };
+                let no_proxy = no_proxy.clone();
+                let intercept = move |target: Option<&str>, host: Option<&str>, _: Option<u16>| {
+                    target == Some(scheme) && !host.map_or(false, |h| bypass(&no_proxy, h))
+                };
+                connector.add_proxy(Proxy::new(Intercept::Custom(intercept.into()), proxy));
+            }
+        }
+    }
+    Connector(connector)
+}
+
+/// Proxy env vars may be upper or lower case
+fn env_var(name: &str) -> Option<String> {
+    env::var(name)
+        .or_else(|_| env::var(name.to_lowercase()))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// Whether a `NO_PROXY` list of hosts and domains, such as `localhost,.internal`,
+/// matches the given host
+fn bypass(
+    no_proxy: &str,
+    host: &str,
+) -> bool {
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host == entry
+                || (host.ends_with(entry) && host[..host.len() - entry.len()].ends_with('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bypass;
+
+    #[test]
+    fn no_proxy_matching() {
+        let no_proxy = "localhost, .internal,example.com";
+        assert!(bypass(no_proxy, "localhost"));
+        assert!(bypass(no_proxy, "docker.internal"));
+        assert!(bypass(no_proxy, "example.com"));
+        assert!(bypass(no_proxy, "docker.example.com"));
+        assert!(!bypass(no_proxy, "badexample.com"));
+        assert!(!bypass(no_proxy, "docker.example.org"));
+        assert!(bypass("*", "docker.example.org"));
+        assert!(!bypass("", "localhost"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Pulling several images at once
+
+use crate::Error;
+use futures::{Async, Poll, Stream};
+use std::vec;
+
+/// Merges the progress of pulls started from a list of image references, with at
+/// most `parallelism` pulls in flight at once. Each item is keyed by the reference
+/// of the pull it came from, and a pull which fails yields its error as an item,
+/// so the other pulls carry on
+pub(crate) struct PullMany<F, S> {
+    pending: vec::IntoIter<String>,
+    active: Vec<(String, S)>,
+    pull: F,
+    parallelism: usize,
+    next: usize,
+}
+
+impl<F, S> PullMany<F, S>
+where
+    F: FnMut(&str) -> S,
+    S: Stream<Error = Error>,
+{
+    pub(crate) fn new(
+        images: Vec<String>,
+        parallelism: usize,
+        pull: F,
+    ) -> Self {
+        PullMany {
+            pending: images.into_iter(),
+            active: Vec::new(),
+            pull,
+            parallelism: parallelism.max(1),
+            next: 0,
+        }
+    }
+}
+
+impl<F, S> Stream for PullMany<F, S>
+where
+    F: FnMut(&str) -> S,
+    S: Stream<Error = Error>,
+{
+    type Item = (String, Result<S::Item, Error>);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Error> {
+        'refill: loop {
+            while self.active.len() < self.parallelism {
+                match self.pending.next() {
+                    Some(image) => {
+                        let pull = (self.pull)(&image);
+                        self.active.push((image, pull));
+                    }
+                    None => break,
+                }
+            }
+            if self.active.is_empty() {
+                return Ok(Async::Ready(None));
+            }
+
+            // start from where the last item came from, so one chatty pull
+            // can't starve the rest
+            for polled in 0..self.active.len() {
+                let index = (self.next + polled) % self.active.len();
+                match self.active[index].1.poll() {
+                    Ok(Async::Ready(Some(item))) => {
+                        self.next = index + 1;
+                        return Ok(Async::Ready(Some((self.active[index].0.clone(), Ok(item)))));
+                    }
+                    Ok(Async::Ready(None)) => {
+                        self.active.swap_remove(index);
+                        continue 'refill;
+                    }
+                    Ok(Async::NotReady) => (),
+                    Err(e) => {
+                        let (image, _) = self.active.swap_remove(index);
+                        self.next = index;
+                        return Ok(Async::Ready(Some((image, Err(e)))));
+                    }
+                }
+            }
+            return Ok(Async::NotReady);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PullMany;
+    use crate::{testing::MockTransport, Docker, Error, PullManyOptions};
+    use futures::{stream, Future, Stream};
+    use hyper::{Method, StatusCode};
+    use serde_json::json;
+    use std::{cell::RefCell, rc::Rc};
+    use tokio::runtime::current_thread::block_on_all;
+
+    #[test]
+    fn pulls_are_merged_and_keyed_by_image() {
+        let images = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        let pulls = PullMany::new(images, 2, |image: &str| {
+            stream::iter_ok::<_, Error>(vec![format!("{}1", image), format!("{}2", image)])
+        });
+        let mut progress = pulls
+            .map(|(image, progress)| (image, progress.unwrap()))
+            .collect()
+            .wait()
+            .unwrap();
+        progress.sort();
+        assert_eq!(
+            vec![
+                ("a".to_owned(), "a1".to_owned()),
+                ("a".to_owned(), "a2".to_owned()),
+                ("b".to_owned(), "b1".to_owned()),
+                ("b".to_owned(), "b2".to_owned()),
+                ("c".to_owned(), "c1".to_owned()),
+                ("c".to_owned(), "c2".to_owned()),
+            ],
+            progress
+        );
+    }
+
+    #[test]
+    fn pulls_start_no_faster_than_the_parallelism_allows() {
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let images = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        let log = started.clone();
+        let mut pulls = PullMany::new(images, 2, move |image: &str| {
+            log.borrow_mut().push(image.to_owned());
+            stream::iter_ok::<_, Error>(vec![()])
+        })
+        .wait();
+
+        pulls.next().unwrap().unwrap().1.unwrap();
+        assert_eq!(vec!["a", "b"], *started.borrow());
+        while let Some(pull) = pulls.next() {
+            pull.unwrap().1.unwrap();
+        }
+        assert_eq!(vec!["a", "b", "c"], *started.borrow());
+    }
+
+    #[test]
+    fn failed_pulls_are_keyed_by_image_and_the_rest_carry_on() {
+        let mock = MockTransport::new();
+        mock.on(Method::POST, "/images/create")
+            .query("fromImage", "a")
+            .respond(StatusCode::OK, r#"{"status":"Pulling a"}"#);
+        mock.on(Method::POST, "/images/create")
+            .query("fromImage", "b")
+            .respond(StatusCode::NOT_FOUND, r#"{"message":"no such image"}"#);
+        mock.on(Method::POST, "/images/create")
+            .query("fromImage", "c")
+            .respond(
+                StatusCode::OK,
+                r#"{"status":"Pulling c"}{"error":"manifest unknown","errorDetail":{"message":"manifest unknown"}}"#,
+            );
+        mock.on(Method::POST, "/images/create")
+            .query("fromImage", "d")
+            .respond(StatusCode::OK, r#"{"status":"Pulling d"}"#);
+        let docker = Docker::from_transport(mock);
+
+        let pulls = docker.images().pull_many(
+            vec!["a", "b", "c", "d"],
+            &PullManyOptions::builder().parallelism(2).build(),
+        );
+        let mut outcomes = block_on_all(
+            pulls
+                .map(|(image, progress)| (image, progress.map_err(|e| e.to_string())))
+                .collect(),
+        )
+        .unwrap();
+        outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+        let failed = outcomes
+            .iter()
+            .filter(|(_, progress)| progress.is_err())
+            .map(|(image, _)| image.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["b", "c"], failed);
+        assert!(outcomes.contains(&(
+            "c".to_owned(),
+            Err(Error::OperationFailed("manifest unknown".to_owned()).to_string())
+        )));
+        assert!(outcomes.contains(&("d".to_owned(), Ok(json!({"status": "Pulling d"})))));
+    }
+}
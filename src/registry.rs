@@ -0,0 +1,816 @@
+//! Querying image registries directly, for the manifests and configs of images
+//! which haven't been pulled
+//!
+//! Registries which ask for a token, as Docker Hub and GHCR do even for public
+//! images, are sent one fetched from the auth server named in their
+//! `WWW-Authenticate` challenge, using the registry's credentials if it has
+//! any. Tokens are reused for later requests to the same repository.
+//!
+//! ```no_run
+//! # // `Registry::new` needs one of the tls features
+//! # #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+//! # fn main() {
+//! use futures::Future;
+//! use shiplift::registry::Registry;
+//!
+//! let registry = Registry::new().unwrap();
+//! tokio::run(
+//!     registry
+//!         .platform_manifest("nginx:1.19", "linux", "amd64")
+//!         .map(|manifest| println!("layers {:?}", manifest.layer_digests()))
+//!         .map_err(|e| eprintln!("Error: {}", e)),
+//! );
+//! # }
+//! # #[cfg(not(any(feature = "tls", feature = "tls-rustls")))]
+//! # fn main() {}
+//! ```
+
+use crate::{
+    builder::RegistryAuth,
+    models::{media_types, ImageConfig, ImageManifest, Manifest},
+    transport::HttpTransport,
+    Error, Result,
+};
+use bytes::Bytes;
+use futures::{future, Future, Stream};
+use hyper::{header, Body, Method, Request, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use url::{form_urlencoded, Url};
+
+/// The registry images without one in their name come from
+const DOCKER_HUB: &str = "registry-1.docker.io";
+
+/// How many redirects are followed for one request
+const MAX_REDIRECTS: usize = 5;
+
+/// How long `Registry::new` waits for a connection to a registry
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long `Registry::new` waits for a registry to respond to a request
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+/// The kinds of manifest an image reference may resolve to
+const MANIFESTS: [&str; 4] = [
+    media_types::DOCKER_MANIFEST_LIST,
+    media_types::OCI_INDEX,
+    media_types::DOCKER_MANIFEST,
+    media_types::OCI_MANIFEST,
+];
+
+type ResponseFuture = Box<dyn Future<Item = Response<Body>, Error = Error> + Send>;
+
+/// A client for the registry API, shared by every registry an image reference
+/// may name
+#[derive(Clone, Debug)]
+pub struct Registry {
+    transport: Arc<dyn HttpTransport>,
+    auth: Option<RegistryAuth>,
+    /// The `Authorization` header last accepted for each repository
+    authorizations: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl Registry {
+    /// A client connecting over https, through any proxy configured by the
+    /// environment
+    #[cfg(any(feature = "tls", feature = "tls-rustls"))]
+    pub fn new() -> Result<Registry> {
+        Ok(Registry::from_transport(Https::new()?))
+    }
+
+    /// A client sending its requests with a custom transport, which is given
+    /// absolute uris
+    pub fn from_transport<T>(transport: T) -> Registry
+    where
+        T: HttpTransport,
+    {
+        Registry {
+            transport: Arc::new(transport),
+            auth: None,
+            authorizations: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Credentials exchanged for tokens when registries ask for them. A
+    /// `RegistryAuth::Token` is used as the refresh token `docker login` stores
+    pub fn with_auth(
+        mut self,
+        auth: RegistryAuth,
+    ) -> Registry {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// The manifest an image reference, such as `nginx:1.19` or
+    /// `ghcr.io/owner/app@sha256:...`, resolves to
+    pub fn manifest(
+        &self,
+        image: &str,
+    ) -> impl Future<Item = Manifest, Error = Error> {
+        let registry = self.clone();
+        future::result(Reference::parse(image)).and_then(move |reference| {
+            registry.get_json(
+                &reference,
+                &reference.manifest_path(),
+                &MANIFESTS.join(", "),
+            )
+        })
+    }
+
+    /// The image manifest of an image for a platform, looked up in the manifest
+    /// list the reference resolves to if it's a multi-platform image
+    pub fn platform_manifest(
+        &self,
+        image: &str,
+        os: &str,
+        architecture: &str,
+    ) -> impl Future<Item = ImageManifest, Error = Error> {
+        let registry = self.clone();
+        let image = image.to_owned();
+        let (os, architecture) = (os.to_owned(), architecture.to_owned());
+        future::result(Reference::parse(&image)).and_then(move |mut reference| {
+            let path = reference.manifest_path();
+            registry
+                .get_json(&reference, &path, &MANIFESTS.join(", "))
+                .and_then(move |manifest| {
+                    let list = match manifest {
+                        Manifest::Image(manifest) => {
+                            return future::Either::A(future::ok(manifest))
+                        }
+                        Manifest::List(list) => list,
+                    };
+                    let descriptor = match list.manifest_for(&os, &architecture) {
+                        Some(descriptor) => descriptor.clone(),
+                        None => {
+                            return future::Either::A(future::err(Error::NotFound {
+                                message: format!(
+                                    "{} has no image for {}/{}",
+                                    image, os, architecture
+                                ),
+                                response: None,
+                            }))
+                        }
+                    };
+                    reference.reference = descriptor.digest;
+                    let path = reference.manifest_path();
+                    future::Either::B(registry.get_json(&reference, &path, &descriptor.media_type))
+                })
+        })
+    }
+
+    /// The config an image manifest, from `platform_manifest`, points to
+    pub fn config(
+        &self,
+        image: &str,
+        manifest: &ImageManifest,
+    ) -> impl Future<Item = ImageConfig, Error = Error> {
+        let registry = self.clone();
+        let config = manifest.config.clone();
+        future::result(Reference::parse(image)).and_then(move |reference| {
+            let path = format!("/v2/{}/blobs/{}", reference.repository, config.digest);
+            registry.get_json(&reference, &path, &config.media_type)
+        })
+    }
+
+    fn get_json<T>(
+        &self,
+        reference: &Reference,
+        path: &str,
+        accept: &str,
+    ) -> impl Future<Item = T, Error = Error>
+    where
+        T: DeserializeOwned,
+    {
+        self.get(reference, path, accept)
+            .and_then(|body| serde_json::from_slice(&body).map_err(Error::from))
+    }
+
+    /// Gets a registry resource, answering the challenge of a `401` response
+    /// and then retrying it
+    fn get(
+        &self,
+        reference: &Reference,
+        path: &str,
+        accept: &str,
+    ) -> impl Future<Item = Bytes, Error = Error> {
+        let uri = format!(
+            "{}://{}{}",
+            scheme(&reference.registry),
+            reference.registry,
+            path
+        );
+        let key = format!("{}/{}", reference.registry, reference.repository);
+        let accept = accept.to_owned();
+        let authorization = self.authorizations.lock().unwrap().get(&key).cloned();
+        let registry = self.clone();
+        self.send(
+            Method::GET,
+            &uri,
+            &accept,
+            authorization.as_ref(),
+            Body::empty(),
+        )
+        .and_then(move |res| {
+            let challenge = res
+                .headers()
+                .get(header::WWW_AUTHENTICATE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(Challenge::parse);
+            let challenge = match challenge {
+                Some(challenge) if res.status() == StatusCode::UNAUTHORIZED => challenge,
+                _ => return future::Either::A(registry.read(&uri, res)),
+            };
+            let retry = registry.clone();
+            future::Either::B(
+                registry
+                    .authorize(&challenge)
+                    .and_then(move |authorization| {
+                        retry
+                            .authorizations
+                            .lock()
+                            .unwrap()
+                            .insert(key, authorization.clone());
+                        retry
+                            .send(
+                                Method::GET,
+                                &uri,
+                                &accept,
+                                Some(&authorization),
+                                Body::empty(),
+                            )
+                            .and_then(move |res| retry.read(&uri, res))
+                    }),
+            )
+        })
+    }
+
+    /// The `Authorization` header a challenge asks for
+    fn authorize(
+        &self,
+        challenge: &Challenge,
+    ) -> Box<dyn Future<Item = String, Error = Error> + Send> {
+        if challenge.scheme.eq_ignore_ascii_case("basic") {
+            return Box::new(future::result(self.basic().ok_or_else(|| {
+                Error::Unauthorized {
+                    message: "the registry requires a username and password".to_owned(),
+                    response: None,
+                }
+            })));
+        }
+        let realm = match challenge.params.get("realm") {
+            Some(realm) => realm,
+            None => {
+                return Box::new(future::err(Error::InvalidResponse(
+                    "the registry's challenge has no realm".to_owned(),
+                )))
+            }
+        };
+        let mut params = form_urlencoded::Serializer::new(String::new());
+        for key in &["service", "scope"] {
+            if let Some(value) = challenge.params.get(*key) {
+                params.append_pair(key, value);
+            }
+        }
+        let (uri, token) = match self.auth {
+            // identity tokens are OAuth2 refresh tokens
+            Some(RegistryAuth::Token { ref identity_token }) => {
+                let body = params
+                    .append_pair("grant_type", "refresh_token")
+                    .append_pair("refresh_token", identity_token)
+                    .append_pair("client_id", "shiplift")
+                    .finish();
+                let token = self.send(
+                    Method::POST,
+                    realm,
+                    "application/json",
+                    None,
+                    Body::from(body),
+                );
+                (realm.to_owned(), token)
+            }
+            _ => {
+                let uri = format!("{}?{}", realm, params.finish());
+                let token = self.send(
+                    Method::GET,
+                    &uri,
+                    "application/json",
+                    self.basic().as_ref(),
+                    Body::empty(),
+                );
+                (uri, token)
+            }
+        };
+        let registry = self.clone();
+        Box::new(
+            token
+                .and_then(move |res| registry.read(&uri, res))
+                .and_then(|body| {
+                    let token: Token = serde_json::from_slice(&body)?;
+                    token
+                        .token
+                        .or(token.access_token)
+                        .map(|token| format!("Bearer {}", token))
+                        .ok_or_else(|| {
+                            Error::InvalidResponse("the auth server sent no token".to_owned())
+                        })
+                }),
+        )
+    }
+
+    fn basic(&self) -> Option<String> {
+        match self.auth {
+            Some(RegistryAuth::Password {
+                ref username,
+                ref password,
+                ..
+            }) if !username.is_empty() => Some(format!(
+                "Basic {}",
+                base64::encode(&format!("{}:{}", username, password))
+            )),
+            _ => None,
+        }
+    }
+
+    fn send(
+        &self,
+        method: Method,
+        uri: &str,
+        accept: &str,
+        authorization: Option<&String>,
+        body: Body,
+    ) -> ResponseFuture {
+        let mut req = Request::builder();
+        req.method(method.clone())
+            .uri(uri)
+            .header(header::ACCEPT, accept);
+        if let Some(authorization) = authorization {
+            req.header(header::AUTHORIZATION, authorization.as_str());
+        }
+        if method == Method::POST {
+            req.header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+        }
+        match req.body(body) {
+            Ok(req) => self.transport.request(req),
+            Err(e) => Box::new(future::err(Error::Http(e))),
+        }
+    }
+
+    /// Reads the body of the response to a request for `uri`, following
+    /// redirects to where blobs are stored, or turns an error response into an
+    /// error
+    fn read(
+        &self,
+        uri: &str,
+        res: Response<Body>,
+    ) -> Box<dyn Future<Item = Bytes, Error = Error> + Send> {
+        self.follow(uri, res, MAX_REDIRECTS)
+    }
+
+    fn follow(
+        &self,
+        uri: &str,
+        res: Response<Body>,
+        redirects: usize,
+    ) -> Box<dyn Future<Item = Bytes, Error = Error> + Send> {
+        let status = res.status();
+        let location = res
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok());
+        if let (true, Some(location)) = (status.is_redirection(), location) {
+            let location = match redirect(uri, location, redirects) {
+                Ok(location) => location,
+                Err(e) => return Box::new(future::err(e)),
+            };
+            // the storage redirected to is authorized by the url itself
+            let registry = self.clone();
+            return Box::new(
+                self.send(Method::GET, &location, "*/*", None, Body::empty())
+                    .and_then(move |res| registry.follow(&location, res, redirects - 1)),
+            );
+        }
+        let (parts, body) = res.into_parts();
+        Box::new(body.concat2().map_err(Error::Hyper).and_then(move |body| {
+            let body = body.into_bytes();
+            if status.is_success() {
+                return Ok(body);
+            }
+            let message = serde_json::from_slice::<Errors>(&body)
+                .ok()
+                .and_then(|errors| errors.errors.into_iter().next())
+                .map(|error| error.message)
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+            Err(Error::from_response(status, parts.headers, body, message))
+        }))
+    }
+}
+
+/// Resolves where a response to a request for `uri` redirects to, refusing
+/// to follow more redirects than are left or to leave https for http
+fn redirect(
+    uri: &str,
+    location: &str,
+    redirects: usize,
+) -> Result<String> {
+    if redirects == 0 {
+        return Err(Error::InvalidResponse(format!(
+            "the registry redirected more than {} times",
+            MAX_REDIRECTS
+        )));
+    }
+    let invalid = |e: url::ParseError| {
+        Error::InvalidResponse(format!("the registry redirected to {}: {}", location, e))
+    };
+    let from = Url::parse(uri).map_err(invalid)?;
+    let to = from.join(location).map_err(invalid)?;
+    if from.scheme() == "https" && to.scheme() != "https" {
+        return Err(Error::InvalidResponse(format!(
+            "the registry redirected from https to {}",
+            to
+        )));
+    }
+    Ok(to.to_string())
+}
+
+/// An image reference split into the registry it's in, its repository there
+/// and its tag or digest
+#[derive(Clone, Debug, PartialEq)]
+struct Reference {
+    registry: String,
+    repository: String,
+    reference: String,
+}
+
+impl Reference {
+    /// Resolves short names the way the docker CLI does, so `nginx` is
+    /// `library/nginx:latest` on Docker Hub
+    fn parse(image: &str) -> Result<Reference> {
+        let (name, reference) = match image.find('@') {
+            Some(at) => (&image[..at], image[at + 1..].to_owned()),
+            None => match image.rfind(':') {
+                Some(colon) if !image[colon..].contains('/') => {
+                    (&image[..colon], image[colon + 1..].to_owned())
+                }
+                _ => (image, "latest".to_owned()),
+            },
+        };
+        let (registry, repository) = match name.find('/') {
+            Some(slash)
+                if name[..slash].contains('.')
+                    || name[..slash].contains(':')
+                    || &name[..slash] == "localhost" =>
+            {
+                (&name[..slash], &name[slash + 1..])
+            }
+            _ => ("docker.io", name),
+        };
+        if repository.is_empty() || reference.is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "invalid image reference {}",
+                image
+            )));
+        }
+        let (registry, repository) = match registry {
+            "docker.io" | "index.docker.io" if !repository.contains('/') => {
+                (DOCKER_HUB, format!("library/{}", repository))
+            }
+            "docker.io" | "index.docker.io" => (DOCKER_HUB, repository.to_owned()),
+            registry => (registry, repository.to_owned()),
+        };
+        Ok(Reference {
+            registry: registry.to_owned(),
+            repository,
+            reference,
+        })
+    }
+
+    fn manifest_path(&self) -> String {
+        format!("/v2/{}/manifests/{}", self.repository, self.reference)
+    }
+}
+
+/// Registries on the local machine are usually served without TLS, as the
+/// daemon allows
+fn scheme(registry: &str) -> &'static str {
+    let host = registry.split(':').next().unwrap_or_default();
+    if host == "localhost" || host == "127.0.0.1" {
+        "http"
+    } else {
+        "https"
+    }
+}
+
+/// A `WWW-Authenticate` challenge, such as
+/// `Bearer realm="https://auth.docker.io/token",service="registry.docker.io"`
+#[derive(Debug, PartialEq)]
+struct Challenge {
+    scheme: String,
+    params: HashMap<String, String>,
+}
+
+impl Challenge {
+    fn parse(header: &str) -> Option<Challenge> {
+        let header = header.trim();
+        let (scheme, mut rest) = match header.find(' ') {
+            Some(space) => (&header[..space], header[space + 1..].trim_start()),
+            None => (header, ""),
+        };
+        let mut params = HashMap::new();
+        while !rest.is_empty() {
+            let eq = rest.find('=')?;
+            let key = rest[..eq].trim().to_lowercase();
+            rest = &rest[eq + 1..];
+            // scopes may hold commas, so quoted values run to their closing quote
+            let value = if rest.starts_with('"') {
+                let end = rest[1..].find('"')? + 1;
+                let value = &rest[1..end];
+                rest = &rest[end + 1..];
+                value
+            } else {
+                let end = rest.find(',').unwrap_or(rest.len());
+                let value = rest[..end].trim();
+                rest = &rest[end..];
+                value
+            };
+            params.insert(key, value.to_owned());
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        }
+        Some(Challenge {
+            scheme: scheme.to_owned(),
+            params,
+        })
+    }
+}
+
+/// An auth server's response, which names the token `access_token` when it's
+/// an OAuth2 server
+#[derive(Deserialize)]
+struct Token {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Errors {
+    errors: Vec<RegistryError>,
+}
+
+#[derive(Deserialize)]
+struct RegistryError {
+    message: String,
+}
+
+#[cfg(feature = "tls")]
+type Connector = hyper_openssl::HttpsConnector<crate::proxy::Connector>;
+
+#[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+type Connector = hyper_rustls::HttpsConnector<crate::proxy::Connector>;
+
+/// The transport `Registry::new` sends requests with
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+#[derive(Debug)]
+struct Https(hyper::Client<Connector>);
+
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+impl Https {
+    fn new() -> Result<Https> {
+        let mut http = hyper::client::HttpConnector::new(1);
+        http.enforce_http(false);
+        http.set_connect_timeout(Some(CONNECT_TIMEOUT));
+        let http = crate::proxy::connector(http, None);
+        let config = crate::TlsConfig::builder().build();
+        #[cfg(feature = "tls")]
+        let connector =
+            hyper_openssl::HttpsConnector::with_connector(http, config.openssl_connector()?)
+                .map_err(|e| Error::Tls(e.to_string()))?;
+        #[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+        let connector = hyper_rustls::HttpsConnector::from((http, config.rustls_config()?));
+        Ok(Https(hyper::Client::builder().build(connector)))
+    }
+}
+
+#[cfg(any(feature = "tls", feature = "tls-rustls"))]
+impl HttpTransport for Https {
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        endpoint.to_owned()
+    }
+
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> ResponseFuture {
+        Box::new(crate::transport::deadline(
+            self.0.request(req).map_err(Error::Hyper),
+            Some(REQUEST_TIMEOUT),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redirect, Challenge, Reference, Registry, MAX_REDIRECTS};
+    use crate::{builder::RegistryAuth, models::Manifest, transport::HttpTransport, Error};
+    use futures::{future, Future};
+    use hyper::{header, Body, Request, Response, StatusCode};
+    use std::sync::{Arc, Mutex};
+
+    const MANIFEST: &str = "https://registry-1.docker.io/v2/library/nginx/manifests/1.19";
+    const TOKEN: &str = "https://auth.docker.io/token?service=registry.docker.io&scope=repository%3Alibrary%2Fnginx%3Apull";
+
+    /// Docker Hub, as far as fetching a manifest goes, recording the uri and
+    /// `Authorization` header of each request
+    #[derive(Clone, Debug, Default)]
+    struct Hub(Arc<Mutex<Vec<String>>>);
+
+    impl HttpTransport for Hub {
+        fn uri(
+            &self,
+            endpoint: &str,
+        ) -> String {
+            endpoint.to_owned()
+        }
+
+        fn request(
+            &self,
+            req: Request<Body>,
+        ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+            let uri = req.uri().to_string();
+            let authorization = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .map_or("", |value| value.to_str().unwrap());
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", uri, authorization).trim_end().to_owned());
+            let res = match (uri.as_str(), authorization) {
+                (MANIFEST, "Bearer abc") => Response::new(Body::from(
+                    r#"{"schemaVersion":2,"config":{"digest":"sha256:c"},"layers":[]}"#,
+                )),
+                (MANIFEST, _) => Response::builder()
+                    .status(StatusCode::UNAUTHORIZED)
+                    .header(
+                        header::WWW_AUTHENTICATE,
+                        r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/nginx:pull""#,
+                    )
+                    .body(Body::from(r#"{"errors":[{"code":"UNAUTHORIZED","message":"authentication required"}]}"#))
+                    .unwrap(),
+                (TOKEN, _) => Response::new(Body::from(r#"{"token":"abc"}"#)),
+                _ => Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::empty())
+                    .unwrap(),
+            };
+            Box::new(future::ok(res))
+        }
+    }
+
+    #[test]
+    fn references() {
+        let parse = |image| Reference::parse(image).unwrap();
+        assert_eq!(
+            Reference {
+                registry: "registry-1.docker.io".to_owned(),
+                repository: "library/nginx".to_owned(),
+                reference: "latest".to_owned(),
+            },
+            parse("nginx")
+        );
+        assert_eq!(
+            "grafana/grafana",
+            parse("docker.io/grafana/grafana:7.3").repository
+        );
+        let ghcr = parse("ghcr.io/owner/app@sha256:abc");
+        assert_eq!(
+            ("ghcr.io", "owner/app", "sha256:abc"),
+            (
+                ghcr.registry.as_str(),
+                ghcr.repository.as_str(),
+                ghcr.reference.as_str()
+            )
+        );
+        let local = parse("localhost:5000/app");
+        assert_eq!(
+            ("localhost:5000", "latest"),
+            (local.registry.as_str(), local.reference.as_str())
+        );
+        assert!(Reference::parse("nginx:").is_err());
+    }
+
+    #[test]
+    fn challenges() {
+        let challenge = Challenge::parse(
+            r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:owner/app:pull,push""#,
+        )
+        .unwrap();
+        assert_eq!("Bearer", challenge.scheme);
+        assert_eq!("https://ghcr.io/token", challenge.params["realm"]);
+        assert_eq!("ghcr.io", challenge.params["service"]);
+        assert_eq!("repository:owner/app:pull,push", challenge.params["scope"]);
+
+        let basic = Challenge::parse(r#"Basic realm="Registry""#).unwrap();
+        assert_eq!("Basic", basic.scheme);
+        assert_eq!("Registry", basic.params["realm"]);
+    }
+
+    #[test]
+    fn anonymous_tokens_are_fetched_and_reused() {
+        let hub = Hub::default();
+        let registry = Registry::from_transport(hub.clone());
+
+        for _ in 0..2 {
+            match registry.manifest("nginx:1.19").wait().unwrap() {
+                Manifest::Image(manifest) => assert_eq!("sha256:c", manifest.config.digest),
+                manifest => panic!("unexpected manifest {:?}", manifest),
+            }
+        }
+        assert_eq!(
+            vec![
+                MANIFEST.to_owned(),
+                TOKEN.to_owned(),
+                format!("{} Bearer abc", MANIFEST),
+                format!("{} Bearer abc", MANIFEST),
+            ],
+            *hub.0.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn credentials_are_exchanged_for_tokens() {
+        let hub = Hub::default();
+        let auth = RegistryAuth::builder()
+            .username("user")
+            .password("secret")
+            .build();
+        let registry = Registry::from_transport(hub.clone()).with_auth(auth);
+
+        registry.manifest("nginx:1.19").wait().unwrap();
+        assert_eq!(
+            format!("{} Basic dXNlcjpzZWNyZXQ=", TOKEN),
+            hub.0.lock().unwrap()[1]
+        );
+    }
+
+    #[test]
+    fn redirects_are_resolved_against_the_request() {
+        let blob = "https://registry.example.com/v2/app/blobs/sha256:c";
+        assert_eq!(
+            "https://registry.example.com/storage/c?sig=x",
+            redirect(blob, "/storage/c?sig=x", 1).unwrap()
+        );
+        assert_eq!(
+            "https://cdn.example.com/c",
+            redirect(blob, "https://cdn.example.com/c", 1).unwrap()
+        );
+        assert!(redirect(blob, "http://cdn.example.com/c", 1).is_err());
+        assert!(redirect(blob, "/storage/c", 0).is_err());
+    }
+
+    /// A registry which redirects every request back to itself
+    #[derive(Clone, Debug, Default)]
+    struct Loop(Arc<Mutex<usize>>);
+
+    impl HttpTransport for Loop {
+        fn uri(
+            &self,
+            endpoint: &str,
+        ) -> String {
+            endpoint.to_owned()
+        }
+
+        fn request(
+            &self,
+            _: Request<Body>,
+        ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+            *self.0.lock().unwrap() += 1;
+            let res = Response::builder()
+                .status(StatusCode::TEMPORARY_REDIRECT)
+                .header(header::LOCATION, "/v2/library/nginx/manifests/1.19")
+                .body(Body::empty())
+                .unwrap();
+            Box::new(future::ok(res))
+        }
+    }
+
+    #[test]
+    fn redirect_loops_are_cut_short() {
+        let registry = Loop::default();
+        let error = Registry::from_transport(registry.clone())
+            .manifest("nginx:1.19")
+            .wait()
+            .unwrap_err();
+        match error {
+            Error::InvalidResponse(_) => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(1 + MAX_REDIRECTS, *registry.0.lock().unwrap());
+    }
+}
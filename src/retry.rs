@@ -0,0 +1,176 @@
+//! Retrying requests which failed for transient reasons
+
+use crate::Error;
+use futures::{
+    future::{self, Either, Loop},
+    Future,
+};
+use std::{
+    cmp,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, Instant},
+};
+use tokio::timer::Delay;
+
+/// How idempotent requests are retried after connection failures and
+/// server errors
+///
+/// Each retry waits a random duration of up to `initial_backoff * 2^n`,
+/// capped at `max_backoff`, before trying again.
+///
+/// ```no_run
+/// use shiplift::{retry::RetryPolicy, Docker};
+/// use std::time::Duration;
+///
+/// let docker = Docker::new()
+///     .with_retry(RetryPolicy::new(5).initial_backoff(Duration::from_millis(50)));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Makes at most `max_attempts` attempts at each request, including the first
+    pub fn new(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// The upper bound of the wait before the first retry. Defaults to 100ms
+    pub fn initial_backoff(
+        mut self,
+        backoff: Duration,
+    ) -> RetryPolicy {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// The upper bound of the wait before any retry. Defaults to 10s
+    pub fn max_backoff(
+        mut self,
+        backoff: Duration,
+    ) -> RetryPolicy {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// The longest wait before the given retry, starting from 0
+    fn backoff_ceiling(
+        &self,
+        retry: u32,
+    ) -> Duration {
+        let ceiling = self
+            .initial_backoff
+            .checked_mul(1 << cmp::min(retry, 31))
+            .unwrap_or(self.max_backoff);
+        cmp::min(ceiling, self.max_backoff)
+    }
+
+    /// A random wait before the given retry, spreading out retries from
+    /// many clients failing at once
+    fn backoff(
+        &self,
+        retry: u32,
+    ) -> Duration {
+        let ceiling = self.backoff_ceiling(retry);
+        let nanos = ceiling.as_secs() * 1_000_000_000 + u64::from(ceiling.subsec_nanos());
+        if nanos == 0 {
+            return ceiling;
+        }
+        // std has no rng, but each RandomState is randomly seeded
+        let jitter = RandomState::new().build_hasher().finish() % nanos;
+        Duration::from_nanos(jitter)
+    }
+
+    /// Runs the future made by `request` until it succeeds, fails with an error
+    /// which isn't worth retrying, or runs out of attempts
+    pub(crate) fn retry<F, R>(
+        self,
+        request: F,
+    ) -> impl Future<Item = R::Item, Error = Error>
+    where
+        F: Fn() -> R,
+        R: Future<Error = Error>,
+    {
+        future::loop_fn(0, move |attempt| {
+            let policy = self;
+            request().then(move |result| match result {
+                Err(ref e) if attempt + 1 < policy.max_attempts && is_transient(e) => {
+                    let retry = Instant::now() + policy.backoff(attempt);
+                    Either::A(
+                        Delay::new(retry)
+                            .then(move |_| Ok::<_, Error>(Loop::Continue(attempt + 1))),
+                    )
+                }
+                result => Either::B(future::result(result.map(Loop::Break))),
+            })
+        })
+    }
+}
+
+/// Whether an error may not happen again when a request is retried
+pub(crate) fn is_transient(error: &Error) -> bool {
+    match error.inner() {
+        Error::Hyper(e) => e.is_connect() || e.is_closed() || e.is_canceled(),
+        Error::IO(_) | Error::Timeout | Error::StreamIdle(_) => true,
+        Error::ServerError { .. } => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::Error;
+    use futures::{future, Future};
+    use hyper::{HeaderMap, StatusCode};
+    use std::{cell::Cell, time::Duration};
+
+    fn fault(code: StatusCode) -> Error {
+        Error::from_response(code, HeaderMap::new(), Default::default(), String::new())
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy::new(10)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1));
+        assert_eq!(Duration::from_millis(100), policy.backoff_ceiling(0));
+        assert_eq!(Duration::from_millis(400), policy.backoff_ceiling(2));
+        assert_eq!(Duration::from_secs(1), policy.backoff_ceiling(4));
+        assert_eq!(Duration::from_secs(1), policy.backoff_ceiling(100));
+        assert!(policy.backoff(2) <= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn retries_server_errors_until_out_of_attempts() {
+        let attempts = Cell::new(0);
+        let policy = RetryPolicy::new(3).initial_backoff(Duration::from_millis(0));
+        let result = tokio::runtime::current_thread::block_on_all(policy.retry(|| {
+            attempts.set(attempts.get() + 1);
+            future::err::<(), _>(fault(StatusCode::SERVICE_UNAVAILABLE))
+        }));
+        assert!(result.is_err());
+        assert_eq!(3, attempts.get());
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let attempts = Cell::new(0);
+        let result = RetryPolicy::new(3)
+            .retry(|| {
+                attempts.set(attempts.get() + 1);
+                future::err::<(), _>(fault(StatusCode::NOT_FOUND))
+            })
+            .wait();
+        assert!(result.is_err());
+        assert_eq!(1, attempts.get());
+    }
+}
@@ -1,71 +1,297 @@
+use crate::models::Change;
 use flate2::{write::GzEncoder, Compression};
+use futures::{Async, Poll, Stream};
 use std::{
+    collections::HashSet,
     fs::{self, File},
-    io::{self, Write},
-    path::{Path, MAIN_SEPARATOR},
+    io, mem,
+    path::{Path, PathBuf},
 };
-use tar::Builder;
-
-// todo: this is pretty involved. (re)factor this into its own crate
-pub fn dir<W>(
-    buf: W,
-    path: &str,
-) -> io::Result<()>
-where
-    W: Write,
-{
-    let mut archive = Builder::new(GzEncoder::new(buf, Compression::best()));
-    fn bundle<F>(
-        dir: &Path,
-        f: &mut F,
-        bundle_dir: bool,
-    ) -> io::Result<()>
-    where
-        F: FnMut(&Path) -> io::Result<()>,
-    {
-        if fs::metadata(dir)?.is_dir() {
-            if bundle_dir {
-                f(&dir)?;
-            }
-            for entry in fs::read_dir(dir)? {
-                let entry = entry?;
-                if fs::metadata(entry.path())?.is_dir() {
-                    bundle(&entry.path(), f, true)?;
-                } else {
-                    f(&entry.path().as_path())?;
+use tar::{Archive, Builder};
+
+/// How much of an archive is sent at once
+const CHUNK_SIZE: usize = 64 * 1024;
+/// The two empty blocks a tar archive ends with
+pub const END_OF_ARCHIVE: [u8; 1024] = [0; 1024];
+/// The kind of a `Change` to a path which has since been deleted
+const DELETED: u64 = 2;
+
+/// Streams a gzipped tarball of a directory as it's built.
+///
+/// The directory is read a chunk at a time as the stream is polled, inside
+/// `tokio_threadpool::blocking` so that reading it doesn't hold up other tasks
+/// on a threadpool. Dropping the stream stops the archiving.
+pub fn dir_stream(path: String) -> impl Stream<Item = Vec<u8>, Error = io::Error> {
+    DirStream {
+        path: PathBuf::from(path),
+        archiving: None,
+        done: false,
+    }
+}
+
+struct DirStream {
+    path: PathBuf,
+    /// Started on the first poll, so that creating the stream doesn't touch the disk
+    archiving: Option<Archiving>,
+    done: bool,
+}
+
+impl DirStream {
+    /// Archives entries until there's a chunk's worth of the archive to send,
+    /// or the archive is complete
+    fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.done {
+            return Ok(None);
+        }
+        if self.archiving.is_none() {
+            self.archiving = Some(Archiving::new(&self.path)?);
+        }
+        let archiving = self.archiving.as_mut().expect("archiving was just started");
+        while archiving.buffered() < CHUNK_SIZE {
+            match archiving.pending.pop() {
+                Some(path) => archiving.append(&path)?,
+                None => {
+                    self.done = true;
+                    let archive = self.archiving.take().expect("archiving is in progress");
+                    return archive.finish().map(Some);
                 }
             }
         }
-        Ok(())
+        Ok(Some(archiving.take()))
     }
+}
 
-    {
-        let base_path = Path::new(path).canonicalize()?;
-        // todo: don't unwrap
-        let mut base_path_str = base_path.to_str().unwrap().to_owned();
-        if let Some(last) = base_path_str.chars().last() {
-            if last != MAIN_SEPARATOR {
-                base_path_str.push(MAIN_SEPARATOR)
-            }
+impl Stream for DirStream {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Vec<u8>>, io::Error> {
+        let result = match tokio_threadpool::blocking(|| self.next_chunk()) {
+            Ok(Async::Ready(result)) => result,
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            // not running on a threadpool, e.g. on a current thread runtime,
+            // so the directory can only be read in place
+            Err(_) => self.next_chunk(),
+        };
+        if result.is_err() {
+            self.done = true;
+            self.archiving = None;
         }
+        result.map(Async::Ready)
+    }
+}
 
-        let mut append = |path: &Path| {
-            let canonical = path.canonicalize()?;
-            // todo: don't unwrap
-            let relativized = canonical
-                .to_str()
-                .unwrap()
-                .trim_start_matches(&base_path_str[..]);
-            if path.is_dir() {
-                archive.append_dir(Path::new(relativized), &canonical)?
-            } else {
-                archive.append_file(Path::new(relativized), &mut File::open(&canonical)?)?
-            }
-            Ok(())
+/// A gzipped tarball of a directory part way through being built
+struct Archiving {
+    builder: Builder<GzEncoder<Vec<u8>>>,
+    /// The canonical path of the directory, which entries are named relative to
+    base: PathBuf,
+    /// The paths still to be archived, the next one last
+    pending: Vec<PathBuf>,
+}
+
+impl Archiving {
+    fn new(path: &Path) -> io::Result<Archiving> {
+        let base = path.canonicalize()?;
+        let mut archiving = Archiving {
+            builder: Builder::new(GzEncoder::new(Vec::new(), Compression::best())),
+            base,
+            pending: Vec::new(),
         };
-        bundle(Path::new(path), &mut append, false)?;
+        if fs::metadata(path)?.is_dir() {
+            archiving.push_entries(path)?;
+        }
+        Ok(archiving)
+    }
+
+    /// Queues the entries of a directory, so they're archived in the order
+    /// they're listed
+    fn push_entries(
+        &mut self,
+        dir: &Path,
+    ) -> io::Result<()> {
+        let entries = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        self.pending.extend(entries.into_iter().rev());
+        Ok(())
+    }
+
+    fn append(
+        &mut self,
+        path: &Path,
+    ) -> io::Result<()> {
+        let canonical = path.canonicalize()?;
+        let relative = canonical.strip_prefix(&self.base).unwrap_or(&canonical);
+        let name = relative.to_str().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not valid UTF-8", relative.display()),
+            )
+        })?;
+        if fs::metadata(path)?.is_dir() {
+            self.builder.append_dir(name, &canonical)?;
+            self.push_entries(path)
+        } else {
+            self.builder.append_file(name, &mut File::open(&canonical)?)
+        }
     }
-    archive.finish()?;
 
-    Ok(())
+    /// How much of the archive has been built but not yet taken
+    fn buffered(&self) -> usize {
+        self.builder.get_ref().get_ref().len()
+    }
+
+    /// Takes what's been built of the archive so far
+    fn take(&mut self) -> Vec<u8> {
+        mem::take(self.builder.get_mut().get_mut())
+    }
+
+    /// Ends the archive, returning what remains of it
+    fn finish(self) -> io::Result<Vec<u8>> {
+        self.builder.into_inner()?.finish()
+    }
+}
+
+/// The paths of a container's changes which hold added or modified content,
+/// leaving out directories which are only listed as changed because of the
+/// changes below them
+pub fn changed_paths(changes: &[Change]) -> Vec<&str> {
+    let parents = changes
+        .iter()
+        .flat_map(|change| Path::new(&change.path).ancestors().skip(1))
+        .collect::<HashSet<_>>();
+    changes
+        .iter()
+        .filter(|change| change.kind != DELETED && !parents.contains(Path::new(&change.path)))
+        .map(|change| change.path.as_str())
+        .collect()
+}
+
+/// Renames the entries of an archive the daemon sent for a path, which are
+/// relative to the path's `parent`, to their paths from the root. The archive
+/// is returned without its end, so that several can be joined into one
+pub fn rebase(
+    archive: &[u8],
+    parent: &Path,
+) -> io::Result<Vec<u8>> {
+    let mut rebased = Builder::new(Vec::with_capacity(archive.len()));
+    for entry in Archive::new(archive).entries()? {
+        let mut entry = entry?;
+        let path = parent.join(entry.path()?);
+        let path = path.strip_prefix("/").unwrap_or(&path).to_owned();
+        let mut header = entry.header().clone();
+        rebased.append_data(&mut header, path, &mut entry)?;
+    }
+    let mut rebased = rebased.into_inner()?;
+    rebased.truncate(rebased.len() - END_OF_ARCHIVE.len());
+    Ok(rebased)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_paths, dir_stream, rebase, END_OF_ARCHIVE};
+    use crate::models::Change;
+    use flate2::read::GzDecoder;
+    use futures::{Future, Stream};
+    use std::{fs, io, path::Path};
+    use tar::{Archive, Builder, Header};
+
+    #[test]
+    fn streams_gzipped_archives() {
+        let archive = dir_stream("fixtures".to_owned()).concat2().wait().unwrap();
+        assert_eq!(&[0x1f, 0x8b], &archive[..2]);
+    }
+
+    #[test]
+    fn missing_directories_are_errors() {
+        assert!(dir_stream("no/such/dir".to_owned())
+            .concat2()
+            .wait()
+            .is_err());
+    }
+
+    #[test]
+    fn archives_hold_every_file() {
+        let archive = dir_stream("fixtures".to_owned()).concat2().wait().unwrap();
+        let entries = Archive::new(GzDecoder::new(&archive[..]))
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().into_owned())
+            .collect::<Vec<_>>();
+        for entry in fs::read_dir("fixtures").unwrap() {
+            let name = entry.unwrap().file_name();
+            assert!(entries.iter().any(|path| path.starts_with(&name)));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_are_errors() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = std::env::temp_dir().join(format!("shiplift-tarball-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(OsStr::from_bytes(b"bad\xff")), "").unwrap();
+        let result = dir_stream(dir.to_str().unwrap().to_owned())
+            .concat2()
+            .wait();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn only_paths_with_content_are_exported() {
+        let changes = [
+            (0, "/etc"),
+            (0, "/etc/hosts"),
+            (1, "/var/lib/app"),
+            (1, "/var/lib/app/data.db"),
+            (1, "/tmp/empty"),
+            (0, "/root"),
+            (2, "/root/.bash_history"),
+            (2, "/srv/old"),
+        ]
+        .iter()
+        .map(|&(kind, path)| Change {
+            kind,
+            path: path.to_owned(),
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["/etc/hosts", "/var/lib/app/data.db", "/tmp/empty"],
+            changed_paths(&changes)
+        );
+    }
+
+    #[test]
+    fn archives_are_rebased_onto_their_parent() {
+        let mut archive = Builder::new(Vec::new());
+        for (path, data) in &[("app/config.toml", "a = 1"), ("app/logs/out.log", "ok")] {
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            archive
+                .append_data(&mut header, path, data.as_bytes())
+                .unwrap();
+        }
+        let archive = archive.into_inner().unwrap();
+
+        let mut rebased = rebase(&archive, Path::new("/etc")).unwrap();
+        assert_ne!(&END_OF_ARCHIVE[..], &rebased[rebased.len() - 1024..]);
+        rebased.extend_from_slice(&END_OF_ARCHIVE);
+        let entries = Archive::new(&rebased[..])
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["etc/app/config.toml", "etc/app/logs/out.log"], entries);
+    }
 }
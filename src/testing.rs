@@ -0,0 +1,736 @@
+//! Utilities for testing code which uses shiplift without a docker daemon
+
+#[cfg(feature = "blocking")]
+use crate::{blocking, ContainerOptions, PullOptions, RmContainerOptions};
+use crate::{
+    transport::{HttpTransport, Transport},
+    Docker, Error, Result,
+};
+use futures::{future, stream, Future, Stream};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+#[cfg(feature = "blocking")]
+use std::{
+    collections::HashMap,
+    net::TcpStream,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use url::form_urlencoded;
+
+/// A transport which serves canned responses instead of talking to a daemon
+///
+/// Requests are answered by the first route registered for their method and
+/// path whose query parameters they include. Requests matching no route get a
+/// `404` response.
+///
+/// ```no_run
+/// use hyper::{Method, StatusCode};
+/// use shiplift::{testing::MockTransport, Docker};
+///
+/// let mock = MockTransport::new();
+/// mock.on(Method::GET, "/containers/json")
+///     .query("all", "true")
+///     .respond(StatusCode::OK, "[]");
+/// let docker = Docker::from_transport(mock.clone());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    routes: Arc<Mutex<Vec<MockRoute>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+/// A response served for matching requests
+#[derive(Clone, Debug)]
+pub struct MockRoute {
+    method: Method,
+    path: String,
+    query: Vec<(String, String)>,
+    status: StatusCode,
+    chunks: Vec<Vec<u8>>,
+    routes: Arc<Mutex<Vec<MockRoute>>>,
+}
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Starts a route for requests with the given method and path, such as
+    /// `/containers/json`. The route is added once it's given a response
+    pub fn on(
+        &self,
+        method: Method,
+        path: &str,
+    ) -> MockRoute {
+        MockRoute {
+            method,
+            path: path.to_owned(),
+            query: Vec::new(),
+            status: StatusCode::OK,
+            chunks: Vec::new(),
+            routes: self.routes.clone(),
+        }
+    }
+
+    /// Serves the responses recorded in a fixtures file written by a
+    /// `RecordingTransport`
+    pub fn replay<P>(path: P) -> Result<MockTransport>
+    where
+        P: AsRef<Path>,
+    {
+        let mock = MockTransport::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let fixture: Fixture = serde_json::from_str(line)?;
+            let method = fixture
+                .method
+                .parse::<Method>()
+                .map_err(http::Error::from)?;
+            let status = StatusCode::from_u16(fixture.status).map_err(http::Error::from)?;
+            let mut parts = fixture.uri.splitn(2, '?');
+            let mut route = mock.on(method, parts.next().unwrap_or("/"));
+            if let Some(query) = parts.next() {
+                for (key, value) in form_urlencoded::parse(query.as_bytes()).into_owned() {
+                    route = route.query(key, value);
+                }
+            }
+            route.respond(status, fixture.body);
+        }
+        Ok(mock)
+    }
+
+    /// The method, path and query of every request received so far,
+    /// e.g. `GET /containers/json?all=true`
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn response(
+        &self,
+        method: &Method,
+        path: &str,
+        query: &[(String, String)],
+    ) -> Response<Body> {
+        let routes = self.routes.lock().unwrap();
+        let route = routes.iter().find(|route| {
+            route.method == *method
+                && route.path == path
+                && route.query.iter().all(|pair| query.contains(pair))
+        });
+        match route {
+            Some(route) => {
+                let chunks = stream::iter_ok::<_, io::Error>(route.chunks.clone());
+                let mut response = Response::new(Body::wrap_stream(chunks));
+                *response.status_mut() = route.status;
+                response
+            }
+            None => {
+                let message = json!({
+                    "message": format!("no mock response for {} {}", method, path)
+                });
+                let mut response = Response::new(Body::from(message.to_string()));
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                response
+            }
+        }
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        format!("http://localhost{}", endpoint)
+    }
+
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+        let uri = req.uri();
+        let query = uri
+            .query()
+            .map(|q| form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+            .unwrap_or_else(Vec::new);
+        self.requests.lock().unwrap().push(format!(
+            "{} {}",
+            req.method(),
+            uri.path_and_query().map(|p| p.as_str()).unwrap_or("/")
+        ));
+        Box::new(future::ok(self.response(req.method(), uri.path(), &query)))
+    }
+}
+
+impl MockRoute {
+    /// Only match requests with the given query parameter
+    pub fn query<K, V>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> MockRoute
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.query.push((key.into(), value.into()));
+        self
+    }
+
+    /// Responds to matching requests with the given status and body
+    pub fn respond<B>(
+        self,
+        status: StatusCode,
+        body: B,
+    ) where
+        B: Into<Vec<u8>>,
+    {
+        self.respond_chunks(status, vec![body])
+    }
+
+    /// Responds to matching requests with the given status and a body
+    /// streamed in the given chunks, as with logs or events
+    pub fn respond_chunks<B>(
+        mut self,
+        status: StatusCode,
+        chunks: Vec<B>,
+    ) where
+        B: Into<Vec<u8>>,
+    {
+        self.status = status;
+        self.chunks = chunks.into_iter().map(Into::into).collect();
+        let routes = self.routes.clone();
+        routes.lock().unwrap().push(self);
+    }
+}
+
+/// A request made to the docker daemon along with its response
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    /// The path and query of the request
+    pub uri: String,
+    pub status: u16,
+    pub body: String,
+}
+
+/// A transport which writes every request made through another transport,
+/// and the response it got, to a fixtures file which `MockTransport::replay`
+/// can serve back later
+///
+/// Responses are buffered in full before being returned, so streams such as
+/// events must end for their requests to be recorded, and attaching to
+/// containers isn't supported.
+///
+/// ```no_run
+/// use shiplift::{testing, Docker};
+///
+/// let docker = testing::record(&Docker::new(), "fixtures/containers.jsonl").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    fixtures: Arc<Mutex<File>>,
+}
+
+impl<T> RecordingTransport<T>
+where
+    T: HttpTransport,
+{
+    /// Records requests made through `inner` to a new fixtures file at `path`,
+    /// replacing any existing file
+    pub fn new<P>(
+        inner: T,
+        path: P,
+    ) -> Result<RecordingTransport<T>>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(RecordingTransport {
+            inner,
+            fixtures: Arc::new(Mutex::new(File::create(path)?)),
+        })
+    }
+}
+
+impl<T> HttpTransport for RecordingTransport<T>
+where
+    T: HttpTransport,
+{
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        self.inner.uri(endpoint)
+    }
+
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+        let method = req.method().to_string();
+        let uri = req
+            .uri()
+            .path_and_query()
+            .map(|p| p.as_str().to_owned())
+            .unwrap_or_else(|| "/".to_owned());
+        let fixtures = self.fixtures.clone();
+        Box::new(self.inner.request(req).and_then(move |response| {
+            let (parts, body) = response.into_parts();
+            body.concat2().map_err(Error::Hyper).and_then(move |body| {
+                let fixture = Fixture {
+                    method,
+                    uri,
+                    status: parts.status.as_u16(),
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                };
+                let mut line = serde_json::to_string(&fixture)?;
+                line.push('\n');
+                fixtures.lock().unwrap().write_all(line.as_bytes())?;
+                Ok(Response::from_parts(parts, Body::from(body)))
+            })
+        }))
+    }
+}
+
+/// Returns a docker instance which sends requests the same way as `docker`,
+/// recording them to a new fixtures file at `path`
+pub fn record<P>(
+    docker: &Docker,
+    path: P,
+) -> Result<Docker>
+where
+    P: AsRef<Path>,
+{
+    // the recording transport is outermost, so it times requests out
+    let mut inner = docker.transport.clone();
+    inner.set_timeout(None);
+    let mut recording = docker.clone();
+    recording.transport = Transport::Custom {
+        transport: Arc::new(RecordingTransport::new(inner, path)?),
+        timeout: docker.transport.timeout(),
+        headers: docker.transport.default_headers().clone(),
+    };
+    Ok(recording)
+}
+
+/// What a `TestContainer` waits for once started, before it's handed over
+#[derive(Clone, Debug, PartialEq)]
+pub enum WaitFor {
+    /// Nothing beyond the container starting
+    Nothing,
+    /// A tcp connection to the host port published for a container port
+    Port(u32),
+    /// The container's healthcheck passing
+    Healthy,
+}
+
+/// A container run for the length of an integration test, which is removed
+/// along with its volumes when dropped
+///
+/// Containers are given unique names and have their ports published to random
+/// ports on the host, so tests can run concurrently. Published ports are
+/// assumed to be reachable on localhost. Requires the `blocking` feature.
+///
+/// ```no_run
+/// use shiplift::{
+///     blocking,
+///     testing::{TestContainer, WaitFor},
+/// };
+///
+/// let docker = blocking::Docker::new().unwrap();
+/// let redis = TestContainer::builder("redis:6")
+///     .port(6379)
+///     .wait_for(WaitFor::Port(6379))
+///     .start(&docker)
+///     .unwrap();
+/// let port = redis.host_port(6379).unwrap();
+/// ```
+#[cfg(feature = "blocking")]
+pub struct TestContainer<'a> {
+    docker: &'a blocking::Docker,
+    id: String,
+    name: String,
+    ports: HashMap<u32, u16>,
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> TestContainer<'a> {
+    /// return a new instance of a builder for a container of `image`
+    pub fn builder(image: &str) -> TestContainerBuilder {
+        TestContainerBuilder {
+            image: image.to_owned(),
+            ports: Vec::new(),
+            env: Vec::new(),
+            cmd: Vec::new(),
+            wait_for: WaitFor::Nothing,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The host port a container port was published to
+    pub fn host_port(
+        &self,
+        port: u32,
+    ) -> Option<u16> {
+        self.ports.get(&port).cloned()
+    }
+
+    /// An interface to the container, for anything else a test needs of it
+    pub fn container(&self) -> crate::Container<'a, 'static> {
+        crate::Container::new(self.docker.client(), self.id.clone())
+    }
+
+    fn wait_for(
+        &self,
+        wait_for: &WaitFor,
+        timeout: Duration,
+    ) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let details = self.docker.block_on(self.container().inspect())?;
+            if !details.state.running {
+                return Err(Error::InvalidResponse(format!(
+                    "test container {} exited with {}",
+                    self.name, details.state.exit_code
+                )));
+            }
+            let ready = match wait_for {
+                WaitFor::Nothing => true,
+                WaitFor::Port(port) => match self.host_port(*port) {
+                    Some(host_port) => {
+                        let addr = ([127, 0, 0, 1], host_port).into();
+                        TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok()
+                    }
+                    None => {
+                        return Err(Error::InvalidInput(format!(
+                            "port {} of test container {} isn't published",
+                            port, self.name
+                        )))
+                    }
+                },
+                WaitFor::Healthy => match details.state.health {
+                    Some(ref health) if health.status == "unhealthy" => {
+                        return Err(Error::InvalidResponse(format!(
+                            "test container {} is unhealthy",
+                            self.name
+                        )))
+                    }
+                    Some(ref health) => health.status == "healthy",
+                    None => {
+                        return Err(Error::InvalidInput(format!(
+                            "test container {} has no healthcheck",
+                            self.name
+                        )))
+                    }
+                },
+            };
+            if ready {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> Drop for TestContainer<'a> {
+    fn drop(&mut self) {
+        let opts = RmContainerOptions::builder()
+            .force(true)
+            .volumes(true)
+            .build();
+        if let Err(e) = self.docker.block_on(self.container().remove(opts)) {
+            log::warn!("failed to remove test container {}: {}", self.name, e);
+        }
+    }
+}
+
+/// Configures and starts a `TestContainer`
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug)]
+pub struct TestContainerBuilder {
+    image: String,
+    ports: Vec<u32>,
+    env: Vec<String>,
+    cmd: Vec<String>,
+    wait_for: WaitFor,
+    timeout: Duration,
+}
+
+#[cfg(feature = "blocking")]
+impl TestContainerBuilder {
+    /// Publishes a tcp port of the container to a random port on the host
+    pub fn port(
+        &mut self,
+        port: u32,
+    ) -> &mut Self {
+        self.ports.push(port);
+        self
+    }
+
+    pub fn env(
+        &mut self,
+        envs: Vec<&str>,
+    ) -> &mut Self {
+        self.env.extend(envs.into_iter().map(String::from));
+        self
+    }
+
+    pub fn cmd(
+        &mut self,
+        cmds: Vec<&str>,
+    ) -> &mut Self {
+        self.cmd = cmds.into_iter().map(String::from).collect();
+        self
+    }
+
+    /// What to wait for before the container is handed over. Nothing by default
+    pub fn wait_for(
+        &mut self,
+        wait_for: WaitFor,
+    ) -> &mut Self {
+        self.wait_for = wait_for;
+        self
+    }
+
+    /// How long to wait for the container to become ready. A minute by default
+    pub fn timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Pulls the image if it's missing, then creates and starts the container,
+    /// returning once it's ready. The container is removed if it fails to start
+    pub fn start<'a>(
+        &self,
+        docker: &'a blocking::Docker,
+    ) -> Result<TestContainer<'a>> {
+        let client = docker.client();
+        if let Err(e) = docker.block_on(client.images().get(&self.image).inspect()) {
+            match e.inner() {
+                Error::NotFound { .. } => self.pull(docker)?,
+                _ => return Err(e),
+            }
+        }
+
+        let name = unique_name();
+        let mut opts = ContainerOptions::builder(&self.image);
+        opts.name(&name);
+        if !self.env.is_empty() {
+            opts.env(self.env.iter().map(String::as_str).collect());
+        }
+        if !self.cmd.is_empty() {
+            opts.cmd(self.cmd.iter().map(String::as_str).collect());
+        }
+        for port in &self.ports {
+            // a host port of 0 has the daemon pick a free one
+            opts.expose(*port, "tcp", 0);
+        }
+        let info = docker.block_on(client.containers().create(&opts.build()))?;
+
+        // from here on, dropping the container cleans up after a failure
+        let mut container = TestContainer {
+            docker,
+            id: info.id,
+            name,
+            ports: HashMap::new(),
+        };
+        docker.block_on(container.container().start())?;
+        let details = docker.block_on(container.container().inspect())?;
+        let published = details.network_settings.ports.unwrap_or_default();
+        for port in &self.ports {
+            let host_port = published
+                .get(&format!("{}/tcp", port))
+                .and_then(|bindings| bindings.as_ref())
+                .and_then(|bindings| bindings.first())
+                .and_then(|binding| binding.get("HostPort"))
+                .and_then(|host_port| host_port.parse().ok());
+            if let Some(host_port) = host_port {
+                container.ports.insert(*port, host_port);
+            }
+        }
+        container.wait_for(&self.wait_for, self.timeout)?;
+        Ok(container)
+    }
+
+    fn pull(
+        &self,
+        docker: &blocking::Docker,
+    ) -> Result<()> {
+        let opts = PullOptions::builder().image(self.image.as_str()).build();
+        let progress = docker.block_on(docker.client().images().pull(&opts).collect())?;
+        // pull failures are reported within the progress, rather than by status
+        match progress.iter().find_map(|p| p.get("error")) {
+            Some(error) => Err(Error::InvalidResponse(format!(
+                "failed to pull {}: {}",
+                self.image, error
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A container name which won't clash with those of other tests, or other runs
+#[cfg(feature = "blocking")]
+fn unique_name() -> String {
+    static CONTAINERS: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    format!(
+        "shiplift-test-{}-{:08x}-{}",
+        process::id(),
+        nanos,
+        CONTAINERS.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MockTransport, RecordingTransport};
+    use crate::{builder::ContainerListOptions, codec::StreamLimits, Docker, Error};
+    use futures::Stream;
+    use hyper::{Method, StatusCode};
+    #[cfg(feature = "blocking")]
+    use std::collections::HashSet;
+    use tokio::runtime::current_thread::block_on_all;
+
+    #[test]
+    fn routes_on_method_path_and_query() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/containers/json")
+            .query("all", "true")
+            .respond(StatusCode::OK, "[]");
+        let docker = Docker::from_transport(mock.clone());
+
+        let containers = block_on_all(
+            docker
+                .containers()
+                .list(&ContainerListOptions::builder().all().build()),
+        )
+        .unwrap();
+        assert!(containers.is_empty());
+
+        match block_on_all(docker.containers().list(&Default::default())) {
+            Err(e) => match e.inner() {
+                Error::NotFound { .. } => (),
+                _ => panic!("unexpected error {}", e),
+            },
+            Ok(containers) => panic!("unexpected response {:?}", containers),
+        }
+        assert_eq!(
+            vec!["GET /containers/json?all=true", "GET /containers/json"],
+            mock.requests()
+        );
+    }
+
+    #[test]
+    fn streams_chunks() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/events").respond_chunks(
+            StatusCode::OK,
+            vec![
+                r#"{"Type":"container","Action":"start","Actor":{"ID":"a","Attributes":{}},"time":1,"timeNano":1}"#,
+                "\n",
+                r#"{"Type":"image","Action":"pull","Actor":{"ID":"b","Attributes":{}},"time":2,"timeNano":2}"#,
+                "\n",
+            ],
+        );
+        let docker = Docker::from_transport(mock);
+
+        let events = block_on_all(docker.events(&Default::default()).collect()).unwrap();
+        assert_eq!(
+            vec!["start", "pull"],
+            events.iter().map(|e| e.action.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_container_names_are_unique() {
+        let names = (0..100)
+            .map(|_| super::unique_name())
+            .collect::<HashSet<_>>();
+        assert_eq!(100, names.len());
+        assert!(names.iter().all(|name| name.starts_with("shiplift-test-")));
+    }
+
+    #[test]
+    fn recording_keeps_the_stream_limits() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/events").respond(
+            StatusCode::OK,
+            r#"{"Type":"container","Action":"start","time":1,"timeNano":1}"#,
+        );
+        let docker = Docker::builder()
+            .transport(mock)
+            .stream_limits(StreamLimits::default().max_line_length(16))
+            .build()
+            .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("shiplift-limits-{}.jsonl", std::process::id()));
+
+        let recording = super::record(&docker, &path).unwrap();
+        let result = block_on_all(recording.events(&Default::default()).collect());
+        std::fs::remove_file(&path).unwrap();
+        match result {
+            Err(Error::TooLong(_)) => (),
+            other => panic!("expected the line to be too long, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn replays_recordings() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        mock.on(Method::GET, "/containers/json")
+            .query("all", "true")
+            .respond(StatusCode::OK, "[]");
+        let path = std::env::temp_dir().join(format!("shiplift-{}.jsonl", std::process::id()));
+
+        let docker = Docker::from_transport(RecordingTransport::new(mock, &path).unwrap());
+        block_on_all(docker.ping()).unwrap();
+        block_on_all(
+            docker
+                .containers()
+                .list(&ContainerListOptions::builder().all().build()),
+        )
+        .unwrap();
+
+        let docker = Docker::from_transport(MockTransport::replay(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("OK", block_on_all(docker.ping()).unwrap());
+        assert!(block_on_all(
+            docker
+                .containers()
+                .list(&ContainerListOptions::builder().all().build())
+        )
+        .unwrap()
+        .is_empty());
+    }
+}
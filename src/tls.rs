@@ -0,0 +1,400 @@
+//! TLS configuration for docker hosts listening over tcp
+
+use crate::{Error, Result};
+#[cfg(feature = "tls")]
+use openssl::{
+    pkcs12::Pkcs12,
+    pkey::PKey,
+    ssl::{SslConnector, SslConnectorBuilder, SslMethod, SslVerifyMode},
+    x509::X509,
+};
+#[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+use rustls::{internal::pemfile, Certificate, RootCertStore, ServerCertVerified, TLSError};
+#[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+use std::sync::Arc;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where a certificate, key or PKCS#12 bundle is read from
+#[derive(Clone, Debug)]
+enum Pem {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl Pem {
+    fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            Pem::File(path) => Ok(fs::read(path)?),
+            Pem::Bytes(bytes) => Ok(bytes.clone()),
+        }
+    }
+}
+
+/// Client certificates and trust settings used to connect to a docker
+/// daemon over TLS
+///
+/// ```no_run
+/// use shiplift::{tls::TlsConfig, Docker};
+///
+/// let config = TlsConfig::builder()
+///     .ca_file("certs/ca.pem")
+///     .cert_file("certs/cert.pem")
+///     .key_file("certs/key.pem")
+///     .build();
+/// let docker = Docker::tls("tcp://docker.example.com:2376".parse().unwrap(), &config);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    ca: Option<Pem>,
+    cert: Option<Pem>,
+    key: Option<Pem>,
+    key_password: Option<String>,
+    /// A PKCS#12 bundle of the client certificate and key, and its password
+    identity: Option<(Pem, String)>,
+    verify: bool,
+    accept_invalid_certs: bool,
+    server_name: Option<String>,
+}
+
+impl TlsConfig {
+    /// return a new instance of a builder for tls configuration
+    pub fn builder() -> TlsConfigBuilder {
+        TlsConfigBuilder::default()
+    }
+
+    /// Reads `ca.pem`, `cert.pem` and `key.pem` from a directory, the layout
+    /// expected by the docker CLI in `DOCKER_CERT_PATH`. Any of them which
+    /// don't exist are left unset
+    pub fn from_cert_path<P>(
+        path: P,
+        verify: bool,
+    ) -> TlsConfig
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut config = TlsConfig::builder();
+        config.verify(verify);
+        let ca = path.join("ca.pem");
+        if ca.is_file() {
+            config.ca_file(ca);
+        }
+        let cert = path.join("cert.pem");
+        if cert.is_file() {
+            config.cert_file(cert);
+        }
+        let key = path.join("key.pem");
+        if key.is_file() {
+            config.key_file(key);
+        }
+        config.build()
+    }
+
+    #[cfg(feature = "tls")]
+    pub(crate) fn openssl_connector(&self) -> Result<SslConnectorBuilder> {
+        let mut connector = SslConnector::builder(SslMethod::tls()).map_err(tls_error)?;
+        connector.set_cipher_list("DEFAULT").map_err(tls_error)?;
+        if let Some(ref cert) = self.cert {
+            let cert = X509::from_pem(&cert.read()?).map_err(tls_error)?;
+            connector.set_certificate(&cert).map_err(tls_error)?;
+        }
+        if let Some(ref key) = self.key {
+            let key = match self.key_password {
+                Some(ref password) => {
+                    PKey::private_key_from_pem_passphrase(&key.read()?, password.as_bytes())
+                }
+                None => PKey::private_key_from_pem(&key.read()?),
+            }
+            .map_err(tls_error)?;
+            connector.set_private_key(&key).map_err(tls_error)?;
+        }
+        if let Some((ref bundle, ref password)) = self.identity {
+            let identity = Pkcs12::from_der(&bundle.read()?)
+                .and_then(|bundle| bundle.parse(password))
+                .map_err(tls_error)?;
+            connector
+                .set_certificate(&identity.cert)
+                .map_err(tls_error)?;
+            connector
+                .set_private_key(&identity.pkey)
+                .map_err(tls_error)?;
+            if let Some(chain) = identity.chain {
+                for cert in chain {
+                    connector.add_extra_chain_cert(cert).map_err(tls_error)?;
+                }
+            }
+        }
+        if self.accept_invalid_certs {
+            connector.set_verify(SslVerifyMode::NONE);
+        } else if self.verify {
+            if let Some(ref ca) = self.ca {
+                for ca in X509::stack_from_pem(&ca.read()?).map_err(tls_error)? {
+                    connector.cert_store_mut().add_cert(ca).map_err(tls_error)?;
+                }
+            }
+        }
+        Ok(connector)
+    }
+
+    /// The name to verify the daemon's certificate against, in place of the
+    /// host it's connected to
+    #[cfg(feature = "tls")]
+    pub(crate) fn server_name(&self) -> Option<&str> {
+        self.server_name.as_ref().map(String::as_str)
+    }
+
+    #[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+    pub(crate) fn rustls_config(&self) -> Result<rustls::ClientConfig> {
+        if self.key_password.is_some() || self.identity.is_some() {
+            return Err(Error::Tls(
+                "encrypted keys and PKCS#12 bundles require the tls feature".into(),
+            ));
+        }
+        if self.server_name.is_some() {
+            return Err(Error::Tls(
+                "overriding the server name requires the tls feature".into(),
+            ));
+        }
+        let mut config = rustls::ClientConfig::new();
+        if self.accept_invalid_certs {
+            config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(AcceptInvalidCerts));
+        }
+        if let (Some(cert), Some(key)) = (&self.cert, &self.key) {
+            let cert_chain = pemfile::certs(&mut cert.read()?.as_slice())
+                .map_err(|_| Error::Tls("invalid client certificate".into()))?;
+            let key = key.read()?;
+            let key = pemfile::pkcs8_private_keys(&mut key.as_slice())
+                .ok()
+                .and_then(|mut keys| keys.pop())
+                .or_else(|| {
+                    pemfile::rsa_private_keys(&mut key.as_slice())
+                        .ok()
+                        .and_then(|mut keys| keys.pop())
+                })
+                .ok_or_else(|| Error::Tls("invalid client key".into()))?;
+            config.set_single_client_cert(cert_chain, key);
+        }
+        match self.ca {
+            Some(ref ca) if self.verify => {
+                config
+                    .root_store
+                    .add_pem_file(&mut ca.read()?.as_slice())
+                    .map_err(|_| Error::Tls("invalid CA certificate".into()))?;
+            }
+            _ => config
+                .root_store
+                .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS),
+        }
+        Ok(config)
+    }
+}
+
+/// Accepts any certificate the daemon presents
+#[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+struct AcceptInvalidCerts;
+
+#[cfg(all(feature = "tls-rustls", not(feature = "tls")))]
+impl rustls::ServerCertVerifier for AcceptInvalidCerts {
+    fn verify_server_cert(
+        &self,
+        _: &RootCertStore,
+        _: &[Certificate],
+        _: webpki::DNSNameRef,
+        _: &[u8],
+    ) -> std::result::Result<ServerCertVerified, TLSError> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(feature = "tls")]
+fn tls_error<E>(err: E) -> Error
+where
+    E: std::fmt::Display,
+{
+    Error::Tls(err.to_string())
+}
+
+#[derive(Default)]
+pub struct TlsConfigBuilder {
+    ca: Option<Pem>,
+    cert: Option<Pem>,
+    key: Option<Pem>,
+    key_password: Option<String>,
+    identity: Option<(Pem, String)>,
+    verify: Option<bool>,
+    accept_invalid_certs: bool,
+    server_name: Option<String>,
+}
+
+impl TlsConfigBuilder {
+    /// PEM file of the certificate authority used to verify the daemon
+    pub fn ca_file<P>(
+        &mut self,
+        path: P,
+    ) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.ca = Some(Pem::File(path.into()));
+        self
+    }
+
+    /// PEM encoded certificate authority used to verify the daemon
+    pub fn ca_pem<B>(
+        &mut self,
+        pem: B,
+    ) -> &mut Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.ca = Some(Pem::Bytes(pem.into()));
+        self
+    }
+
+    /// PEM file of the client certificate
+    pub fn cert_file<P>(
+        &mut self,
+        path: P,
+    ) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.cert = Some(Pem::File(path.into()));
+        self
+    }
+
+    /// PEM encoded client certificate
+    pub fn cert_pem<B>(
+        &mut self,
+        pem: B,
+    ) -> &mut Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.cert = Some(Pem::Bytes(pem.into()));
+        self
+    }
+
+    /// PEM file of the client certificate's private key
+    pub fn key_file<P>(
+        &mut self,
+        path: P,
+    ) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.key = Some(Pem::File(path.into()));
+        self
+    }
+
+    /// PEM encoded private key of the client certificate
+    pub fn key_pem<B>(
+        &mut self,
+        pem: B,
+    ) -> &mut Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.key = Some(Pem::Bytes(pem.into()));
+        self
+    }
+
+    /// Password of an encrypted private key. Only supported with the `tls` feature
+    pub fn key_password<S>(
+        &mut self,
+        password: S,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.key_password = Some(password.into());
+        self
+    }
+
+    /// PKCS#12 (`.pfx` or `.p12`) file holding the client certificate and its
+    /// private key, in place of `cert_file` and `key_file`. Only supported with
+    /// the `tls` feature
+    pub fn pkcs12_file<P, S>(
+        &mut self,
+        path: P,
+        password: S,
+    ) -> &mut Self
+    where
+        P: Into<PathBuf>,
+        S: Into<String>,
+    {
+        self.identity = Some((Pem::File(path.into()), password.into()));
+        self
+    }
+
+    /// DER encoded PKCS#12 bundle holding the client certificate and its
+    /// private key. Only supported with the `tls` feature
+    pub fn pkcs12_der<B, S>(
+        &mut self,
+        der: B,
+        password: S,
+    ) -> &mut Self
+    where
+        B: Into<Vec<u8>>,
+        S: Into<String>,
+    {
+        self.identity = Some((Pem::Bytes(der.into()), password.into()));
+        self
+    }
+
+    /// Whether to verify the daemon's certificate against the configured
+    /// certificate authority. Defaults to `true`; when disabled the system's
+    /// trusted roots are used instead, as with an unset `DOCKER_TLS_VERIFY`.
+    pub fn verify(
+        &mut self,
+        verify: bool,
+    ) -> &mut Self {
+        self.verify = Some(verify);
+        self
+    }
+
+    /// Accept any certificate the daemon presents, even if it's self-signed,
+    /// expired or for another host.
+    ///
+    /// This leaves connections open to man-in-the-middle attacks, so should only
+    /// be used in lab environments.
+    pub fn danger_accept_invalid_certs(
+        &mut self,
+        accept: bool,
+    ) -> &mut Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Verify the daemon's certificate against, and send as SNI, the given name
+    /// rather than that of the host connected to, such as when connecting by IP
+    /// address. Only supported with the `tls` feature
+    pub fn server_name<S>(
+        &mut self,
+        name: S,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.server_name = Some(name.into());
+        self
+    }
+
+    pub fn build(&self) -> TlsConfig {
+        TlsConfig {
+            ca: self.ca.clone(),
+            cert: self.cert.clone(),
+            key: self.key.clone(),
+            key_password: self.key_password.clone(),
+            identity: self.identity.clone(),
+            verify: self.verify.unwrap_or(true),
+            accept_invalid_certs: self.accept_invalid_certs,
+            server_name: self.server_name.clone(),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Forwarding container output into the `tracing` ecosystem, so it can be merged
+//! into the host application's logging pipeline
+//!
+//! Each line becomes an event with the target `shiplift::container`, tagged with
+//! the container's id and name and the stream it was written to. Lines written
+//! to stdout are logged at `INFO`, and those written to stderr at `WARN`.
+//!
+//! ```no_run
+//! use futures::Future;
+//! use shiplift::{trace, Docker, LogsOptions};
+//!
+//! let docker = Docker::new();
+//! let container = docker.containers().get("my-container");
+//! let logs = container.logs(
+//!     &LogsOptions::builder()
+//!         .stdout(true)
+//!         .stderr(true)
+//!         .follow(true)
+//!         .build(),
+//! );
+//! tokio::run(
+//!     trace::forward(logs, "my-container", "my-container")
+//!         .map_err(|e| eprintln!("Error: {}", e)),
+//! );
+//! ```
+
+use crate::{
+    tty::{Chunk, StreamType},
+    Error,
+};
+use futures::{Future, Stream};
+
+/// Emits each line of a container's output as a `tracing` event, resolving once
+/// the output ends. A final line without a newline is emitted too
+pub fn forward<S>(
+    logs: S,
+    id: &str,
+    name: &str,
+) -> impl Future<Item = (), Error = Error>
+where
+    S: Stream<Item = Chunk, Error = Error>,
+{
+    let lines = Lines {
+        id: id.to_owned(),
+        // the api reports names with a leading slash
+        name: name.trim_start_matches('/').to_owned(),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    logs.fold(lines, |mut lines, chunk| {
+        lines.push(&chunk);
+        Ok::<_, Error>(lines)
+    })
+    .map(|mut lines| lines.flush())
+}
+
+/// Splits output into lines, buffering the partial line of each stream
+struct Lines {
+    id: String,
+    name: String,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl Lines {
+    fn push(
+        &mut self,
+        chunk: &Chunk,
+    ) {
+        let Lines {
+            ref id,
+            ref name,
+            ref mut stdout,
+            ref mut stderr,
+        } = *self;
+        let buffer = match chunk.stream_type {
+            StreamType::StdErr => stderr,
+            _ => stdout,
+        };
+        buffer.extend_from_slice(&chunk.data);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line = buffer.drain(..=end).collect::<Vec<_>>();
+            emit(id, name, chunk.stream_type, &line[..end]);
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.stdout.is_empty() {
+            emit(&self.id, &self.name, StreamType::StdOut, &self.stdout);
+            self.stdout.clear();
+        }
+        if !self.stderr.is_empty() {
+            emit(&self.id, &self.name, StreamType::StdErr, &self.stderr);
+            self.stderr.clear();
+        }
+    }
+}
+
+fn emit(
+    id: &str,
+    name: &str,
+    stream: StreamType,
+    line: &[u8],
+) {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end_matches('\r');
+    match stream {
+        StreamType::StdErr => tracing::warn!(
+            target: "shiplift::container",
+            stream = "stderr",
+            container.id = id,
+            container.name = name,
+            "{}",
+            line
+        ),
+        _ => tracing::info!(
+            target: "shiplift::container",
+            stream = "stdout",
+            container.id = id,
+            container.name = name,
+            "{}",
+            line
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::forward;
+    use crate::{
+        tty::{Chunk, StreamType},
+        Error,
+    };
+    use bytes::Bytes;
+    use futures::{stream, Future};
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    /// Records the level and fields of every event
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+        fn record_debug(
+            &mut self,
+            field: &Field,
+            value: &dyn fmt::Debug,
+        ) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(
+            &self,
+            _: &Metadata,
+        ) -> bool {
+            true
+        }
+
+        fn new_span(
+            &self,
+            _: &span::Attributes,
+        ) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(
+            &self,
+            _: &span::Id,
+            _: &span::Record,
+        ) {
+        }
+
+        fn record_follows_from(
+            &self,
+            _: &span::Id,
+            _: &span::Id,
+        ) {
+        }
+
+        fn event(
+            &self,
+            event: &Event,
+        ) {
+            let mut fields = Fields(vec![event.metadata().level().to_string()]);
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0.join(" "));
+        }
+
+        fn enter(
+            &self,
+            _: &span::Id,
+        ) {
+        }
+
+        fn exit(
+            &self,
+            _: &span::Id,
+        ) {
+        }
+    }
+
+    fn chunk(
+        stream_type: StreamType,
+        data: &'static str,
+    ) -> Chunk {
+        Chunk {
+            stream_type,
+            data: Bytes::from_static(data.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn lines_become_events() {
+        let recorder = Recorder::default();
+        let logs = stream::iter_ok::<_, Error>(vec![
+            chunk(StreamType::StdOut, "starting\nlist"),
+            chunk(StreamType::StdErr, "oops\r\n"),
+            chunk(StreamType::StdOut, "ening\nbye"),
+        ]);
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            forward(logs, "abc", "/web").wait().unwrap();
+        });
+        let fields = r#"container.id="abc" container.name="web""#;
+        assert_eq!(
+            vec![
+                format!(r#"INFO message=starting stream="stdout" {}"#, fields),
+                format!(r#"WARN message=oops stream="stderr" {}"#, fields),
+                format!(r#"INFO message=listening stream="stdout" {}"#, fields),
+                format!(r#"INFO message=bye stream="stdout" {}"#, fields),
+            ],
+            *recorder.0.lock().unwrap()
+        );
+    }
+}
@@ -1,16 +1,19 @@
 //! Transports for communicating with the docker daemon
 
-use crate::{Error, Result};
+use crate::{proxy, Error, Result};
+use bytes::Bytes;
 use futures::{
     future::{self, Either},
-    Future, IntoFuture, Stream,
+    task::{self, Task},
+    Async, Future, Poll, Stream,
 };
 use hyper::{
-    client::{Client, HttpConnector},
-    header, Body, Chunk, Method, Request, StatusCode,
+    client::Client, header, Body, Chunk, HeaderMap, Method, Request, Response, StatusCode,
 };
 #[cfg(feature = "tls")]
 use hyper_openssl::HttpsConnector;
+#[cfg(feature = "tls-rustls")]
+use hyper_rustls::HttpsConnector as RustlsConnector;
 #[cfg(feature = "unix-socket")]
 use hyperlocal::UnixConnector;
 #[cfg(feature = "unix-socket")]
@@ -19,11 +22,107 @@ use log::debug;
 use mime::Mime;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::{fmt, iter};
+use std::{
+    fmt, io, iter,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::timer::{Delay, Timeout};
 use tokio_io::{AsyncRead, AsyncWrite};
 
 pub fn tar() -> Mime {
-    "application/tar".parse().unwrap()
+    "application/tar".parse().expect("valid mime literal")
+}
+
+/// Fails a future with `Error::Timeout` if it doesn't resolve within `timeout`,
+/// if there is one
+pub(crate) fn deadline<F>(
+    future: F,
+    timeout: Option<Duration>,
+) -> impl Future<Item = F::Item, Error = Error>
+where
+    F: Future<Error = Error>,
+{
+    match timeout {
+        Some(timeout) => Either::A(Timeout::new(future, timeout).map_err(|e| {
+            if e.is_elapsed() {
+                Error::Timeout
+            } else {
+                let message = e.to_string();
+                match e.into_inner() {
+                    Some(e) => e,
+                    // the timer itself failed, e.g. outside of a tokio runtime
+                    None => Error::IO(io::Error::new(io::ErrorKind::Other, message)),
+                }
+            }
+        })),
+        None => Either::B(future),
+    }
+}
+
+/// The most a buffered response's `Content-Length` may reserve up front
+const MAX_PREALLOCATION: usize = 16 * 1024 * 1024;
+
+/// Reads a whole response body into a buffer sized from its `Content-Length`,
+/// so large bodies such as image inspects and `df` aren't grown chunk by chunk
+fn read_body(
+    headers: &HeaderMap,
+    body: Body,
+) -> impl Future<Item = Vec<u8>, Error = Error> {
+    let capacity = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<usize>().ok())
+        .map_or(0, |length| length.min(MAX_PREALLOCATION));
+    body.map_err(Error::Hyper)
+        .fold(Vec::with_capacity(capacity), |mut buffer, chunk| {
+            buffer.extend_from_slice(&chunk);
+            Ok::<_, Error>(buffer)
+        })
+}
+
+/// A means of sending http requests to the docker daemon, for connecting in ways
+/// shiplift doesn't support out of the box, such as over vsock or an existing
+/// duplex stream
+///
+/// ```no_run
+/// use futures::Future;
+/// use hyper::{client::HttpConnector, Body, Client, Request, Response};
+/// use shiplift::{transport::HttpTransport, Docker, Error};
+///
+/// #[derive(Debug)]
+/// struct Local(Client<HttpConnector>);
+///
+/// impl HttpTransport for Local {
+///     fn uri(&self, endpoint: &str) -> String {
+///         format!("http://127.0.0.1:2375{}", endpoint)
+///     }
+///
+///     fn request(
+///         &self,
+///         req: Request<Body>,
+///     ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+///         Box::new(self.0.request(req).map_err(Error::Hyper))
+///     }
+/// }
+///
+/// let docker = Docker::from_transport(Local(Client::new()));
+/// ```
+pub trait HttpTransport: fmt::Debug + Send + Sync + 'static {
+    /// The uri requests to an endpoint, such as `/containers/json`, are sent to
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String;
+
+    /// Sends a request to the daemon, resolving to its response.
+    ///
+    /// Attaching to containers requires the response to support
+    /// `hyper::Body::on_upgrade`, as those from a `hyper::Client` do.
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send>;
 }
 
 /// Transports are types which define the means of communication
@@ -32,20 +131,40 @@ pub fn tar() -> Mime {
 pub enum Transport {
     /// A network tcp interface
     Tcp {
-        client: Client<HttpConnector>,
+        client: Client<proxy::Connector>,
         host: String,
+        timeout: Option<Duration>,
+        headers: HeaderMap,
     },
     /// TCP/TLS
     #[cfg(feature = "tls")]
     EncryptedTcp {
-        client: Client<HttpsConnector<HttpConnector>>,
+        client: Client<HttpsConnector<proxy::Connector>>,
+        host: String,
+        timeout: Option<Duration>,
+        headers: HeaderMap,
+    },
+    /// TCP/TLS using rustls rather than openssl
+    #[cfg(feature = "tls-rustls")]
+    RustlsTcp {
+        client: Client<RustlsConnector<proxy::Connector>>,
         host: String,
+        timeout: Option<Duration>,
+        headers: HeaderMap,
+    },
+    /// A user supplied implementation
+    Custom {
+        transport: Arc<dyn HttpTransport>,
+        timeout: Option<Duration>,
+        headers: HeaderMap,
     },
     /// A Unix domain socket
     #[cfg(feature = "unix-socket")]
     Unix {
         client: Client<UnixConnector>,
         path: String,
+        timeout: Option<Duration>,
+        headers: HeaderMap,
     },
 }
 
@@ -58,6 +177,9 @@ impl fmt::Debug for Transport {
             Transport::Tcp { ref host, .. } => write!(f, "Tcp({})", host),
             #[cfg(feature = "tls")]
             Transport::EncryptedTcp { ref host, .. } => write!(f, "EncryptedTcp({})", host),
+            #[cfg(feature = "tls-rustls")]
+            Transport::RustlsTcp { ref host, .. } => write!(f, "RustlsTcp({})", host),
+            Transport::Custom { ref transport, .. } => write!(f, "Custom({:?})", transport),
             #[cfg(feature = "unix-socket")]
             Transport::Unix { ref path, .. } => write!(f, "Unix({})", path),
         }
@@ -72,18 +194,42 @@ impl Transport {
         endpoint: &str,
         body: Option<(B, Mime)>,
     ) -> impl Future<Item = String, Error = Error>
+    where
+        B: Into<Body>,
+    {
+        self.request_with_headers(method, endpoint, body)
+            .map(|(body, _)| body)
+    }
+
+    /// Make a request and return the whole response in a `String`, along
+    /// with the headers of the response
+    pub fn request_with_headers<B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<(B, Mime)>,
+    ) -> impl Future<Item = (String, HeaderMap), Error = Error>
     where
         B: Into<Body>,
     {
         let endpoint = endpoint.to_string();
-        self.stream_chunks(method, &endpoint, body, None::<iter::Empty<_>>)
-            .concat2()
-            .and_then(|v| {
-                String::from_utf8(v.to_vec())
-                    .map_err(Error::Encoding)
-                    .into_future()
+        let context = (method.clone(), endpoint.clone());
+        let response = self
+            .response(method, &endpoint, body, None::<iter::Empty<_>>)
+            .and_then(|r| {
+                let (parts, body) = r.into_parts();
+                read_body(&parts.headers, body)
+                    .and_then(|v| String::from_utf8(v).map_err(Error::Encoding))
+                    .map(move |body| (body, parts.headers))
             })
-            .inspect(move |body| debug!("{} raw response: {}", endpoint, body))
+            .inspect(move |(body, _)| {
+                if !carries_credentials(&endpoint) {
+                    debug!("{} raw response: {}", endpoint, body)
+                }
+            });
+        // the whole body of buffered responses must arrive in time
+        self.with_timeout(response)
+            .map_err(move |e| e.context(&context.0, &context.1))
     }
 
     /// Make a request and return a `Stream` of `Chunks` as they are returned.
@@ -98,47 +244,71 @@ impl Transport {
         B: Into<Body>,
         H: IntoIterator<Item = (&'static str, String)>,
     {
-        let req = self
-            .build_request(method, endpoint, body, headers, |_| ())
-            .expect("Failed to build request!");
-
-        self.send_request(req)
-            .and_then(|res| {
-                let status = res.status();
-                match status {
-                    // Success case: pass on the response
-                    StatusCode::OK
-                    | StatusCode::CREATED
-                    | StatusCode::SWITCHING_PROTOCOLS
-                    | StatusCode::NO_CONTENT => Either::A(future::ok(res)),
-                    // Error case: parse the body to try to extract the error message
-                    _ => Either::B(
-                        res.into_body()
-                            .concat2()
-                            .map_err(Error::Hyper)
-                            .and_then(|v| {
-                                String::from_utf8(v.into_iter().collect::<Vec<u8>>())
-                                    .map_err(Error::Encoding)
-                            })
-                            .and_then(move |body| {
-                                future::err(Error::Fault {
-                                    code: status,
-                                    message: Self::get_error_message(&body).unwrap_or_else(|| {
-                                        status
-                                            .canonical_reason()
-                                            .unwrap_or_else(|| "unknown error code")
-                                            .to_owned()
-                                    }),
-                                })
-                            }),
-                    ),
-                }
-            })
+        let context = (method.clone(), endpoint.to_owned());
+        self.response(method, endpoint, body, headers)
             .map(|r| {
                 // Convert the response body into a stream of chunks
                 r.into_body().map_err(Error::Hyper)
             })
             .flatten_stream()
+            .map_err(move |e| e.context(&context.0, &context.1))
+    }
+
+    /// Make a request and return only the headers of the response.
+    pub fn request_headers(
+        &self,
+        method: Method,
+        endpoint: &str,
+    ) -> impl Future<Item = HeaderMap, Error = Error> {
+        let context = (method.clone(), endpoint.to_owned());
+        self.response::<Body, iter::Empty<_>>(method, endpoint, None, None)
+            .map(|r| r.headers().clone())
+            .map_err(move |e| e.context(&context.0, &context.1))
+    }
+
+    /// Make a request and return the response, or an error if the daemon
+    /// did not respond with a success status.
+    fn response<B, H>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<(B, Mime)>,
+        headers: Option<H>,
+    ) -> impl Future<Item = Response<Body>, Error = Error>
+    where
+        B: Into<Body>,
+        H: IntoIterator<Item = (&'static str, String)>,
+    {
+        let req = match self.build_request(method, endpoint, body, headers, |_| ()) {
+            Ok(req) => req,
+            Err(e) => return Either::A(future::err(e)),
+        };
+
+        Either::B(self.send_request(req).and_then(|res| {
+            let status = res.status();
+            match status {
+                // Success case: pass on the response
+                StatusCode::OK
+                | StatusCode::CREATED
+                | StatusCode::SWITCHING_PROTOCOLS
+                | StatusCode::NO_CONTENT => Either::A(future::ok(res)),
+                // Error case: parse the body to try to extract the error message
+                _ => {
+                    let (parts, body) = res.into_parts();
+                    Either::B(read_body(&parts.headers, body).and_then(move |body| {
+                        let body = Bytes::from(body);
+                        let message = Self::get_error_message(&String::from_utf8_lossy(&body))
+                            .unwrap_or_else(|| {
+                                status
+                                    .canonical_reason()
+                                    .unwrap_or_else(|| "unknown error code")
+                                    .to_owned()
+                            });
+                        future::err(Error::from_response(status, parts.headers, body, message))
+                    }))
+                }
+            }
+        }))
     }
 
     /// Builds an HTTP request.
@@ -157,22 +327,15 @@ impl Transport {
         let mut builder = Request::builder();
         f(&mut builder);
 
-        let req = match *self {
-            Transport::Tcp { ref host, .. } => {
-                builder.method(method).uri(&format!("{}{}", host, endpoint))
-            }
-            #[cfg(feature = "tls")]
-            Transport::EncryptedTcp { ref host, .. } => {
-                builder.method(method).uri(&format!("{}{}", host, endpoint))
-            }
-            #[cfg(feature = "unix-socket")]
-            Transport::Unix { ref path, .. } => {
-                let uri: hyper::Uri = DomainUri::new(&path, endpoint).into();
-                builder.method(method).uri(&uri.to_string())
-            }
-        };
+        let req = builder
+            .method(method)
+            .uri(&HttpTransport::uri(self, endpoint));
         let req = req.header(header::HOST, "");
 
+        for (k, v) in self.default_headers() {
+            req.header(k, v);
+        }
+
         if let Some(h) = headers {
             for (k, v) in h.into_iter() {
                 req.header(k, v);
@@ -192,19 +355,110 @@ impl Transport {
         &self,
         req: Request<hyper::Body>,
     ) -> impl Future<Item = hyper::Response<Body>, Error = Error> {
+        let logged = logger(&req);
+        #[cfg(feature = "debug-http")]
+        let req = crate::debug::request(req);
         let req = match self {
             Transport::Tcp { ref client, .. } => client.request(req),
             #[cfg(feature = "tls")]
             Transport::EncryptedTcp { ref client, .. } => client.request(req),
+            #[cfg(feature = "tls-rustls")]
+            Transport::RustlsTcp { ref client, .. } => client.request(req),
+            Transport::Custom { ref transport, .. } => {
+                return Either::A(self.with_timeout(transport.request(req)).map(logged));
+            }
             #[cfg(feature = "unix-socket")]
             Transport::Unix { ref client, .. } => client.request(req),
         };
 
-        req.map_err(Error::Hyper)
+        let socket = self.socket_path().map(str::to_owned);
+        Either::B(
+            self.with_timeout(req.map_err(move |e| match socket {
+                Some(path) => Error::from_socket_error(path, e),
+                None => Error::Hyper(e),
+            }))
+            .map(logged),
+        )
+    }
+
+    /// The path of the Unix socket this transport connects to, if any
+    fn socket_path(&self) -> Option<&str> {
+        match *self {
+            #[cfg(feature = "unix-socket")]
+            Transport::Unix { ref path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Headers sent with every request
+    pub(crate) fn default_headers(&self) -> &HeaderMap {
+        match *self {
+            Transport::Tcp { ref headers, .. } => headers,
+            #[cfg(feature = "tls")]
+            Transport::EncryptedTcp { ref headers, .. } => headers,
+            #[cfg(feature = "tls-rustls")]
+            Transport::RustlsTcp { ref headers, .. } => headers,
+            Transport::Custom { ref headers, .. } => headers,
+            #[cfg(feature = "unix-socket")]
+            Transport::Unix { ref headers, .. } => headers,
+        }
+    }
+
+    /// The maximum time to wait on the daemon for a response
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        match *self {
+            Transport::Tcp { timeout, .. } => timeout,
+            #[cfg(feature = "tls")]
+            Transport::EncryptedTcp { timeout, .. } => timeout,
+            #[cfg(feature = "tls-rustls")]
+            Transport::RustlsTcp { timeout, .. } => timeout,
+            Transport::Custom { timeout, .. } => timeout,
+            #[cfg(feature = "unix-socket")]
+            Transport::Unix { timeout, .. } => timeout,
+        }
+    }
+
+    /// Replaces the maximum time to wait on the daemon for a response
+    pub(crate) fn set_timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) {
+        match *self {
+            Transport::Tcp {
+                timeout: ref mut t, ..
+            } => *t = timeout,
+            #[cfg(feature = "tls")]
+            Transport::EncryptedTcp {
+                timeout: ref mut t, ..
+            } => *t = timeout,
+            #[cfg(feature = "tls-rustls")]
+            Transport::RustlsTcp {
+                timeout: ref mut t, ..
+            } => *t = timeout,
+            Transport::Custom {
+                timeout: ref mut t, ..
+            } => *t = timeout,
+            #[cfg(feature = "unix-socket")]
+            Transport::Unix {
+                timeout: ref mut t, ..
+            } => *t = timeout,
+        }
+    }
+
+    /// Fails a future with `Error::Timeout` if it doesn't resolve within this
+    /// transport's timeout
+    fn with_timeout<F>(
+        &self,
+        future: F,
+    ) -> impl Future<Item = F::Item, Error = Error>
+    where
+        F: Future<Error = Error>,
+    {
+        deadline(future, self.timeout())
     }
 
-    /// Makes an HTTP request, upgrading the connection to a TCP
-    /// stream on success.
+    /// Makes an HTTP request, upgrading the connection to a raw
+    /// stream on success, over any transport hyper can upgrade.
     ///
     /// This method can be used for operations such as viewing
     /// docker container logs interactively.
@@ -217,28 +471,24 @@ impl Transport {
     where
         B: Into<Body>,
     {
-        match self {
-            Transport::Tcp { .. } => (),
-            #[cfg(feature = "tls")]
-            Transport::EncryptedTcp { .. } => (),
-            #[cfg(feature = "unix-socket")]
-            Transport::Unix { .. } => panic!("connection streaming is only supported over TCP"),
-        };
+        let context = (method.clone(), endpoint.to_owned());
+        let req = self.build_request(method, endpoint, body, None::<iter::Empty<_>>, |builder| {
+            builder
+                .header(header::CONNECTION, "Upgrade")
+                .header(header::UPGRADE, "tcp");
+        });
 
-        let req = self
-            .build_request(method, endpoint, body, None::<iter::Empty<_>>, |builder| {
-                builder
-                    .header(header::CONNECTION, "Upgrade")
-                    .header(header::UPGRADE, "tcp");
-            })
-            .expect("Failed to build request!");
+        let res = match req {
+            Ok(req) => Either::A(self.send_request(req)),
+            Err(e) => Either::B(future::err(e)),
+        };
 
-        self.send_request(req)
-            .and_then(|res| match res.status() {
-                StatusCode::SWITCHING_PROTOCOLS => Ok(res),
-                _ => Err(Error::ConnectionNotUpgraded),
-            })
-            .and_then(|res| res.into_body().on_upgrade().from_err())
+        res.and_then(|res| match res.status() {
+            StatusCode::SWITCHING_PROTOCOLS => Ok(res),
+            _ => Err(Error::ConnectionNotUpgraded),
+        })
+        .and_then(|res| res.into_body().on_upgrade().from_err())
+        .map_err(move |e| e.context(&context.0, &context.1))
     }
 
     pub fn stream_upgrade_multiplexed<B>(
@@ -263,7 +513,665 @@ impl Transport {
     }
 }
 
+impl HttpTransport for Transport {
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        match *self {
+            Transport::Tcp { ref host, .. } => format!("{}{}", host, endpoint),
+            #[cfg(feature = "tls")]
+            Transport::EncryptedTcp { ref host, .. } => format!("{}{}", host, endpoint),
+            #[cfg(feature = "tls-rustls")]
+            Transport::RustlsTcp { ref host, .. } => format!("{}{}", host, endpoint),
+            Transport::Custom { ref transport, .. } => transport.uri(endpoint),
+            #[cfg(feature = "unix-socket")]
+            Transport::Unix { ref path, .. } => {
+                let uri: hyper::Uri = DomainUri::new(&path, endpoint).into();
+                uri.to_string()
+            }
+        }
+    }
+
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+        Box::new(self.send_request(req))
+    }
+}
+
+/// A transport which caps the number of requests in flight through another
+/// transport, so batch operations from many tasks don't exhaust the daemon's
+/// connection backlog. Further requests wait for one in flight to finish.
+///
+/// A request counts as in flight until the daemon responds, not while its
+/// response body streams, so long-lived streams such as events don't hold
+/// back other requests.
+///
+/// Configured with `DockerBuilder::max_concurrent_requests`.
+#[derive(Debug)]
+pub struct ConcurrencyLimit<T> {
+    inner: Arc<T>,
+    limiter: Arc<Limiter>,
+}
+
+impl<T> ConcurrencyLimit<T>
+where
+    T: HttpTransport,
+{
+    /// Allows `max_in_flight` requests at once, or one if given zero, which
+    /// would otherwise hold back every request forever
+    pub fn new(
+        inner: T,
+        max_in_flight: usize,
+    ) -> ConcurrencyLimit<T> {
+        ConcurrencyLimit {
+            inner: Arc::new(inner),
+            limiter: Arc::new(Limiter::new(max_in_flight.max(1))),
+        }
+    }
+}
+
+impl<T> HttpTransport for ConcurrencyLimit<T>
+where
+    T: HttpTransport,
+{
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        self.inner.uri(endpoint)
+    }
+
+    fn request(
+        &self,
+        req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+        let inner = self.inner.clone();
+        let limiter = self.limiter.clone();
+        Box::new(
+            future::poll_fn(move || Ok(Limiter::poll_acquire(&limiter))).and_then(move |permit| {
+                inner.request(req).then(move |res| {
+                    drop(permit);
+                    res
+                })
+            }),
+        )
+    }
+}
+
+/// Hooks run around each request sent to the daemon, such as to sign
+/// requests, audit them or serve them from a cache
+///
+/// Installed with `DockerBuilder::middleware`, or by wrapping a transport
+/// in an `Intercept`.
+pub trait Middleware: Send + Sync + 'static {
+    /// Inspects or modifies a request before it is sent. Returning an error
+    /// fails the request without sending it
+    fn on_request(
+        &self,
+        _req: &mut Request<Body>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Inspects or modifies a response before its body is read. Returning an
+    /// error fails the request
+    fn on_response(
+        &self,
+        _res: &mut Response<Body>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<M> Middleware for Arc<M>
+where
+    M: Middleware + ?Sized,
+{
+    fn on_request(
+        &self,
+        req: &mut Request<Body>,
+    ) -> Result<()> {
+        (**self).on_request(req)
+    }
+
+    fn on_response(
+        &self,
+        res: &mut Response<Body>,
+    ) -> Result<()> {
+        (**self).on_response(res)
+    }
+}
+
+/// Runs a `Middleware` around the requests sent with another transport
+pub struct Intercept<T> {
+    inner: Arc<T>,
+    middleware: Arc<dyn Middleware>,
+}
+
+impl<T> Intercept<T>
+where
+    T: HttpTransport,
+{
+    pub fn new<M>(
+        inner: T,
+        middleware: M,
+    ) -> Intercept<T>
+    where
+        M: Middleware,
+    {
+        Intercept {
+            inner: Arc::new(inner),
+            middleware: Arc::new(middleware),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Intercept<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        f.debug_struct("Intercept")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T> HttpTransport for Intercept<T>
+where
+    T: HttpTransport,
+{
+    fn uri(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        self.inner.uri(endpoint)
+    }
+
+    fn request(
+        &self,
+        mut req: Request<Body>,
+    ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+        if let Err(e) = self.middleware.on_request(&mut req) {
+            return Box::new(future::err(e));
+        }
+        let middleware = self.middleware.clone();
+        Box::new(
+            self.inner
+                .request(req)
+                .and_then(move |mut res| middleware.on_response(&mut res).map(|_| res)),
+        )
+    }
+}
+
+/// A counting semaphore for `ConcurrencyLimit`
+#[derive(Debug)]
+struct Limiter {
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    available: usize,
+    waiting: Vec<Task>,
+}
+
+/// A request slot, given back when dropped
+struct Permit {
+    limiter: Arc<Limiter>,
+}
+
+impl Limiter {
+    fn new(permits: usize) -> Limiter {
+        Limiter {
+            state: Mutex::new(LimiterState {
+                available: permits,
+                waiting: Vec::new(),
+            }),
+        }
+    }
+
+    fn poll_acquire(limiter: &Arc<Limiter>) -> Async<Permit> {
+        let mut state = limiter.state.lock().unwrap();
+        if state.available > 0 {
+            state.available -= 1;
+            Async::Ready(Permit {
+                limiter: limiter.clone(),
+            })
+        } else {
+            // a task polled again before being woken is already waiting
+            if !state.waiting.iter().any(Task::will_notify_current) {
+                state.waiting.push(task::current());
+            }
+            Async::NotReady
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        state.available += 1;
+        // waiters may have gone away, so wake them all rather than risk
+        // handing the permit to one which will never poll again
+        for task in state.waiting.drain(..) {
+            task.notify();
+        }
+    }
+}
+
+/// Fails a stream with `Error::StreamIdle` once nothing has been received for
+/// a given duration, rather than hanging forever on a connection which died
+/// without being closed.
+///
+/// The daemon doesn't send keepalives, so streams which may go quiet for long
+/// periods, such as events, need a generous timeout.
+///
+/// ```no_run
+/// use shiplift::{transport::IdleTimeout, Docker};
+/// use std::time::Duration;
+/// use tokio::prelude::{Future, Stream};
+///
+/// let docker = Docker::new();
+/// let stats = docker.containers().get("web").stats();
+/// tokio::run(
+///     IdleTimeout::new(stats, Duration::from_secs(30))
+///         .for_each(|stat| {
+///             println!("{:?}", stat);
+///             Ok(())
+///         })
+///         .map_err(|e| eprintln!("Error: {}", e)),
+/// );
+/// ```
+pub struct IdleTimeout<S> {
+    stream: S,
+    timeout: Duration,
+    delay: Delay,
+}
+
+impl<S> IdleTimeout<S>
+where
+    S: Stream<Error = Error>,
+{
+    pub fn new(
+        stream: S,
+        timeout: Duration,
+    ) -> IdleTimeout<S> {
+        IdleTimeout {
+            stream,
+            timeout,
+            delay: Delay::new(Instant::now() + timeout),
+        }
+    }
+}
+
+impl<S> Stream for IdleTimeout<S>
+where
+    S: Stream<Error = Error>,
+{
+    type Item = S::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, Error> {
+        if let Async::Ready(item) = self.stream.poll()? {
+            self.delay.reset(Instant::now() + self.timeout);
+            return Ok(Async::Ready(item));
+        }
+        match self.delay.poll() {
+            Ok(Async::Ready(())) => Err(Error::StreamIdle(self.timeout)),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            // the timer itself failed, e.g. outside of a tokio runtime
+            Err(e) => Err(Error::IO(io::Error::new(
+                io::ErrorKind::Other,
+                e.to_string(),
+            ))),
+        }
+    }
+}
+
+/// Endpoints whose request or response bodies carry credentials or secrets,
+/// given as path segments with `*` matching any one segment
+const REDACTED_BODIES: &[&[&str]] = &[
+    &["auth"],
+    &["secrets", "create"],
+    &["secrets", "*", "update"],
+    &["swarm", "init"],
+    &["swarm", "join"],
+    &["swarm", "unlock"],
+    &["swarm", "unlockkey"],
+];
+
+/// Whether the bodies sent to or received from an endpoint must not be logged
+pub(crate) fn carries_credentials(path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or_default();
+    let mut segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    // requests pinned to an api version are prefixed with it, e.g. `/v1.40/auth`
+    let versioned = segments.first().map_or(false, |first| {
+        first.len() > 1
+            && first.starts_with('v')
+            && first[1..].chars().all(|c| c.is_ascii_digit() || c == '.')
+    });
+    if versioned {
+        segments.remove(0);
+    }
+    REDACTED_BODIES.iter().any(|endpoint| {
+        endpoint.len() == segments.len()
+            && endpoint
+                .iter()
+                .zip(&segments)
+                .all(|(expected, segment)| *expected == "*" || expected == segment)
+    })
+}
+
+/// Logs the response to a request when the `debug-http` feature is enabled
+#[cfg(feature = "debug-http")]
+fn logger(req: &Request<Body>) -> impl Fn(Response<Body>) -> Response<Body> {
+    let redact_body = carries_credentials(req.uri().path());
+    move |res| crate::debug::response(res, redact_body)
+}
+
+#[cfg(not(feature = "debug-http"))]
+fn logger(_: &Request<Body>) -> impl Fn(Response<Body>) -> Response<Body> {
+    |res| res
+}
+
 #[derive(Serialize, Deserialize)]
 struct ErrorResponse {
     message: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        carries_credentials, read_body, ConcurrencyLimit, HttpTransport, IdleTimeout, Intercept,
+        Limiter, Middleware,
+    };
+    use crate::{
+        testing::MockTransport, Docker, Error, ImportOptions, LogsOptions, Result, WaitOptions,
+    };
+    use futures::{future, stream, Async, Future, Stream};
+    use hyper::{header, Body, HeaderMap, Method, Request, Response, StatusCode};
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use tokio::{runtime::current_thread::block_on_all, timer::Delay};
+
+    #[test]
+    fn limited_requests_all_complete() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        let docker = Docker::from_transport(ConcurrencyLimit::new(mock.clone(), 1));
+
+        let pings = block_on_all(future::join_all((0..5).map(|_| docker.ping()))).unwrap();
+        assert_eq!(vec!["OK"; 5], pings);
+        assert_eq!(5, mock.requests().len());
+    }
+
+    /// Records the paths requested and the statuses responded with
+    #[derive(Default)]
+    struct Audit(Mutex<Vec<String>>);
+
+    impl Middleware for Audit {
+        fn on_request(
+            &self,
+            req: &mut Request<Body>,
+        ) -> Result<()> {
+            self.0.lock().unwrap().push(req.uri().path().to_owned());
+            Ok(())
+        }
+
+        fn on_response(
+            &self,
+            res: &mut Response<Body>,
+        ) -> Result<()> {
+            self.0.lock().unwrap().push(res.status().to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn middleware_sees_requests_and_responses() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        let audit = Arc::new(Audit::default());
+        let docker = Docker::from_transport(Intercept::new(mock, audit.clone()));
+
+        assert_eq!("OK", block_on_all(docker.ping()).unwrap());
+        assert_eq!(vec!["/_ping", "200 OK"], *audit.0.lock().unwrap());
+    }
+
+    #[test]
+    fn faults_keep_the_raw_response() {
+        let mock = MockTransport::new();
+        let body = r#"{"message":"invalid reference format","hint":"lowercase"}"#;
+        mock.on(Method::GET, "/_ping")
+            .respond(StatusCode::BAD_REQUEST, body);
+        let docker = Docker::from_transport(mock);
+
+        let error = block_on_all(docker.ping()).unwrap_err();
+        match error.inner() {
+            Error::Fault { code, message, .. } => {
+                assert_eq!(StatusCode::BAD_REQUEST, *code);
+                assert_eq!("invalid reference format", message);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(body.as_bytes(), &error.response().unwrap().body[..]);
+    }
+
+    #[test]
+    fn not_found_errors_keep_the_raw_response() {
+        let mock = MockTransport::new();
+        let body = r#"{"message":"no such image: busybox"}"#;
+        mock.on(Method::GET, "/images/busybox/json")
+            .respond(StatusCode::NOT_FOUND, body);
+        let docker = Docker::from_transport(mock);
+
+        let error = block_on_all(docker.images().get("busybox").inspect()).unwrap_err();
+        match error.inner() {
+            Error::NotFound { message, .. } => assert_eq!("no such image: busybox", message),
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(body.as_bytes(), &error.response().unwrap().body[..]);
+    }
+
+    /// A daemon which never responds
+    #[derive(Debug)]
+    struct Stalled;
+
+    impl HttpTransport for Stalled {
+        fn uri(
+            &self,
+            endpoint: &str,
+        ) -> String {
+            format!("http://localhost{}", endpoint)
+        }
+
+        fn request(
+            &self,
+            _: Request<Body>,
+        ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+            Box::new(future::empty())
+        }
+    }
+
+    /// A daemon which takes a while to respond to every request
+    #[derive(Debug)]
+    struct Slow(Duration);
+
+    impl HttpTransport for Slow {
+        fn uri(
+            &self,
+            endpoint: &str,
+        ) -> String {
+            format!("http://localhost{}", endpoint)
+        }
+
+        fn request(
+            &self,
+            _: Request<Body>,
+        ) -> Box<dyn Future<Item = Response<Body>, Error = Error> + Send> {
+            Box::new(
+                Delay::new(Instant::now() + self.0)
+                    .map_err(|e| Error::IO(io::Error::new(io::ErrorKind::Other, e)))
+                    .map(|_| Response::new(Body::empty())),
+            )
+        }
+    }
+
+    fn slow_docker() -> Docker {
+        Docker::builder()
+            .transport(Slow(Duration::from_millis(50)))
+            .request_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn stopping_waits_for_the_container_to_stop() {
+        let docker = slow_docker();
+        let container = docker.containers().get("app");
+        match block_on_all(container.start()).unwrap_err().inner() {
+            Error::Timeout => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+        block_on_all(container.stop(Some(Duration::from_secs(1)))).unwrap();
+    }
+
+    #[test]
+    fn restarting_waits_for_the_container_to_stop() {
+        let docker = slow_docker();
+        let container = docker.containers().get("app");
+        block_on_all(container.restart(Some(Duration::from_secs(1)))).unwrap();
+    }
+
+    #[test]
+    fn imports_can_override_the_timeout() {
+        let docker = slow_docker();
+        let tarball = || stream::once(Ok::<_, io::Error>(vec![0; 512]));
+        let error = block_on_all(docker.images().import_stream(tarball()).collect()).unwrap_err();
+        match error.inner() {
+            Error::Timeout => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let opts = ImportOptions::builder()
+            .timeout(Duration::from_secs(1))
+            .build();
+        block_on_all(
+            docker
+                .images()
+                .import_stream_with(&opts, tarball())
+                .collect(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn requests_can_override_the_timeout() {
+        let docker = Docker::from_transport(Stalled);
+        let container = docker.containers().get("app");
+        let opts = LogsOptions::builder()
+            .timeout(Duration::from_millis(10))
+            .build();
+        let error = block_on_all(container.logs(&opts).collect()).unwrap_err();
+        match error.inner() {
+            Error::Timeout => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let opts = WaitOptions::builder()
+            .timeout(Duration::from_millis(10))
+            .build();
+        match block_on_all(container.wait_with(&opts))
+            .unwrap_err()
+            .inner()
+        {
+            Error::Timeout => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zero_limit_allows_one_request() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        let docker = Docker::from_transport(ConcurrencyLimit::new(mock, 0));
+
+        let pings = block_on_all(future::join_all((0..2).map(|_| docker.ping()))).unwrap();
+        assert_eq!(vec!["OK"; 2], pings);
+    }
+
+    #[test]
+    fn waiting_tasks_are_registered_once() {
+        let limiter = Arc::new(Limiter::new(1));
+        block_on_all(future::lazy(|| {
+            let permit = Limiter::poll_acquire(&limiter);
+            for _ in 0..3 {
+                assert!(Limiter::poll_acquire(&limiter).is_not_ready());
+            }
+            assert_eq!(1, limiter.state.lock().unwrap().waiting.len());
+            drop(permit);
+            Ok::<_, ()>(())
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn idle_streams_time_out() {
+        let mut sent = false;
+        let idle = stream::poll_fn(move || {
+            if sent {
+                Ok(Async::NotReady)
+            } else {
+                sent = true;
+                Ok(Async::Ready(Some(1)))
+            }
+        });
+        let mut items = Vec::new();
+        let result = block_on_all(IdleTimeout::new(idle, Duration::from_millis(10)).for_each(
+            |item| {
+                items.push(item);
+                Ok(())
+            },
+        ));
+        match result {
+            Err(Error::StreamIdle(_)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(vec![1], items);
+    }
+
+    #[test]
+    fn bodies_are_read_into_a_buffer_of_their_content_length() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_LENGTH, "11".parse().unwrap());
+        let chunks = stream::iter_ok::<_, io::Error>(vec!["hello", " ", "world"]);
+
+        let body = read_body(&headers, Body::wrap_stream(chunks))
+            .wait()
+            .unwrap();
+        assert_eq!(b"hello world", &body[..]);
+        assert_eq!(11, body.capacity());
+    }
+
+    #[test]
+    fn credential_endpoints_are_recognised() {
+        assert!(carries_credentials("/auth"));
+        assert!(carries_credentials("/v1.40/auth"));
+        assert!(carries_credentials("/secrets/create"));
+        assert!(carries_credentials("/swarm/unlockkey?x=1"));
+        assert!(carries_credentials("/secrets/abc/update"));
+        assert!(!carries_credentials("/secrets"));
+        assert!(!carries_credentials("/containers/create"));
+        assert!(!carries_credentials("/volumes/auth"));
+    }
+}
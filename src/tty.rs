@@ -1,17 +1,29 @@
-use crate::errors::Error;
-use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
-use bytes::BytesMut;
-use futures::{self, Async};
-use hyper::rt::{Future, Stream};
+//! Decoding of container output streams
+//!
+//! Streams upgraded from http connections, such as those of attached containers,
+//! use the `tokio-io` IO traits, as do the rest of this crate's IO types. With the
+//! `futures-io` feature, `Multiplexed::compat` adapts them to futures 0.3 and the
+//! `futures-io` traits instead.
+
+use crate::{codec::StreamLimits, errors::Error};
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{Bytes, BytesMut};
+use futures::{self, Async, Future, Poll};
+use hyper::rt::Stream;
 use log::trace;
-use std::io::{self, Cursor};
-use tokio_codec::Decoder;
+use std::{
+    cmp,
+    collections::HashMap,
+    io::{self, Write},
+};
+use tokio_codec::{Decoder, FramedRead};
 use tokio_io::{AsyncRead, AsyncWrite};
+use url::form_urlencoded;
 
 #[derive(Debug)]
 pub struct Chunk {
     pub stream_type: StreamType,
-    pub data: Vec<u8>,
+    pub data: Bytes,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -21,28 +33,43 @@ pub enum StreamType {
     StdErr,
 }
 
-/// A multiplexed stream.
+/// A multiplexed stream of a container's output, which writes to its stdin.
 pub struct Multiplexed {
-    stdin: Box<dyn AsyncWrite>,
-    chunks: Box<dyn futures::Stream<Item = Chunk, Error = crate::Error>>,
+    stdin: Box<dyn AsyncWrite + Send>,
+    chunks: Box<dyn futures::Stream<Item = Chunk, Error = crate::Error> + Send>,
 }
 
 pub struct MultiplexedBlocking {
-    stdin: Box<dyn AsyncWrite>,
-    chunks: Box<dyn Iterator<Item = Result<Chunk, crate::Error>>>,
+    stdin: Box<dyn AsyncWrite + Send>,
+    chunks: Box<dyn Iterator<Item = Result<Chunk, crate::Error>> + Send>,
 }
 
 /// Represent the current state of the decoding of a TTY frame
 enum TtyDecoderState {
+    /// We have yet to find out whether the stream is multiplexed
+    Detecting,
     /// We have yet to read a frame header
     WaitingHeader,
     /// We have read a header and extracted the payload size and stream type,
     /// and are now waiting to read the corresponding payload
     WaitingPayload(usize, StreamType),
+    /// The stream is the raw output of a container created with a TTY, which
+    /// docker doesn't multiplex
+    Raw,
+    /// We are dropping the rest of a truncated frame's payload
+    Skipping(usize),
 }
 
+/// Decodes the output of a container, multiplexed into frames of stdout and
+/// stderr, or the raw output of a container created with a TTY, which is
+/// decoded as stdout.
+///
+/// Which of the two a stream is gets detected from its first bytes.
 pub struct TtyDecoder {
     state: TtyDecoderState,
+    limits: StreamLimits,
+    /// How much of the current frame's payload is beyond the limit
+    excess: usize,
 }
 
 impl Chunk {
@@ -51,7 +78,7 @@ impl Chunk {
     /// Returns `None` if the raw bytes do not represent
     /// a valid UTF-8 string.
     pub fn as_string(&self) -> Option<String> {
-        String::from_utf8(self.data.clone()).ok()
+        String::from_utf8(self.data.to_vec()).ok()
     }
 
     /// Unconditionally interprets the raw bytes as a string.
@@ -60,12 +87,79 @@ impl Chunk {
     pub fn as_string_lossy(&self) -> String {
         String::from_utf8_lossy(&self.data).into_owned()
     }
+
+    /// Splits the attributes docker prefixes to each line of logs requested
+    /// with `details` from the rest of the line.
+    ///
+    /// Returns `None` if the chunk doesn't start with such a prefix.
+    pub fn details(&self) -> Option<(LogDetails, Bytes)> {
+        let end = self.data.iter().position(|b| *b == b' ')?;
+        let mut attributes = HashMap::new();
+        if end > 0 {
+            for pair in self.data[..end].split(|b| *b == b',') {
+                if !pair.contains(&b'=') {
+                    return None;
+                }
+                attributes.extend(form_urlencoded::parse(pair).into_owned());
+            }
+        }
+        Some((
+            LogDetails { attributes },
+            self.data.slice(end + 1, self.data.len()),
+        ))
+    }
+}
+
+/// The attributes given to a log line by its log driver, such as labels,
+/// environment variables and, for swarm services, the task it came from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogDetails {
+    pub attributes: HashMap<String, String>,
+}
+
+impl LogDetails {
+    /// The swarm task which logged the line
+    pub fn task_id(&self) -> Option<&str> {
+        self.attribute("com.docker.swarm.task.id")
+    }
+
+    /// The swarm node the line was logged on
+    pub fn node_id(&self) -> Option<&str> {
+        self.attribute("com.docker.swarm.node.id")
+    }
+
+    /// The swarm service which logged the line
+    pub fn service_id(&self) -> Option<&str> {
+        self.attribute("com.docker.swarm.service.id")
+    }
+
+    fn attribute(
+        &self,
+        key: &str,
+    ) -> Option<&str> {
+        self.attributes.get(key).map(String::as_str)
+    }
 }
 
 impl TtyDecoder {
     pub fn new() -> Self {
+        Self::with_limits(StreamLimits::default())
+    }
+
+    /// A decoder for the raw output of a container created with a TTY
+    pub fn raw() -> Self {
+        Self {
+            state: TtyDecoderState::Raw,
+            ..Self::new()
+        }
+    }
+
+    /// A decoder enforcing the `max_frame_length` of `limits` on multiplexed frames
+    pub fn with_limits(limits: StreamLimits) -> Self {
         Self {
-            state: TtyDecoderState::WaitingHeader,
+            state: TtyDecoderState::Detecting,
+            limits,
+            excess: 0,
         }
     }
 }
@@ -76,6 +170,29 @@ impl Default for TtyDecoder {
     }
 }
 
+impl StreamType {
+    fn from_byte(byte: u8) -> Option<StreamType> {
+        match byte {
+            0 => Some(StreamType::StdIn),
+            1 => Some(StreamType::StdOut),
+            2 => Some(StreamType::StdErr),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the start of a stream could be a frame header, a stream type
+/// followed by three zero bytes. Returns `None` until there's enough to tell
+fn is_frame_header(start: &[u8]) -> Option<bool> {
+    match start.split_first() {
+        None => None,
+        Some((&stream_type, _)) if StreamType::from_byte(stream_type).is_none() => Some(false),
+        Some((_, rest)) if rest.iter().take(3).any(|&b| b != 0) => Some(false),
+        Some(_) if start.len() < 4 => None,
+        Some(_) => Some(true),
+    }
+}
+
 impl Decoder for TtyDecoder {
     type Item = Chunk;
     type Error = Error;
@@ -86,6 +203,27 @@ impl Decoder for TtyDecoder {
     ) -> Result<Option<Self::Item>, Self::Error> {
         loop {
             match self.state {
+                TtyDecoderState::Detecting => match is_frame_header(&src[..]) {
+                    Some(true) => {
+                        trace!("Detected a multiplexed stream");
+                        self.state = TtyDecoderState::WaitingHeader;
+                    }
+                    Some(false) => {
+                        trace!("Detected a raw TTY stream");
+                        self.state = TtyDecoderState::Raw;
+                    }
+                    None => return Ok(None),
+                },
+                TtyDecoderState::Raw => {
+                    if src.is_empty() {
+                        return Ok(None);
+                    }
+                    let data = src.take().freeze();
+                    return Ok(Some(Chunk {
+                        stream_type: StreamType::StdOut,
+                        data,
+                    }));
+                }
                 TtyDecoderState::WaitingHeader => {
                     if src.len() < 8 {
                         trace!("Not enough data to read a header");
@@ -93,30 +231,22 @@ impl Decoder for TtyDecoder {
                     } else {
                         trace!("Reading header");
                         let header_bytes = src.split_to(8);
-                        let payload_size: Vec<u8> = header_bytes[4..8].to_vec();
-                        let stream_type = match header_bytes[0] {
-                            0 => {
-                                return Err(Error::InvalidResponse(
-                                    "Unsupported stream of type stdin".to_string(),
-                                ));
-                            }
-                            1 => StreamType::StdOut,
-                            2 => StreamType::StdErr,
-                            n => {
-                                return Err(Error::InvalidResponse(format!(
-                                    "Unsupported stream of type {}",
-                                    n
-                                )));
-                            }
-                        };
-
-                        let length =
-                            Cursor::new(&payload_size).read_u32::<BigEndian>().unwrap() as usize;
+                        let stream_type = StreamType::from_byte(header_bytes[0])
+                            .ok_or_else(|| Error::InvalidStreamType(header_bytes[0]))?;
+                        let length = BigEndian::read_u32(&header_bytes[4..8]) as usize;
                         trace!(
                             "Read header: length = {}, stream_type = {:?}",
                             length,
                             stream_type
                         );
+                        let length = match self.limits.max_frame_length {
+                            Some(max) if length > max && self.limits.truncate => {
+                                self.excess = length - max;
+                                max
+                            }
+                            Some(max) if length > max => return Err(Error::TooLong(max)),
+                            _ => length,
+                        };
                         // We've successfully read a header, now we wait for the payload
                         self.state = TtyDecoderState::WaitingPayload(length, stream_type);
                         continue;
@@ -132,25 +262,57 @@ impl Decoder for TtyDecoder {
                         return Ok(None);
                     } else {
                         trace!("Reading payload");
-                        let data = src.split_to(len)[..].to_owned();
+                        let data = src.split_to(len).freeze();
                         let tty_chunk = Chunk { stream_type, data };
 
                         // We've successfully read a full frame, now we go back to waiting for the next
-                        // header
-                        self.state = TtyDecoderState::WaitingHeader;
+                        // header, once any of the payload beyond the limit has been dropped
+                        self.state = match self.excess {
+                            0 => TtyDecoderState::WaitingHeader,
+                            excess => TtyDecoderState::Skipping(excess),
+                        };
+                        self.excess = 0;
                         return Ok(Some(tty_chunk));
                     }
                 }
+                TtyDecoderState::Skipping(remaining) => {
+                    if src.is_empty() {
+                        return Ok(None);
+                    }
+                    let skipped = cmp::min(remaining, src.len());
+                    src.split_to(skipped);
+                    self.state = match remaining - skipped {
+                        0 => TtyDecoderState::WaitingHeader,
+                        remaining => TtyDecoderState::Skipping(remaining),
+                    };
+                }
             }
         }
     }
+
+    fn decode_eof(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(chunk) = self.decode(src)? {
+            return Ok(Some(chunk));
+        }
+        if let TtyDecoderState::Detecting = self.state {
+            // too short to be multiplexed
+            self.state = TtyDecoderState::Raw;
+            return self.decode(src);
+        }
+        // the stream ended partway through a frame
+        src.clear();
+        Ok(None)
+    }
 }
 
 impl Multiplexed {
     /// Create a multiplexed stream.
     pub(crate) fn new<T>(stream: T) -> Multiplexed
     where
-        T: AsyncRead + AsyncWrite + 'static,
+        T: AsyncRead + AsyncWrite + Send + 'static,
     {
         let (reader, stdin) = stream.split();
         Multiplexed {
@@ -165,6 +327,13 @@ impl Multiplexed {
             chunks: Box::new(self.chunks.wait()),
         }
     }
+
+    /// A futures 0.3 `Stream` of the container's output, which implements the
+    /// `futures-io` `AsyncWrite` to write to its stdin
+    #[cfg(feature = "futures-io")]
+    pub fn compat(self) -> futures_util::compat::Compat01As03<Multiplexed> {
+        futures_util::compat::Compat01As03::new(self)
+    }
 }
 
 impl futures::Stream for Multiplexed {
@@ -204,75 +373,180 @@ macro_rules! delegate_io_write {
 delegate_io_write!(Multiplexed);
 delegate_io_write!(MultiplexedBlocking);
 
+impl AsyncWrite for Multiplexed {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.stdin.shutdown()
+    }
+}
+
 pub fn chunks<S>(stream: S) -> impl futures::Stream<Item = Chunk, Error = crate::Error>
 where
     S: AsyncRead,
 {
-    let stream = futures::stream::unfold(stream, |stream| {
-        let header_future = ::tokio_io::io::read_exact(stream, vec![0; 8]);
-
-        let fut = header_future.and_then(|(stream, header_bytes)| {
-            let size_bytes = &header_bytes[4..];
-            let data_length = BigEndian::read_u32(size_bytes);
-            let stream_type = match header_bytes[0] {
-                0 => StreamType::StdIn,
-                1 => StreamType::StdOut,
-                2 => StreamType::StdErr,
-                n => panic!("invalid stream number from docker daemon: '{}'", n),
-            };
+    FramedRead::new(stream, TtyDecoder::new())
+}
 
-            ::tokio_io::io::read_exact(stream, vec![0; data_length as usize])
-                .map(move |(stream, data)| (Chunk { stream_type, data }, stream))
-        });
-        // FIXME: when updated to futures 0.2, the future itself returns the Option((Chunk,
-        // stream)).
-        // This is much better because it would allow us to swallow the unexpected eof and
-        // stop the stream much cleaner than writing a custom stream filter.
-        Some(fut)
-    });
-
-    util::stop_on_err(stream, |e| e.kind() != io::ErrorKind::UnexpectedEof)
-        .map_err(crate::Error::from)
+/// Copies a container's output to this process's stdout and stderr, according
+/// to the stream each chunk was written to
+pub fn copy_to_console<S>(stream: S) -> impl Future<Item = (), Error = Error>
+where
+    S: Stream<Item = Chunk, Error = Error>,
+{
+    copy_to(stream, io::stdout(), io::stderr()).map(|_| ())
 }
 
-mod util {
-    use futures::{Async, Stream};
+/// Copies a container's output to the given writers, according to the stream
+/// each chunk was written to, resolving to the writers once the output ends
+pub fn copy_to<S, O, E>(
+    stream: S,
+    stdout: O,
+    stderr: E,
+) -> impl Future<Item = (O, E), Error = Error>
+where
+    S: Stream<Item = Chunk, Error = Error>,
+    O: Write,
+    E: Write,
+{
+    stream.fold((stdout, stderr), |(mut stdout, mut stderr), chunk| {
+        let written = match chunk.stream_type {
+            StreamType::StdErr => stderr.write_all(&chunk.data).and_then(|_| stderr.flush()),
+            _ => stdout.write_all(&chunk.data).and_then(|_| stdout.flush()),
+        };
+        written.map(|_| (stdout, stderr)).map_err(Error::from)
+    })
+}
 
-    pub struct StopOnError<S, F> {
-        stream: S,
-        f: F,
+#[cfg(test)]
+mod tests {
+    use super::{copy_to, Chunk, StreamType, TtyDecoder};
+    use crate::{codec::StreamLimits, Error};
+    use bytes::{Bytes, BytesMut};
+    use futures::{stream, Future};
+    use tokio_codec::Decoder;
+
+    fn decode_all(bytes: &[u8]) -> Result<Vec<(String, String)>, Error> {
+        let mut decoder = TtyDecoder::new();
+        let mut src = BytesMut::from(bytes);
+        let mut chunks = Vec::new();
+        while let Some(chunk) = decoder.decode_eof(&mut src)? {
+            let stream = match chunk.stream_type {
+                StreamType::StdIn => "stdin",
+                StreamType::StdOut => "stdout",
+                StreamType::StdErr => "stderr",
+            };
+            chunks.push((stream.to_owned(), chunk.as_string_lossy()));
+        }
+        Ok(chunks)
     }
 
-    pub fn stop_on_err<S, F>(
-        stream: S,
-        f: F,
-    ) -> StopOnError<S, F>
-    where
-        S: Stream,
-        F: FnMut(&S::Error) -> bool,
-    {
-        StopOnError { stream, f }
+    #[test]
+    fn decodes_multiplexed_frames() {
+        let bytes = b"\x01\0\0\0\0\0\0\x03out\x02\0\0\0\0\0\0\x03err";
+        assert_eq!(
+            vec![
+                ("stdout".to_owned(), "out".to_owned()),
+                ("stderr".to_owned(), "err".to_owned())
+            ],
+            decode_all(bytes).unwrap()
+        );
     }
 
-    impl<S, F> Stream for StopOnError<S, F>
-    where
-        S: Stream,
-        F: FnMut(&S::Error) -> bool,
-    {
-        type Item = S::Item;
-        type Error = S::Error;
-
-        fn poll(&mut self) -> Result<Async<Option<S::Item>>, S::Error> {
-            match self.stream.poll() {
-                Err(e) => {
-                    if (self.f)(&e) {
-                        Err(e)
-                    } else {
-                        Ok(Async::Ready(None))
-                    }
-                }
-                a => a,
-            }
+    #[test]
+    fn decodes_raw_tty_output() {
+        assert_eq!(
+            vec![("stdout".to_owned(), "$ ls\r\n".to_owned())],
+            decode_all(b"$ ls\r\n").unwrap()
+        );
+        assert_eq!(
+            vec![("stdout".to_owned(), "\x01ab".to_owned())],
+            decode_all(b"\x01ab").unwrap()
+        );
+    }
+
+    #[test]
+    fn oversized_frames_can_be_truncated() {
+        let bytes = &b"\x01\0\0\0\0\0\0\x06abcdef\x02\0\0\0\0\0\0\x02gh"[..];
+        let mut decoder = TtyDecoder::with_limits(StreamLimits::default().max_frame_length(4));
+        match decoder.decode(&mut BytesMut::from(bytes)) {
+            Err(Error::TooLong(4)) => (),
+            other => panic!("unexpected result {:?}", other.map(|c| c.map(|c| c.data))),
+        }
+
+        let mut decoder =
+            TtyDecoder::with_limits(StreamLimits::default().max_frame_length(4).truncate(true));
+        let mut src = BytesMut::from(bytes);
+        let mut chunks = Vec::new();
+        while let Some(chunk) = decoder.decode_eof(&mut src).unwrap() {
+            chunks.push(chunk.as_string_lossy());
+        }
+        assert_eq!(vec!["abcd", "gh"], chunks);
+    }
+
+    #[test]
+    fn unknown_stream_types_are_errors() {
+        let mut decoder = TtyDecoder::new();
+        let mut src = BytesMut::from(&b"\x01\0\0\0\0\0\0\x01a\x07\0\0\0\0\0\0\x01a"[..]);
+        assert!(decoder.decode(&mut src).unwrap().is_some());
+        match decoder.decode(&mut src) {
+            Err(Error::InvalidStreamType(7)) => (),
+            other => panic!("unexpected result {:?}", other.map(|c| c.map(|c| c.data))),
+        }
+    }
+
+    #[test]
+    fn copies_chunks_to_their_streams() {
+        let chunk = |stream_type, data: &'static str| Chunk {
+            stream_type,
+            data: Bytes::from_static(data.as_bytes()),
+        };
+        let chunks = stream::iter_ok::<_, Error>(vec![
+            chunk(StreamType::StdOut, "out "),
+            chunk(StreamType::StdErr, "err"),
+            chunk(StreamType::StdOut, "put"),
+        ]);
+        let (stdout, stderr) = copy_to(chunks, Vec::new(), Vec::new()).wait().unwrap();
+        assert_eq!(b"out put", &stdout[..]);
+        assert_eq!(b"err", &stderr[..]);
+    }
+
+    #[test]
+    fn splits_details_from_log_lines() {
+        let chunk = Chunk {
+            stream_type: StreamType::StdOut,
+            data: Bytes::from_static(
+                b"com.docker.swarm.node.id=n1,com.docker.swarm.task.id=t%2F1 hello world\n",
+            ),
+        };
+        let (details, line) = chunk.details().unwrap();
+        assert_eq!(Some("t/1"), details.task_id());
+        assert_eq!(Some("n1"), details.node_id());
+        assert_eq!(None, details.service_id());
+        assert_eq!(&b"hello world\n"[..], &line[..]);
+
+        let chunk = Chunk {
+            stream_type: StreamType::StdOut,
+            data: Bytes::from_static(b" no attributes"),
+        };
+        let (details, line) = chunk.details().unwrap();
+        assert!(details.attributes.is_empty());
+        assert_eq!(&b"no attributes"[..], &line[..]);
+
+        let chunk = Chunk {
+            stream_type: StreamType::StdOut,
+            data: Bytes::from_static(b"plain output"),
+        };
+        assert!(chunk.details().is_none());
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn attached_streams_implement_futures_io() {
+        fn assert_futures_io<T>()
+        where
+            T: futures_util::stream::Stream<Item = Result<super::Chunk, Error>>
+                + futures_util::io::AsyncWrite,
+        {
         }
+        assert_futures_io::<futures_util::compat::Compat01As03<super::Multiplexed>>();
     }
 }
@@ -0,0 +1,139 @@
+//! Docker remote API versions
+
+use crate::{Error, Result};
+use std::{fmt, str::FromStr};
+
+/// A version of the docker remote API, such as `1.41`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    major: u32,
+    minor: u32,
+}
+
+impl ApiVersion {
+    pub fn new(
+        major: u32,
+        minor: u32,
+    ) -> ApiVersion {
+        ApiVersion { major, minor }
+    }
+
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+
+    /// Prefixes an endpoint with this version, e.g. `/containers/json`
+    /// becomes `/v1.41/containers/json`
+    pub(crate) fn prefix(
+        &self,
+        endpoint: &str,
+    ) -> String {
+        format!("/v{}{}", self, endpoint)
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = Error;
+
+    /// Parses versions in the form `1.41` or `v1.41`
+    fn from_str(s: &str) -> Result<ApiVersion> {
+        let invalid = || Error::InvalidApiVersion(s.to_owned());
+        let version = s.trim();
+        let version = version.trim_start_matches('v');
+        let mut parts = version.splitn(2, '.');
+        let major = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let minor = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        Ok(ApiVersion::new(major, minor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiVersion;
+    use crate::{testing::MockTransport, Docker, Error};
+    use hyper::{Method, StatusCode};
+    use tokio::runtime::current_thread::block_on_all;
+
+    fn daemon() -> MockTransport {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping").respond(StatusCode::OK, "OK");
+        mock.on(Method::GET, "/version").respond(
+            StatusCode::OK,
+            r#"{"ApiVersion":"1.40","MinAPIVersion":"1.12","Version":"19.03.8"}"#,
+        );
+        mock
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(ApiVersion::new(1, 41), "1.41".parse().unwrap());
+        assert_eq!(ApiVersion::new(1, 41), "v1.41".parse().unwrap());
+        assert!("1".parse::<ApiVersion>().is_err());
+        assert!("one.two".parse::<ApiVersion>().is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(ApiVersion::new(1, 9) < ApiVersion::new(1, 41));
+        assert!(ApiVersion::new(2, 0) > ApiVersion::new(1, 41));
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(
+            "/v1.41/containers/json",
+            ApiVersion::new(1, 41).prefix("/containers/json")
+        );
+    }
+
+    #[test]
+    fn negotiation_lowers_versions_newer_than_the_daemons() {
+        let docker = Docker::from_transport(daemon()).with_api_version(ApiVersion::new(1, 41));
+        let docker = block_on_all(docker.negotiate()).unwrap();
+        assert_eq!(Some(ApiVersion::new(1, 40)), docker.api_version());
+
+        let docker = Docker::from_transport(daemon()).with_api_version(ApiVersion::new(1, 25));
+        let docker = block_on_all(docker.negotiate()).unwrap();
+        assert_eq!(Some(ApiVersion::new(1, 25)), docker.api_version());
+
+        let docker = block_on_all(Docker::from_transport(daemon()).negotiate()).unwrap();
+        assert_eq!(None, docker.api_version());
+    }
+
+    #[test]
+    fn negotiation_rejects_versions_older_than_the_daemon_accepts() {
+        let mock = daemon();
+        let docker = Docker::from_transport(mock.clone()).with_api_version(ApiVersion::new(1, 11));
+        match block_on_all(docker.negotiate()) {
+            Err(Error::InvalidApiVersion(_)) => (),
+            other => panic!("unexpected result {:?}", other.map(|d| d.api_version())),
+        }
+        // the daemon is probed without the pinned version, which it would reject
+        assert_eq!(vec!["GET /_ping", "GET /version"], mock.requests());
+    }
+
+    #[test]
+    fn negotiation_fails_when_the_daemon_is_unreachable() {
+        let docker = Docker::from_transport(MockTransport::new());
+        assert!(block_on_all(docker.negotiate()).is_err());
+    }
+}